@@ -64,6 +64,37 @@ fn main() {
             String::from_utf8_lossy(&gcc_output.stderr)
         )
     }
+    let gcc_output = Command::new("gcc")
+        .arg("-Wextra")
+        .arg("-DGC_STATS")
+        .arg("-c")
+        .arg("runtime/libarena.c")
+        .arg("-o")
+        .arg("libarena_gc_stats.o")
+        .output()
+        .expect("Failed to execute gcc");
+    if !gcc_output.status.success() {
+        panic!(
+            "gcc failed with error code {:?}. Output:\n{}",
+            gcc_output.status.code(),
+            String::from_utf8_lossy(&gcc_output.stderr)
+        )
+    }
+    let gcc_output = Command::new("gcc")
+        .arg("-Wextra")
+        .arg("-c")
+        .arg("runtime/gc_stats.c")
+        .arg("-o")
+        .arg("gc_stats.o")
+        .output()
+        .expect("Failed to execute gcc");
+    if !gcc_output.status.success() {
+        panic!(
+            "gcc failed with error code {:?}. Output:\n{}",
+            gcc_output.status.code(),
+            String::from_utf8_lossy(&gcc_output.stderr)
+        )
+    }
     let ar_output = Command::new("ar")
         .arg("rc")
         .arg("libarena.a")
@@ -106,6 +137,21 @@ fn main() {
             String::from_utf8_lossy(&ar_output.stderr)
         )
     }
+    let ar_output = Command::new("ar")
+        .arg("rc")
+        .arg("libarena_gc_stats.a")
+        .arg("libarena_gc_stats.o")
+        .arg("profiling.o")
+        .arg("gc_stats.o")
+        .output()
+        .expect("Failed to execute ar");
+    if !ar_output.status.success() {
+        panic!(
+            "ar failed with error code {:?}. Output:\n{}",
+            ar_output.status.code(),
+            String::from_utf8_lossy(&ar_output.stderr)
+        )
+    }
     let rm_output = Command::new("rm")
         .arg("libarena.o")
         .output()
@@ -150,4 +196,26 @@ fn main() {
             String::from_utf8_lossy(&rm_output.stderr)
         )
     }
+    let rm_output = Command::new("rm")
+        .arg("libarena_gc_stats.o")
+        .output()
+        .expect("Failed to execute rm");
+    if !rm_output.status.success() {
+        panic!(
+            "rm failed with error code {:?}. Output:\n{}",
+            rm_output.status.code(),
+            String::from_utf8_lossy(&rm_output.stderr)
+        )
+    }
+    let rm_output = Command::new("rm")
+        .arg("gc_stats.o")
+        .output()
+        .expect("Failed to execute rm");
+    if !rm_output.status.success() {
+        panic!(
+            "rm failed with error code {:?}. Output:\n{}",
+            rm_output.status.code(),
+            String::from_utf8_lossy(&rm_output.stderr)
+        )
+    }
 }
@@ -0,0 +1,238 @@
+#include <stdint.h>
+#include <stdio.h>
+#include <string.h>
+#include <time.h>
+#include <malloc.h>
+#include "profiling.h"
+#include "gc_stats.h"
+
+// **********************
+// ****** HISTOGRAM ******
+// **********************
+
+// HDR-style histogram: values below SUB_BUCKET_COUNT are stored exactly, every
+// larger power of two is split into SUB_BUCKET_COUNT / 2 linear sub buckets.
+// This keeps the relative error below 1 / (SUB_BUCKET_COUNT / 2) ~ 3%.
+#define SUB_BUCKET_BITS 6 // => SUB_BUCKET_COUNT := 64
+#define SUB_BUCKET_COUNT (1 << SUB_BUCKET_BITS)
+#define SUB_BUCKET_HALF (SUB_BUCKET_COUNT / 2)
+#define HISTOGRAM_LEN ((64 - SUB_BUCKET_BITS + 2) * SUB_BUCKET_HALF)
+
+#define EVENT_TYPE_COUNT 5
+
+typedef struct {
+    uint64_t counts[HISTOGRAM_LEN];
+    uint64_t count;
+    uint64_t total;
+    uint64_t min;
+    uint64_t max;
+} histogram;
+
+uint32_t histogram_index(uint64_t value) {
+    if (value < SUB_BUCKET_COUNT) {
+        return (uint32_t)value;
+    }
+    uint32_t msb = 63 - __builtin_clzll(value);
+    uint32_t shift = msb - SUB_BUCKET_BITS + 1;
+    uint32_t sub_bucket = (uint32_t)(value >> shift); // in [SUB_BUCKET_HALF, SUB_BUCKET_COUNT)
+    return (shift + 1) * SUB_BUCKET_HALF + (sub_bucket - SUB_BUCKET_HALF);
+}
+
+// Highest value that falls into the bucket at index
+uint64_t histogram_value(uint32_t index) {
+    if (index < SUB_BUCKET_COUNT) {
+        return index;
+    }
+    uint32_t shift = index / SUB_BUCKET_HALF - 1;
+    uint64_t sub_bucket = index % SUB_BUCKET_HALF + SUB_BUCKET_HALF;
+    return (sub_bucket << shift) + (((uint64_t)1) << shift) - 1;
+}
+
+void histogram_record(histogram *h, uint64_t value) {
+    h->counts[histogram_index(value)] += 1;
+    if (h->count == 0 || value < h->min) {
+        h->min = value;
+    }
+    if (value > h->max) {
+        h->max = value;
+    }
+    h->count += 1;
+    h->total += value;
+}
+
+uint64_t histogram_percentile(histogram *h, double percentile) {
+    if (h->count == 0) {
+        return 0;
+    }
+    uint64_t rank = (uint64_t)(percentile / 100.0 * h->count + 0.5);
+    if (rank == 0) {
+        rank = 1;
+    }
+    uint64_t seen = 0;
+    for (uint32_t i = 0; i < HISTOGRAM_LEN; i++) {
+        seen += h->counts[i];
+        if (seen >= rank) {
+            uint64_t value = histogram_value(i);
+            return value < h->max ? value : h->max;
+        }
+    }
+    return h->max;
+}
+
+// *********************
+// ****** GC STATS ******
+// *********************
+
+const char *EVENT_NAMES[EVENT_TYPE_COUNT] = {
+    "type_alloc",
+    "type_free",
+    "ptr_access",
+    "ptr_drop",
+    "tgc",
+};
+
+uint64_t stats_format;
+histogram event_histograms[EVENT_TYPE_COUNT];
+// Outermost events only: nested events (e.g. recursive ARC frees) are part of
+// the pause of the event that triggered them
+histogram pause_histogram;
+uint32_t event_depth = 0;
+uint64_t stats_allocated_objects = 0;
+uint64_t stats_allocated_bytes = 0;
+uint64_t stats_currently_allocated_bytes = 0;
+uint64_t stats_max_allocated_bytes = 0;
+// Sums sampled at every allocation to compute averages
+uint64_t stats_allocated_bytes_sum = 0;
+uint64_t stats_stack_offset_sum = 0;
+
+// Defined in profiling.c
+uint64_t get_stack_len(void *sp, uint64_t segment_len);
+
+uint64_t gc_stats_now() {
+    struct timespec ts;
+    clock_gettime(CLOCK_MONOTONIC, &ts);
+    return ((uint64_t)ts.tv_sec) * 1000000000 + ts.tv_nsec;
+}
+
+void gc_stats_init(uint64_t format) {
+    stats_format = format;
+    memset(event_histograms, 0, sizeof(event_histograms));
+    memset(&pause_histogram, 0, sizeof(pause_histogram));
+}
+
+void gc_stats_alloc_bytes(uint64_t len, void *sp, int segment_len_bits) {
+    stats_allocated_objects += 1;
+    stats_allocated_bytes += len;
+    stats_currently_allocated_bytes += len;
+    if (stats_currently_allocated_bytes > stats_max_allocated_bytes) {
+        stats_max_allocated_bytes = stats_currently_allocated_bytes;
+    }
+    stats_allocated_bytes_sum += stats_currently_allocated_bytes;
+    uint64_t segment_len = (1 << segment_len_bits) * sizeof(void*);
+    stats_stack_offset_sum += get_stack_len(sp, segment_len);
+}
+
+void gc_stats_alloc_ptr(void *ptr, void *sp, int segment_len_bits) {
+    gc_stats_alloc_bytes(malloc_usable_size(ptr), sp, segment_len_bits);
+}
+
+uint64_t stats_average(uint64_t sum) {
+    return stats_allocated_objects == 0 ? 0 : sum / stats_allocated_objects;
+}
+
+void gc_stats_free_bytes(uint64_t len) {
+    stats_currently_allocated_bytes -= len;
+}
+
+void gc_stats_free_ptr(void *ptr) {
+    gc_stats_free_bytes(malloc_usable_size(ptr));
+}
+
+uint64_t gc_stats_event_start() {
+    event_depth += 1;
+    return gc_stats_now();
+}
+
+void gc_stats_event_end(uint64_t type, uint64_t start) {
+    uint64_t duration = gc_stats_now() - start;
+    histogram_record(&event_histograms[type], duration);
+    event_depth -= 1;
+    if (event_depth == 0) {
+        histogram_record(&pause_histogram, duration);
+    }
+}
+
+void print_histogram_text(const char *name, histogram *h) {
+    fprintf(stderr, "%-12s %12ld %14ld %10ld %10ld %10ld %10ld %10ld %10ld\n",
+            name,
+            h->count,
+            h->total,
+            h->count == 0 ? 0 : h->total / h->count,
+            histogram_percentile(h, 50.0),
+            histogram_percentile(h, 90.0),
+            histogram_percentile(h, 99.0),
+            histogram_percentile(h, 99.9),
+            h->max);
+}
+
+void print_histogram_json(const char *name, histogram *h) {
+    fprintf(stderr, "\"%s\": {\"count\": %ld, \"total_ns\": %ld, \"mean_ns\": %ld, \"p50_ns\": %ld, \"p90_ns\": %ld, \"p99_ns\": %ld, \"p999_ns\": %ld, \"max_ns\": %ld}",
+            name,
+            h->count,
+            h->total,
+            h->count == 0 ? 0 : h->total / h->count,
+            histogram_percentile(h, 50.0),
+            histogram_percentile(h, 90.0),
+            histogram_percentile(h, 99.0),
+            histogram_percentile(h, 99.9),
+            h->max);
+}
+
+void gc_stats_report() {
+    if (stats_format == GC_STATS_FORMAT_TEXT) {
+        fprintf(stderr, "\n==== GC STATISTICS ====\n");
+        fprintf(stderr, "%-12s %12s %14s %10s %10s %10s %10s %10s %10s\n",
+                "event", "count", "total_ns", "mean_ns", "p50_ns", "p90_ns", "p99_ns", "p99.9_ns", "max_ns");
+        for (uint32_t type = 0; type < EVENT_TYPE_COUNT; type++) {
+            if (event_histograms[type].count != 0) {
+                print_histogram_text(EVENT_NAMES[type], &event_histograms[type]);
+            }
+        }
+        print_histogram_text("pause", &pause_histogram);
+        fprintf(stderr, "collections:         %ld\n", event_histograms[TGC].count);
+        fprintf(stderr, "total gc time (ns):  %ld\n", pause_histogram.total);
+        fprintf(stderr, "max pause (ns):      %ld\n", pause_histogram.max);
+        fprintf(stderr, "p99 pause (ns):      %ld\n", histogram_percentile(&pause_histogram, 99.0));
+        fprintf(stderr, "allocated objects:   %ld\n", stats_allocated_objects);
+        fprintf(stderr, "allocated bytes:     %ld\n", stats_allocated_bytes);
+        fprintf(stderr, "max allocated bytes: %ld\n", stats_max_allocated_bytes);
+        fprintf(stderr, "avg allocated bytes: %ld\n", stats_average(stats_allocated_bytes_sum));
+        fprintf(stderr, "avg stack offset:    %ld\n", stats_average(stats_stack_offset_sum));
+    } else if (stats_format == GC_STATS_FORMAT_JSON) {
+        fprintf(stderr, "{\"events\": {");
+        uint32_t printed = 0;
+        for (uint32_t type = 0; type < EVENT_TYPE_COUNT; type++) {
+            if (event_histograms[type].count != 0) {
+                if (printed != 0) {
+                    fprintf(stderr, ", ");
+                }
+                print_histogram_json(EVENT_NAMES[type], &event_histograms[type]);
+                printed += 1;
+            }
+        }
+        fprintf(stderr, "}, ");
+        print_histogram_json("pause", &pause_histogram);
+        fprintf(stderr, ", \"collections\": %ld, \"total_gc_time_ns\": %ld, \"max_pause_ns\": %ld, \"p99_pause_ns\": %ld",
+                event_histograms[TGC].count,
+                pause_histogram.total,
+                pause_histogram.max,
+                histogram_percentile(&pause_histogram, 99.0));
+        fprintf(stderr, ", \"total_allocated_objects\": %ld, \"total_allocated_bytes\": %ld, \"max_allocated_bytes\": %ld",
+                stats_allocated_objects,
+                stats_allocated_bytes,
+                stats_max_allocated_bytes);
+        fprintf(stderr, ", \"average_allocated_bytes\": %ld, \"average_stack_offset\": %ld}\n",
+                stats_average(stats_allocated_bytes_sum),
+                stats_average(stats_stack_offset_sum));
+    }
+}
@@ -0,0 +1,38 @@
+#include <stdint.h>
+
+// *********************
+// ****** GC STATS ******
+// *********************
+
+#ifdef GC_STATS
+#define GC_STATS_INIT(format)               gc_stats_init(format)
+#define GC_STATS_ALLOC_PTR(ptr, sp, segment_len_bits)   gc_stats_alloc_ptr(ptr, sp, segment_len_bits)
+#define GC_STATS_ALLOC_BYTES(len, sp, segment_len_bits) gc_stats_alloc_bytes(len, sp, segment_len_bits)
+#define GC_STATS_FREE_PTR(ptr)              gc_stats_free_ptr(ptr)
+#define GC_STATS_FREE_BYTES(len)            gc_stats_free_bytes(len)
+#define GC_STATS_EVENT_START(start)         uint64_t start = gc_stats_event_start()
+#define GC_STATS_EVENT_END(type, start)     gc_stats_event_end(type, start)
+#define GC_STATS_REPORT()                   gc_stats_report()
+#else
+#define GC_STATS_INIT(format)
+#define GC_STATS_ALLOC_PTR(ptr, sp, segment_len_bits)
+#define GC_STATS_ALLOC_BYTES(len, sp, segment_len_bits)
+#define GC_STATS_FREE_PTR(ptr)
+#define GC_STATS_FREE_BYTES(len)
+#define GC_STATS_EVENT_START(start)
+#define GC_STATS_EVENT_END(type, start)
+#define GC_STATS_REPORT()
+#endif
+
+#define GC_STATS_FORMAT_NONE 0
+#define GC_STATS_FORMAT_TEXT 1
+#define GC_STATS_FORMAT_JSON 2
+
+void gc_stats_init(uint64_t format);
+void gc_stats_alloc_ptr(void *ptr, void *sp, int segment_len_bits);
+void gc_stats_alloc_bytes(uint64_t len, void *sp, int segment_len_bits);
+void gc_stats_free_ptr(void *ptr);
+void gc_stats_free_bytes(uint64_t len);
+uint64_t gc_stats_event_start();
+void gc_stats_event_end(uint64_t type, uint64_t start);
+void gc_stats_report();
@@ -1,8 +1,11 @@
+#include <errno.h>
+#include <stdarg.h>
 #include <stdint.h>
 #include <stdio.h>
 #include <stdlib.h>
 #include <string.h>
 #include "profiling.h"
+#include "gc_stats.h"
 
 // *******************
 // ****** STACK ******
@@ -10,6 +13,8 @@
 
 #define SEGMENT_LEN_BITS 10 // => SEGMENT_LEN := 1024 pointers
 
+void arena_panic(char *format, ...);
+
 uint64_t PROFILING_FREQUENCY;
 
 void *alloc_new_segment(void *previous_segment) {
@@ -51,28 +56,52 @@ void close_stack() {
 // ****** SPILL / ARC ******
 // *************************
 
-void init_heap() {
+// An object starts with the GC header and its case id, whose upper 16 bits
+// count the pointers following it. Objects with more pointers, like arrays,
+// store LONG_POINTER_COUNT there and the count in the next 64 bits.
+#define LONG_POINTER_COUNT 0xFFFF
+
+// Returns the number of pointers of the object and sets *pointers to the first
+// one
+uint64_t object_pointers(void *obj, void ***pointers) {
+    uint32_t *header = (uint32_t *)obj;
+    uint64_t pointer_count = *(header + 1) >> 16;
+    if (pointer_count == LONG_POINTER_COUNT) {
+        *pointers = (void **)(header + 4);
+        return *((uint64_t *)(header + 2));
+    }
+    *pointers = (void **)(header + 2);
+    return pointer_count;
+}
+
+void init_heap(uint64_t gc_stats_format) {
     INIT_HEAP_PROFILING();
+    GC_STATS_INIT(gc_stats_format);
 }
 
 void *type_alloc(uint64_t size, void *sp) {
     HEAP_EVENT_START_PROFILING();
+    GC_STATS_EVENT_START(stats_start);
 
     void* ptr = malloc(size);
 
     HEAP_ALLOC_PTR_PROFILING(ptr);
+    GC_STATS_ALLOC_PTR(ptr, sp, SEGMENT_LEN_BITS);
+    GC_STATS_EVENT_END(TYPE_ALLOC, stats_start);
     HEAP_EVENT_END_PROFILING(TYPE_ALLOC, sp, SEGMENT_LEN_BITS, PROFILING_FREQUENCY);
     return ptr;
 }
 
 void type_free(void *ptr, void *sp) {
     HEAP_FREE_PTR_PROFILING(ptr);
+    GC_STATS_FREE_PTR(ptr);
 
     free(ptr);
 }
 
 void close_heap() {
     CLOSE_HEAP_PROFILING();
+    GC_STATS_REPORT();
 }
 
 // *****************
@@ -89,16 +118,18 @@ void arc_ptr_access(void *ptr, void *sp) {
     */
 
     HEAP_EVENT_START_PROFILING();
+    GC_STATS_EVENT_START(stats_start);
     uint32_t *header = (uint32_t *)ptr;
     *header = *header + 1;
+    GC_STATS_EVENT_END(PTR_ACCESS, stats_start);
     HEAP_EVENT_END_PROFILING(PTR_ACCESS, sp, SEGMENT_LEN_BITS, PROFILING_FREQUENCY);
 }
 
 void arc_free_obj(void *ptr, void *sp) {
-    uint32_t *header = (uint32_t *)ptr;
-    uint32_t pointer_count = *(header + 1) >> 16;
-    for (uint32_t offset = 0; offset < pointer_count; offset++) {
-        void* obj_ptr = *(((void**)(header + 2)) + offset);
+    void **pointers;
+    uint64_t pointer_count = object_pointers(ptr, &pointers);
+    for (uint64_t offset = 0; offset < pointer_count; offset++) {
+        void* obj_ptr = *(pointers + offset);
         if (obj_ptr != NULL) {
             arc_drop_ptr(obj_ptr, sp);
         }
@@ -117,15 +148,19 @@ void arc_drop_ptr(void *ptr, void *sp) {
     */
 
     HEAP_EVENT_START_PROFILING();
+    GC_STATS_EVENT_START(stats_drop_start);
     uint32_t *header = (uint32_t *)ptr;
     *header = *header - 1;
+    GC_STATS_EVENT_END(PTR_DROP, stats_drop_start);
     HEAP_EVENT_END_PROFILING(PTR_DROP, sp, SEGMENT_LEN_BITS, PROFILING_FREQUENCY);
 
     if (*header == 0) {
         HEAP_EVENT_START_PROFILING();
+        GC_STATS_EVENT_START(stats_free_start);
 
         arc_free_obj(ptr, sp);
 
+        GC_STATS_EVENT_END(TYPE_FREE, stats_free_start);
         HEAP_EVENT_END_PROFILING(TYPE_FREE, sp, SEGMENT_LEN_BITS, PROFILING_FREQUENCY);
     }
 }
@@ -136,16 +171,19 @@ void arc_drop_ptr(void *ptr, void *sp) {
 
 #define NURSERY_LEN_BITS 15 // => NURSERY_LEN := 32768 bytes
 
+// The nursery grows if the live objects and a new one do not fit into it
+uint64_t nursery_len;
 void *nursery_active;
 void *nursery_copy;
 void *nursery_active_end;
 void *nursery_copy_end;
 void *nursery_pointer;
 
-void tgc_init_heap() {
+void tgc_init_heap(uint64_t gc_stats_format) {
     INIT_HEAP_PROFILING();
+    GC_STATS_INIT(gc_stats_format);
 
-    uint32_t nursery_len = (1 << NURSERY_LEN_BITS);
+    nursery_len = (1 << NURSERY_LEN_BITS);
     nursery_active = malloc(nursery_len);
     nursery_copy = malloc(nursery_len);
     nursery_active_end= nursery_active + nursery_len;
@@ -159,31 +197,32 @@ void *copy_object(void *obj) {
     if (*((uint32_t*)obj) != 0xFFFFFFFF) {
         void *ptr = nursery_pointer;
         // Copy object to new heap
-        uint32_t obj_len = *((uint32_t*)obj);
+        uint64_t obj_len = *((uint32_t*)obj);
         uint64_t padded_obj_len = ((obj_len + 7) / 8) * 8;
         memcpy(ptr, obj, padded_obj_len);
         nursery_pointer = nursery_pointer + padded_obj_len;
 
-        // Overwrite current location with forwarding pointer
+        // Overwrite current location with forwarding pointer. Objects are
+        // aligned to 8 bytes, so the offset is stored in units of 8 bytes.
         *((uint32_t*)obj) = 0xFFFFFFFF;
-        uint32_t offset = (uint32_t)(ptr - nursery_copy);
+        uint32_t offset = (uint32_t)((ptr - nursery_copy) / 8);
         *((uint32_t*)(obj + sizeof(uint32_t))) = offset;
 
 
         // Recursive call to pointers in object
-        uint32_t obj_header = *((uint32_t*)(ptr + sizeof(uint32_t)));
-        uint32_t rec_count = obj_header >> 16;
-        for (uint32_t pointer = 0; pointer < rec_count; pointer++) {
-            void* obj_ptr = ptr + 2 * sizeof(uint32_t) + pointer * sizeof(void*);
-            if (*((void**)obj_ptr) != NULL) {
-                *((void**)obj_ptr) = copy_object(*((void**)obj_ptr));
+        void **pointers;
+        uint64_t rec_count = object_pointers(ptr, &pointers);
+        for (uint64_t pointer = 0; pointer < rec_count; pointer++) {
+            void **obj_ptr = pointers + pointer;
+            if (*obj_ptr != NULL) {
+                *obj_ptr = copy_object(*obj_ptr);
             }
         }
 
         return ptr;
     } else {
         // obj was already moved. return address stored.
-        return nursery_copy + *((uint32_t*)(obj + sizeof(uint32_t)));
+        return nursery_copy + (uint64_t)*((uint32_t*)(obj + sizeof(uint32_t))) * 8;
     }
 }
 
@@ -226,25 +265,308 @@ void tgc_close_heap() {
         printf("NURSERY IS NOT EMPTY\n");
     }
     CLOSE_HEAP_PROFILING();
+    GC_STATS_REPORT();
+}
+
+// Collects the nursery, recorded in the GC stats and heap profile
+void tgc_recorded_garbage_collection(void *sp) {
+    HEAP_EVENT_START_PROFILING();
+    GC_STATS_EVENT_START(stats_tgc_start);
+
+    uint64_t bytes_freed = tgc_garbage_collection(sp);
+
+    HEAP_FREE_BYTES_PROFILING(bytes_freed);
+    GC_STATS_FREE_BYTES(bytes_freed);
+    GC_STATS_EVENT_END(TGC, stats_tgc_start);
+    HEAP_EVENT_END_PROFILING(TGC, sp, SEGMENT_LEN_BITS, PROFILING_FREQUENCY);
+}
+
+// Doubles the nursery until the live objects and len more bytes fit into it.
+// The live objects are copied into the new nursery by another collection.
+void tgc_grow_nursery(uint64_t len, void *sp) {
+    uint64_t live_len = nursery_pointer - nursery_active;
+    while (live_len + len >= nursery_len) {
+        nursery_len *= 2;
+    }
+    free(nursery_copy);
+    nursery_copy = malloc(nursery_len);
+    nursery_copy_end = nursery_copy + nursery_len;
+    tgc_recorded_garbage_collection(sp);
+    free(nursery_copy);
+    nursery_copy = malloc(nursery_len);
+    nursery_copy_end = nursery_copy + nursery_len;
 }
 
 void *tgc_type_alloc(uint64_t size, void *sp) {
+    // The header stores the size in 32 bits
+    if (size > UINT32_MAX) {
+        arena_panic("an object of %lu bytes is too large", (unsigned long)size);
+    }
     uint64_t padded_len = ((size + 7) / 8) * 8;
     if (nursery_pointer + padded_len >= nursery_active_end) {
-        HEAP_EVENT_START_PROFILING();
-
-        uint64_t bytes_freed = tgc_garbage_collection(sp);
-
-        HEAP_FREE_BYTES_PROFILING(bytes_freed);
-        HEAP_EVENT_END_PROFILING(TGC, sp, SEGMENT_LEN_BITS, PROFILING_FREQUENCY);
+        tgc_recorded_garbage_collection(sp);
+        if (nursery_pointer + padded_len >= nursery_active_end) {
+            tgc_grow_nursery(padded_len, sp);
+        }
     }
 
     HEAP_EVENT_START_PROFILING();
+    GC_STATS_EVENT_START(stats_alloc_start);
 
     void *ret = nursery_pointer;
     nursery_pointer = nursery_pointer + padded_len;
 
     HEAP_ALLOC_BYTES_PROFILING(padded_len);
+    GC_STATS_ALLOC_BYTES(padded_len, sp, SEGMENT_LEN_BITS);
+    GC_STATS_EVENT_END(TYPE_ALLOC, stats_alloc_start);
     HEAP_EVENT_END_PROFILING(TYPE_ALLOC, sp, SEGMENT_LEN_BITS, PROFILING_FREQUENCY);
     return ret;
 }
+
+// *********************
+// ****** STRINGS ******
+// *********************
+
+// Strings are allocated by the generated code, these functions only work on
+// their bytes, which are not terminated by NUL.
+
+// Returns a negative number if a is smaller than b, 0 if they are equal and
+// a positive number otherwise
+int32_t string_compare(char *a, int32_t a_len, char *b, int32_t b_len) {
+    int32_t len = a_len < b_len ? a_len : b_len;
+    int cmp = memcmp(a, b, len);
+    if (cmp != 0) {
+        return cmp;
+    }
+    return a_len - b_len;
+}
+
+// Returns the index of the first occurrence of pattern or -1
+int32_t string_find(char *s, int32_t s_len, char *pattern, int32_t pattern_len) {
+    for (int32_t i = 0; i + pattern_len <= s_len; i++) {
+        if (memcmp(s + i, pattern, pattern_len) == 0) {
+            return i;
+        }
+    }
+    return -1;
+}
+
+// Parses an optional minus followed by digits and stops at the first other
+// character. Overflows wrap around.
+int32_t string_to_i32(char *s, int32_t len) {
+    int32_t i = 0;
+    int negative = len > 0 && s[0] == '-';
+    if (negative) {
+        i = 1;
+    }
+    uint32_t res = 0;
+    for (; i < len && s[i] >= '0' && s[i] <= '9'; i++) {
+        res = res * 10 + (s[i] - '0');
+    }
+    return negative ? -res : res;
+}
+
+int32_t i32_string_len(int32_t i) {
+    int32_t len = i < 0 ? 2 : 1;
+    while (i <= -10 || i >= 10) {
+        i = i / 10;
+        len++;
+    }
+    return len;
+}
+
+void i32_string_write(char *s, int32_t len, int32_t i) {
+    int32_t first_digit = 0;
+    if (i < 0) {
+        s[0] = '-';
+        first_digit = 1;
+    }
+    // Digits of negative numbers are negative, so INT32_MIN needs no special
+    // case
+    for (int32_t index = len - 1; index >= first_digit; index--) {
+        int32_t digit = i % 10;
+        s[index] = '0' + (digit < 0 ? -digit : digit);
+        i = i / 10;
+    }
+}
+
+// *************************
+// ****** ENVIRONMENT ******
+// *************************
+
+// The command line arguments, saved by main before it calls the main function
+// of the program, which gets them as an array of strings
+int32_t ARG_COUNT;
+char **ARG_VALUES;
+
+void init_args(int32_t argc, char **argv) {
+    ARG_COUNT = argc;
+    ARG_VALUES = argv;
+}
+
+int32_t arg_count() {
+    return ARG_COUNT;
+}
+
+char *arg_at(int32_t i) {
+    return ARG_VALUES[i];
+}
+
+// Returns the value of the environment variable or an empty string if it is
+// not set
+char *env_value(char *name) {
+    char *value = getenv(name);
+    return value == NULL ? "" : value;
+}
+
+// ****************
+// ****** IO ******
+// ****************
+
+// Bytes read by the last operation. The generated code allocates a string of
+// their length and copies them into it with io_take.
+char *PENDING;
+int32_t PENDING_LEN;
+
+// Message of the last failed operation or an empty string if the last
+// operation succeeded
+char IO_ERROR[256];
+
+static int32_t set_pending(char *bytes, size_t len) {
+    PENDING = bytes;
+    PENDING_LEN = len;
+    return PENDING_LEN;
+}
+
+static void set_io_error(char *path) {
+    snprintf(IO_ERROR, sizeof(IO_ERROR), "%s: %s", path, strerror(errno));
+}
+
+// The paths are strings of the program, which are not terminated by NUL
+static char *c_string(char *bytes, int32_t len) {
+    char *s = malloc(len + 1);
+    memcpy(s, bytes, len);
+    s[len] = '\0';
+    return s;
+}
+
+void io_take(char *dst) {
+    if (PENDING_LEN > 0) {
+        memcpy(dst, PENDING, PENDING_LEN);
+    }
+    free(PENDING);
+    set_pending(NULL, 0);
+}
+
+// Reads the next line of stdin including its newline. Returns 0 at the end of
+// the input.
+int32_t io_read_line() {
+    IO_ERROR[0] = '\0';
+    char *line = NULL;
+    size_t capacity = 0;
+    ssize_t len = getline(&line, &capacity, stdin);
+    if (len < 0) {
+        if (ferror(stdin)) {
+            set_io_error("stdin");
+        }
+        free(line);
+        return set_pending(NULL, 0);
+    }
+    return set_pending(line, len);
+}
+
+int32_t io_read_file(char *path_bytes, int32_t path_len) {
+    IO_ERROR[0] = '\0';
+    char *path = c_string(path_bytes, path_len);
+    FILE *file = fopen(path, "rb");
+    if (file == NULL) {
+        set_io_error(path);
+        free(path);
+        return set_pending(NULL, 0);
+    }
+    // Reads in chunks, since the size of pipes is not known in advance
+    size_t capacity = 4096;
+    size_t len = 0;
+    char *content = malloc(capacity);
+    size_t read;
+    while ((read = fread(content + len, 1, capacity - len, file)) > 0) {
+        len += read;
+        if (len == capacity) {
+            capacity *= 2;
+            content = realloc(content, capacity);
+        }
+    }
+    if (ferror(file)) {
+        set_io_error(path);
+        len = 0;
+    } else if (len > INT32_MAX) {
+        snprintf(IO_ERROR, sizeof(IO_ERROR), "%s: File too large", path);
+        len = 0;
+    }
+    fclose(file);
+    free(path);
+    return set_pending(content, len);
+}
+
+void io_write_file(char *path_bytes, int32_t path_len, char *content, int32_t len) {
+    IO_ERROR[0] = '\0';
+    char *path = c_string(path_bytes, path_len);
+    FILE *file = fopen(path, "wb");
+    if (file == NULL) {
+        set_io_error(path);
+        free(path);
+        return;
+    }
+    if (fwrite(content, 1, len, file) != (size_t)len) {
+        set_io_error(path);
+    }
+    if (fclose(file) != 0 && IO_ERROR[0] == '\0') {
+        set_io_error(path);
+    }
+    free(path);
+}
+
+int32_t io_last_error() {
+    size_t len = strlen(IO_ERROR);
+    char *message = malloc(len);
+    memcpy(message, IO_ERROR, len);
+    return set_pending(message, len);
+}
+
+void io_eprint(char *bytes, int32_t len) {
+    fprintf(stderr, "%.*s", len, bytes);
+}
+
+void io_eprint_str(char *s) {
+    fputs(s, stderr);
+}
+
+// ********************
+// ****** CHECKS ******
+// ********************
+
+// Exit code of programs stopped by panic or assert, which tells them apart from
+// failed runtime checks
+#define PANIC_EXIT_CODE 101
+
+// Called by panic and assert with the location of the call
+void user_panic(char *location, char *message, int32_t len) {
+    fprintf(stderr, "%s%.*s\n", location, len, message);
+    exit(PANIC_EXIT_CODE);
+}
+
+void user_panic_str(char *location, char *message) {
+    user_panic(location, message, strlen(message));
+}
+
+// Called by the generated code when a runtime check fails. exit flushes the
+// output the program printed so far.
+void arena_panic(char *format, ...) {
+    va_list args;
+    va_start(args, format);
+    fprintf(stderr, "Runtime error: ");
+    vfprintf(stderr, format, args);
+    fprintf(stderr, "\n");
+    va_end(args);
+    exit(1);
+}
@@ -0,0 +1,271 @@
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::Instant;
+
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+use crate::input::{selected_backends, Backend, BenchArgs};
+
+// Same columns as tools/results_template.csv followed by the metrics that
+// are only available through `arena bench`
+const COLUMNS: [&str; 21] = [
+    "gc",
+    "arc_drop_ptr",
+    "arc_ptr_access",
+    "stack_alloc",
+    "type_alloc",
+    "arc_free_obj",
+    "tgc_garbage_collection",
+    "type_free",
+    "total_time",
+    "total_allocated_objects",
+    "total_allocated_bytes",
+    "average_object_size",
+    "average_currently_allocated_bytes",
+    "max_currently_allocated_bytes",
+    "average_stack_offset",
+    "run",
+    "max_rss_kb",
+    "collections",
+    "total_gc_time_ns",
+    "max_pause_ns",
+    "p99_pause_ns",
+];
+
+struct Measurement {
+    wall_time_ms: f64,
+    max_rss_kb: i64,
+    stderr: String,
+}
+
+struct HeapStats {
+    // Wall time of the run that collected the statistics. The runtime events
+    // are reported relative to it because timing every event slows the
+    // program down.
+    wall_time_ms: f64,
+    stats: serde_json::Value,
+}
+
+pub fn run_benchmark(args: &BenchArgs) {
+    let backends = selected_backends(&args.gc);
+    let out_dir = std::env::temp_dir().join(format!("arena-bench-{}", std::process::id()));
+    fs::create_dir_all(&out_dir).expect("Could not create temporary benchmark folder");
+
+    let mut rows = Vec::new();
+    for backend in backends {
+        let executable = out_dir.join(backend.name());
+        let stats_executable = out_dir.join(format!("{}_stats", backend.name()));
+        compile(args, backend, &executable, &[]);
+        compile(args, backend, &stats_executable, &["--gc-stats=json"]);
+
+        eprintln!("Collecting heap statistics [{}]", backend.name());
+        let stats_run = run(&stats_executable, &args.args);
+        let heap_stats = HeapStats {
+            wall_time_ms: stats_run.wall_time_ms,
+            stats: serde_json::from_str(
+                stats_run
+                    .stderr
+                    .lines()
+                    .last()
+                    .expect("Runtime did not report GC statistics"),
+            )
+            .expect("Invalid GC statistics"),
+        };
+
+        for i in 0..args.runs {
+            eprintln!("Run {}/{} [{}]", i + 1, args.runs, backend.name());
+            let measurement = run(&executable, &args.args);
+            rows.push(build_row(backend, i, &measurement, &heap_stats));
+        }
+    }
+    let _ = fs::remove_dir_all(&out_dir);
+
+    if let Some(path) = &args.json {
+        fs::write(path, to_json(&rows)).expect("Could not write JSON results");
+    }
+    match &args.csv {
+        Some(path) => fs::write(path, to_csv(&rows)).expect("Could not write CSV results"),
+        None => {
+            if args.json.is_none() {
+                print!("{}", to_csv(&rows))
+            }
+        }
+    }
+}
+
+// Builds the program with the compiler flags of the benchmark
+fn compile(args: &BenchArgs, backend: Backend, executable: &Path, extra_args: &[&str]) {
+    let compiler = std::env::current_exe().expect("Could not get executable path");
+    let mut command = Command::new(compiler);
+    command.arg("build").arg(backend.flag()).args(extra_args);
+    if args.unchecked {
+        command.arg("--unchecked");
+    }
+    for folder in &args.include.include_folders {
+        command.arg("-I").arg(folder);
+    }
+    for library in &args.link.libraries {
+        command.arg("-l").arg(library);
+    }
+    for folder in &args.link.library_paths {
+        command.arg("-L").arg(folder);
+    }
+    let compile_output = command
+        .arg(&args.file_path)
+        .arg("-o")
+        .arg(executable)
+        .output()
+        .expect("Failed to execute compiler");
+    if !compile_output.status.success() {
+        panic!(
+            "Compilation for {} failed with error code {:?}. Output:\n{}",
+            backend.name(),
+            compile_output.status.code(),
+            String::from_utf8_lossy(&compile_output.stderr)
+        )
+    }
+}
+
+#[allow(clippy::zombie_processes)]
+fn run(executable: &Path, args: &[String]) -> Measurement {
+    let start = Instant::now();
+    let mut child = Command::new(executable)
+        .args(args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap_or_else(|_| panic!("Failed to execute {}", executable.to_str().unwrap()));
+    let mut stderr = String::new();
+    child
+        .stderr
+        .take()
+        .unwrap()
+        .read_to_string(&mut stderr)
+        .expect("Could not read stderr of benchmark");
+
+    // std::process::Child::wait does not report resource usage, so the
+    // child is reaped with wait4 directly
+    let mut status = 0;
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    let pid = child.id() as libc::pid_t;
+    if unsafe { libc::wait4(pid, &mut status, 0, &mut usage) } != pid {
+        panic!("Could not wait for {}", executable.to_str().unwrap());
+    }
+    let wall_time_ms = start.elapsed().as_secs_f64() * 1000.0;
+    if !libc::WIFEXITED(status) || libc::WEXITSTATUS(status) != 0 {
+        panic!(
+            "{} failed with status {}. StdErr:\n{}",
+            executable.to_str().unwrap(),
+            status,
+            stderr
+        )
+    }
+    Measurement {
+        wall_time_ms,
+        max_rss_kb: usage.ru_maxrss,
+        stderr,
+    }
+}
+
+fn build_row(
+    backend: Backend,
+    run: usize,
+    measurement: &Measurement,
+    heap_stats: &HeapStats,
+) -> Vec<Option<String>> {
+    // Percentage of the run time spent in the runtime event
+    let event_share = |event: &str| {
+        let event_ns = heap_stats
+            .stats
+            .pointer(&format!("/events/{}/total_ns", event))
+            .and_then(serde_json::Value::as_f64)
+            .unwrap_or(0.0);
+        Some(format!(
+            "{:.2}",
+            event_ns / 1e6 / heap_stats.wall_time_ms * 100.0
+        ))
+    };
+    let stat = |key: &str| {
+        heap_stats
+            .stats
+            .get(key)
+            .and_then(serde_json::Value::as_f64)
+            .unwrap_or(0.0)
+    };
+    let objects = stat("total_allocated_objects");
+    let bytes = stat("total_allocated_bytes");
+    vec![
+        Some(backend.name().to_string()),
+        event_share("ptr_drop"),
+        event_share("ptr_access"),
+        // Stack allocations are not timed by the runtime
+        None,
+        event_share("type_alloc"),
+        // arc_free_obj is not timed on its own, type_free includes its
+        // recursive drops
+        None,
+        event_share("tgc"),
+        event_share("type_free"),
+        Some(format!("{:.2}", measurement.wall_time_ms)),
+        Some(format!("{:.2}", objects)),
+        Some(format!("{:.2}", bytes)),
+        Some(format!(
+            "{:.2}",
+            if objects == 0.0 { 0.0 } else { bytes / objects }
+        )),
+        Some(format!("{:.2}", stat("average_allocated_bytes"))),
+        Some(format!("{:.2}", stat("max_allocated_bytes"))),
+        Some(format!("{:.2}", stat("average_stack_offset"))),
+        Some(run.to_string()),
+        Some(measurement.max_rss_kb.to_string()),
+        Some(format!("{}", stat("collections"))),
+        Some(format!("{}", stat("total_gc_time_ns"))),
+        Some(format!("{}", stat("max_pause_ns"))),
+        Some(format!("{}", stat("p99_pause_ns"))),
+    ]
+}
+
+fn to_csv(rows: &[Vec<Option<String>>]) -> String {
+    let mut csv = COLUMNS.join(",");
+    csv.push('\n');
+    for row in rows {
+        csv.push_str(
+            &row.iter()
+                .map(|value| value.clone().unwrap_or_default())
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+        csv.push('\n');
+    }
+    csv
+}
+
+fn to_json(rows: &[Vec<Option<String>>]) -> String {
+    let rows = rows.iter().map(|row| JsonRow(row)).collect::<Vec<_>>();
+    let mut json = serde_json::to_string_pretty(&rows).expect("Could not serialize results");
+    json.push('\n');
+    json
+}
+
+// A row as a JSON object with the fields in the order of the columns
+struct JsonRow<'a>(&'a [Option<String>]);
+
+impl Serialize for JsonRow<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(COLUMNS.len()))?;
+        for (column, value) in COLUMNS.iter().zip(self.0.iter()) {
+            match value {
+                Some(value) if *column != "gc" => {
+                    let number = value
+                        .parse::<serde_json::Number>()
+                        .expect("Benchmark result is not a number");
+                    map.serialize_entry(column, &number)?
+                }
+                value => map.serialize_entry(column, value)?,
+            }
+        }
+        map.end()
+    }
+}
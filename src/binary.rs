@@ -7,6 +7,8 @@ use crate::module::Const;
 pub struct Binary<'input> {
     pub functions: HashMap<Rc<String>, BinFunction<'input>>,
     pub types: HashMap<Rc<String>, BinType<'input>>,
+    // Linked C functions by their symbol
+    pub externs: HashMap<Rc<String>, BinExtern>,
 }
 
 #[derive(Debug)]
@@ -18,6 +20,10 @@ pub struct BinType<'input> {
 pub struct BinTypeCase<'input> {
     pub name: &'input str,
     pub fields: Vec<Rc<String>>,
+    // None for fields without a name
+    pub field_names: Vec<Option<&'input str>>,
+    // Whether other modules than the one of the type can access the fields
+    pub public: bool,
 }
 
 #[derive(Debug)]
@@ -27,6 +33,13 @@ pub struct BinFunction<'input> {
     pub body: TypedExpr<'input>,
 }
 
+// Externs are called without the stack pointer of the arena stack
+#[derive(Debug)]
+pub struct BinExtern {
+    pub args: Vec<Rc<String>>,
+    pub ret_type: Rc<String>,
+}
+
 #[derive(Debug)]
 pub struct BinParamDef<'input> {
     pub name: &'input str,
@@ -41,6 +54,8 @@ pub struct TypedExpr<'input> {
 
 #[derive(Debug)]
 pub enum BinExpr<'input> {
+    // The elements of an array literal
+    Array(Vec<TypedExpr<'input>>),
     Const(Const),
     FuncCall(Rc<String>, Vec<TypedExpr<'input>>),
     GetTypeCaseField(Box<TypedExpr<'input>>, &'input str, usize),
@@ -0,0 +1,402 @@
+// Code generation of the build in arrays. An array is an object on the heap
+// with the GC header, a case id, its length as i64 and its elements. The case
+// id holds the number of pointers in 16 bits, so arrays of objects store
+// LONG_POINTER_COUNT there and the runtime reads their length instead. Arrays
+// are immutable, set and push return a copy. Like functions, the build in
+// functions drop the arrays passed to them.
+
+use llvm_sys as llvm;
+use std::ffi::{CStr, CString};
+use std::rc::Rc;
+
+use crate::codegen::build_in::BuildIn;
+use crate::codegen::check;
+use crate::codegen::expression::get_next_stack_element;
+use crate::codegen::function::create_func_call;
+use crate::codegen::garbage_collection::GC;
+use crate::codegen::CodegenContext;
+use crate::types::{
+    array_element, get_struct_size, type_to_llvm_type, I32_TYPE, I64_TYPE, VOID_PTR_TYPE,
+};
+
+// Pointer count of the case id that makes the runtime read the length, has to
+// match LONG_POINTER_COUNT in runtime/libarena.c
+const LONG_POINTER_COUNT: u64 = 0xFFFF;
+
+pub fn len<Gc: GC>(
+    cc: &CodegenContext,
+    array: *mut llvm::LLVMValue,
+    sp: *mut llvm::LLVMValue,
+) -> *mut llvm::LLVMValue {
+    let len = len_of::<Gc>(cc, array);
+    Gc::type_ptr_drop(cc, array, sp);
+    len
+}
+
+pub fn get<Gc: GC>(
+    cc: &CodegenContext,
+    array: *mut llvm::LLVMValue,
+    index: *mut llvm::LLVMValue,
+    sp: *mut llvm::LLVMValue,
+) -> *mut llvm::LLVMValue {
+    check::index::<Gc>(
+        cc,
+        "get",
+        "an array",
+        index,
+        len_of::<Gc>(cc, array),
+        false,
+        sp,
+    );
+    let name = CString::new("element").unwrap();
+    let element = unsafe {
+        llvm::core::LLVMBuildLoad(
+            cc.builder,
+            element_ptr::<Gc>(cc, array, index),
+            name.as_ptr(),
+        )
+    };
+    if has_objects(&array_type(array)) {
+        Gc::type_ptr_access(cc, element, sp);
+    }
+    Gc::type_ptr_drop(cc, array, sp);
+    element
+}
+
+pub fn set<Gc: GC>(
+    cc: &CodegenContext,
+    array: *mut llvm::LLVMValue,
+    index: *mut llvm::LLVMValue,
+    element: *mut llvm::LLVMValue,
+    sp: *mut llvm::LLVMValue,
+) -> *mut llvm::LLVMValue {
+    let len = len_of::<Gc>(cc, array);
+    check::index::<Gc>(cc, "set", "an array", index, len, false, sp);
+    let array_type = array_type(array);
+    let (array_slot, new_sp) = save::<Gc>(cc, array, &array_type, sp);
+    let (element_slot, new_sp) = save_element::<Gc>(cc, element, &array_type, new_sp);
+    let copy = alloc::<Gc>(cc, &array_type, len, new_sp);
+    let array = load(cc, array_slot);
+    let element = element_slot.map_or(element, |slot| load(cc, slot));
+    copy_elements::<Gc>(cc, copy, array, len, sp);
+    if has_objects(&array_type) {
+        // The copied element is replaced, so the copy does not reference it
+        let name = CString::new("replaced").unwrap();
+        let replaced = unsafe {
+            llvm::core::LLVMBuildLoad(
+                cc.builder,
+                element_ptr::<Gc>(cc, copy, index),
+                name.as_ptr(),
+            )
+        };
+        Gc::type_ptr_drop(cc, replaced, sp);
+    }
+    store::<Gc>(cc, copy, index, element);
+    Gc::type_ptr_drop(cc, array, sp);
+    copy
+}
+
+pub fn push<Gc: GC>(
+    cc: &CodegenContext,
+    array: *mut llvm::LLVMValue,
+    element: *mut llvm::LLVMValue,
+    sp: *mut llvm::LLVMValue,
+) -> *mut llvm::LLVMValue {
+    let len = len_of::<Gc>(cc, array);
+    let name = CString::new("len").unwrap();
+    let new_len =
+        unsafe { llvm::core::LLVMBuildAdd(cc.builder, len, const_i32(cc, 1), name.as_ptr()) };
+    let array_type = array_type(array);
+    let (array_slot, new_sp) = save::<Gc>(cc, array, &array_type, sp);
+    let (element_slot, new_sp) = save_element::<Gc>(cc, element, &array_type, new_sp);
+    let copy = alloc::<Gc>(cc, &array_type, new_len, new_sp);
+    let array = load(cc, array_slot);
+    let element = element_slot.map_or(element, |slot| load(cc, slot));
+    copy_elements::<Gc>(cc, copy, array, len, sp);
+    store::<Gc>(cc, copy, len, element);
+    Gc::type_ptr_drop(cc, array, sp);
+    copy
+}
+
+// Allocates an array of the given length, its elements still have to be
+// stored
+pub fn alloc<Gc: GC>(
+    cc: &CodegenContext,
+    array_type: &Rc<String>,
+    len: *mut llvm::LLVMValue,
+    sp: *mut llvm::LLVMValue,
+) -> *mut llvm::LLVMValue {
+    let element = array_element(array_type).expect("Expected an array type");
+    let name = CString::new("size").unwrap();
+    let (size, len) = unsafe {
+        let len = llvm::core::LLVMBuildZExt(cc.builder, len, int64_type(cc), name.as_ptr());
+        let element_size =
+            llvm::core::LLVMSizeOf(type_to_llvm_type(cc.context, &cc.llvm_structs, &element));
+        let elements_size = llvm::core::LLVMBuildMul(cc.builder, len, element_size, name.as_ptr());
+        let size = llvm::core::LLVMBuildAdd(
+            cc.builder,
+            get_struct_size(&cc.llvm_structs, array_type),
+            elements_size,
+            name.as_ptr(),
+        );
+        (size, len)
+    };
+    let malloc_ret = Gc::type_allocation(cc, size, sp);
+    let struct_name = CString::new(format!("{}*", array_type)).unwrap();
+    let array = unsafe {
+        llvm::core::LLVMBuildBitCast(
+            cc.builder,
+            malloc_ret,
+            type_to_llvm_type(cc.context, &cc.llvm_structs, array_type),
+            struct_name.as_ptr(),
+        )
+    };
+    Gc::init_header(cc, array, size);
+    // Arrays have a single case, the pointers of arrays of objects are counted
+    // by their length
+    let case_id = if has_objects(array_type) {
+        LONG_POINTER_COUNT << 16
+    } else {
+        0
+    };
+    unsafe {
+        llvm::core::LLVMBuildStore(
+            cc.builder,
+            const_i32(cc, case_id),
+            field::<Gc>(cc, array, 0),
+        );
+        llvm::core::LLVMBuildStore(cc.builder, len, field::<Gc>(cc, array, 1));
+    }
+    array
+}
+
+pub fn store<Gc: GC>(
+    cc: &CodegenContext,
+    array: *mut llvm::LLVMValue,
+    index: *mut llvm::LLVMValue,
+    element: *mut llvm::LLVMValue,
+) {
+    unsafe { llvm::core::LLVMBuildStore(cc.builder, element, element_ptr::<Gc>(cc, array, index)) };
+}
+
+// Copies the first len elements of src to dest. The objects among them gain a
+// reference from dest.
+fn copy_elements<Gc: GC>(
+    cc: &CodegenContext,
+    dest: *mut llvm::LLVMValue,
+    src: *mut llvm::LLVMValue,
+    len: *mut llvm::LLVMValue,
+    sp: *mut llvm::LLVMValue,
+) {
+    let array_type = array_type(dest);
+    let element = array_element(&array_type).unwrap();
+    let name = CString::new("copy_len").unwrap();
+    let void_ptr_type = type_to_llvm_type(
+        cc.context,
+        &cc.llvm_structs,
+        &Rc::new(VOID_PTR_TYPE.to_string()),
+    );
+    let zero = const_i32(cc, 0);
+    let (dest_ptr, src_ptr, copy_len) = unsafe {
+        let len = llvm::core::LLVMBuildZExt(cc.builder, len, int64_type(cc), name.as_ptr());
+        let element_size =
+            llvm::core::LLVMSizeOf(type_to_llvm_type(cc.context, &cc.llvm_structs, &element));
+        (
+            llvm::core::LLVMBuildBitCast(
+                cc.builder,
+                element_ptr::<Gc>(cc, dest, zero),
+                void_ptr_type,
+                name.as_ptr(),
+            ),
+            llvm::core::LLVMBuildBitCast(
+                cc.builder,
+                element_ptr::<Gc>(cc, src, zero),
+                void_ptr_type,
+                name.as_ptr(),
+            ),
+            llvm::core::LLVMBuildMul(cc.builder, len, element_size, name.as_ptr()),
+        )
+    };
+    create_func_call::<Gc>(
+        cc,
+        &Rc::new(BuildIn::memcpy.as_str().to_string()),
+        &mut vec![dest_ptr, src_ptr, copy_len],
+        sp,
+    );
+    if has_objects(&array_type) {
+        access_elements::<Gc>(cc, dest, len, sp);
+    }
+}
+
+// Calls Gc::type_ptr_access on the first len elements of the array
+fn access_elements<Gc: GC>(
+    cc: &CodegenContext,
+    array: *mut llvm::LLVMValue,
+    len: *mut llvm::LLVMValue,
+    sp: *mut llvm::LLVMValue,
+) {
+    unsafe {
+        let entry_block = llvm::core::LLVMGetInsertBlock(cc.builder);
+        let current_func = llvm::core::LLVMGetBasicBlockParent(entry_block);
+        let loop_name = CString::new("access_loop").unwrap();
+        let loop_block =
+            llvm::core::LLVMAppendBasicBlockInContext(cc.context, current_func, loop_name.as_ptr());
+        let body_name = CString::new("access_element").unwrap();
+        let body_block =
+            llvm::core::LLVMAppendBasicBlockInContext(cc.context, current_func, body_name.as_ptr());
+        let end_name = CString::new("access_end").unwrap();
+        let end_block =
+            llvm::core::LLVMAppendBasicBlockInContext(cc.context, current_func, end_name.as_ptr());
+        llvm::core::LLVMBuildBr(cc.builder, loop_block);
+
+        llvm::core::LLVMPositionBuilderAtEnd(cc.builder, loop_block);
+        let index_name = CString::new("index").unwrap();
+        let index = llvm::core::LLVMBuildPhi(cc.builder, int32_type(cc), index_name.as_ptr());
+        let cmp_name = CString::new("in_array").unwrap();
+        let in_array = llvm::core::LLVMBuildICmp(
+            cc.builder,
+            llvm::LLVMIntPredicate::LLVMIntSLT,
+            index,
+            len,
+            cmp_name.as_ptr(),
+        );
+        llvm::core::LLVMBuildCondBr(cc.builder, in_array, body_block, end_block);
+
+        llvm::core::LLVMPositionBuilderAtEnd(cc.builder, body_block);
+        let element_name = CString::new("element").unwrap();
+        let element = llvm::core::LLVMBuildLoad(
+            cc.builder,
+            element_ptr::<Gc>(cc, array, index),
+            element_name.as_ptr(),
+        );
+        Gc::type_ptr_access(cc, element, sp);
+        let next_name = CString::new("next_index").unwrap();
+        let next =
+            llvm::core::LLVMBuildAdd(cc.builder, index, const_i32(cc, 1), next_name.as_ptr());
+        let body_end_block = llvm::core::LLVMGetInsertBlock(cc.builder);
+        llvm::core::LLVMBuildBr(cc.builder, loop_block);
+
+        let mut incoming_values = [const_i32(cc, 0), next];
+        let mut incoming_blocks = [entry_block, body_end_block];
+        llvm::core::LLVMAddIncoming(
+            index,
+            incoming_values.as_mut_ptr(),
+            incoming_blocks.as_mut_ptr(),
+            2,
+        );
+        llvm::core::LLVMPositionBuilderAtEnd(cc.builder, end_block);
+    }
+}
+
+// The type id of an array, which is the name of its LLVM struct
+fn array_type(array: *mut llvm::LLVMValue) -> Rc<String> {
+    unsafe {
+        let struct_type = llvm::core::LLVMGetElementType(llvm::core::LLVMTypeOf(array));
+        let name = CStr::from_ptr(llvm::core::LLVMGetStructName(struct_type));
+        Rc::new(name.to_string_lossy().into_owned())
+    }
+}
+
+// Whether the elements are objects, which the GC has to trace
+fn has_objects(array_type: &str) -> bool {
+    array_element(array_type).is_some_and(|element| element.starts_with('$'))
+}
+
+// Puts the array on the arena stack for the case that the next allocation
+// leads to a garbage collection. Returns the stack element and the new SP.
+fn save<Gc: GC>(
+    cc: &CodegenContext,
+    value: *mut llvm::LLVMValue,
+    type_id: &Rc<String>,
+    sp: *mut llvm::LLVMValue,
+) -> (*mut llvm::LLVMValue, *mut llvm::LLVMValue) {
+    let name = CString::new("saved_array").unwrap();
+    let (slot, new_sp) = get_next_stack_element::<Gc>(sp, cc, &name, type_id);
+    unsafe { llvm::core::LLVMBuildStore(cc.builder, value, slot) };
+    (slot, new_sp)
+}
+
+// Saves the element if it is an object
+fn save_element<Gc: GC>(
+    cc: &CodegenContext,
+    element: *mut llvm::LLVMValue,
+    array_type: &Rc<String>,
+    sp: *mut llvm::LLVMValue,
+) -> (Option<*mut llvm::LLVMValue>, *mut llvm::LLVMValue) {
+    if !has_objects(array_type) {
+        return (None, sp);
+    }
+    let element_type = array_element(array_type).unwrap();
+    let (slot, new_sp) = save::<Gc>(cc, element, &element_type, sp);
+    (Some(slot), new_sp)
+}
+
+fn load(cc: &CodegenContext, slot: *mut llvm::LLVMValue) -> *mut llvm::LLVMValue {
+    let name = CString::new("saved_array").unwrap();
+    unsafe { llvm::core::LLVMBuildLoad(cc.builder, slot, name.as_ptr()) }
+}
+
+// Pointer to the field after the GC header with the given index, 0 is the
+// case id and 1 the length
+fn field<Gc: GC>(
+    cc: &CodegenContext,
+    array: *mut llvm::LLVMValue,
+    index: u64,
+) -> *mut llvm::LLVMValue {
+    let name = CString::new("array_field").unwrap();
+    unsafe {
+        llvm::core::LLVMBuildGEP(
+            cc.builder,
+            array,
+            vec![
+                const_i32(cc, 0),
+                const_i32(cc, Gc::get_type_header_length() + index),
+            ]
+            .as_mut_ptr(),
+            2,
+            name.as_ptr(),
+        )
+    }
+}
+
+fn len_of<Gc: GC>(cc: &CodegenContext, array: *mut llvm::LLVMValue) -> *mut llvm::LLVMValue {
+    let name = CString::new("len").unwrap();
+    unsafe {
+        let len = llvm::core::LLVMBuildLoad(cc.builder, field::<Gc>(cc, array, 1), name.as_ptr());
+        llvm::core::LLVMBuildTrunc(cc.builder, len, int32_type(cc), name.as_ptr())
+    }
+}
+
+fn element_ptr<Gc: GC>(
+    cc: &CodegenContext,
+    array: *mut llvm::LLVMValue,
+    index: *mut llvm::LLVMValue,
+) -> *mut llvm::LLVMValue {
+    let name = CString::new("element_ptr").unwrap();
+    unsafe {
+        llvm::core::LLVMBuildGEP(
+            cc.builder,
+            array,
+            vec![
+                const_i32(cc, 0),
+                const_i32(cc, Gc::get_type_header_length() + 2),
+                index,
+            ]
+            .as_mut_ptr(),
+            3,
+            name.as_ptr(),
+        )
+    }
+}
+
+fn const_i32(cc: &CodegenContext, i: u64) -> *mut llvm::LLVMValue {
+    unsafe { llvm::core::LLVMConstInt(int32_type(cc), i, 0) }
+}
+
+fn int32_type(cc: &CodegenContext) -> *mut llvm::LLVMType {
+    type_to_llvm_type(cc.context, &cc.llvm_structs, &Rc::new(I32_TYPE.to_string()))
+}
+
+fn int64_type(cc: &CodegenContext) -> *mut llvm::LLVMType {
+    type_to_llvm_type(cc.context, &cc.llvm_structs, &Rc::new(I64_TYPE.to_string()))
+}
@@ -2,11 +2,15 @@ use llvm_sys as llvm;
 use std::ffi::CString;
 use std::rc::Rc;
 
+use crate::codegen::array;
+use crate::codegen::check;
 use crate::codegen::function::create_func_call;
 use crate::codegen::garbage_collection::GC;
+use crate::codegen::string;
 use crate::codegen::CodegenContext;
 use crate::types::{
-    BOOL_TYPE, EXIT_TYPE, I32_TYPE, I64_TYPE, STR_TYPE, U8_TYPE, VOID_PTR_TYPE, VOID_TYPE,
+    array_element, BOOL_TYPE, EXIT_TYPE, I32_TYPE, I64_TYPE, STRING_TYPE, STR_TYPE, U8_TYPE,
+    VOID_PTR_TYPE, VOID_TYPE,
 };
 
 macro_rules! enum_str {
@@ -32,12 +36,17 @@ macro_rules! enum_str {
                     _ => panic!("ENUM VARIANT NOT FOUND: {}", s),
                 }
             }
+
+            pub fn is_variant(s: &str) -> bool {
+                matches!(s, $(stringify!($variant))|*)
+            }
         }
     };
 }
 
 enum_str!(
     #[allow(non_camel_case_types)]
+    #[derive(Clone, Copy)]
     enum BuildIn {
         init_stack,
         stack_alloc,
@@ -54,6 +63,27 @@ enum_str!(
         print_u8,
         print_i32,
         printf,
+        memcpy,
+        strlen,
+        string_compare,
+        string_find,
+        string_to_i32,
+        i32_string_len,
+        i32_string_write,
+        arena_panic,
+        user_panic,
+        user_panic_str,
+        init_args,
+        arg_count,
+        arg_at,
+        env_value,
+        io_take,
+        io_read_line,
+        io_read_file,
+        io_write_file,
+        io_last_error,
+        io_eprint,
+        io_eprint_str,
         char_at,
         exit,
         eq_i32,
@@ -83,6 +113,35 @@ enum_str!(
         neg_i32,
         neg_u8,
         not_bool,
+        string_str,
+        string_u8,
+        string_i32,
+        print_string,
+        len_string,
+        char_at_string,
+        concat_string,
+        slice_string,
+        find_string,
+        to_i32_string,
+        eq_string,
+        neq_string,
+        lt_string,
+        le_string,
+        gt_string,
+        ge_string,
+        env_str,
+        read_line,
+        read_file_string,
+        write_file_string,
+        io_error,
+        eprint_str,
+        eprint_string,
+        panic_str,
+        panic_string,
+        len_array,
+        get_array,
+        set_array,
+        push_array,
     }
 );
 
@@ -226,11 +285,127 @@ pub fn get_build_in_signature(
             Rc::new(BuildIn::not_bool.as_str().to_string()),
             Rc::new(BOOL_TYPE.to_string()),
         )),
+        ("string", [STR_TYPE]) => Some((
+            Rc::new(BuildIn::string_str.as_str().to_string()),
+            Rc::new(STRING_TYPE.to_string()),
+        )),
+        ("string", [U8_TYPE]) => Some((
+            Rc::new(BuildIn::string_u8.as_str().to_string()),
+            Rc::new(STRING_TYPE.to_string()),
+        )),
+        ("string", [I32_TYPE]) => Some((
+            Rc::new(BuildIn::string_i32.as_str().to_string()),
+            Rc::new(STRING_TYPE.to_string()),
+        )),
+        ("env", [STR_TYPE]) => Some((
+            Rc::new(BuildIn::env_str.as_str().to_string()),
+            Rc::new(STRING_TYPE.to_string()),
+        )),
+        ("read_line", []) => Some((
+            Rc::new(BuildIn::read_line.as_str().to_string()),
+            Rc::new(STRING_TYPE.to_string()),
+        )),
+        ("read_file", [STRING_TYPE]) => Some((
+            Rc::new(BuildIn::read_file_string.as_str().to_string()),
+            Rc::new(STRING_TYPE.to_string()),
+        )),
+        ("write_file", [STRING_TYPE, STRING_TYPE]) => Some((
+            Rc::new(BuildIn::write_file_string.as_str().to_string()),
+            Rc::new(VOID_TYPE.to_string()),
+        )),
+        ("io_error", []) => Some((
+            Rc::new(BuildIn::io_error.as_str().to_string()),
+            Rc::new(STRING_TYPE.to_string()),
+        )),
+        ("eprint", [STR_TYPE]) => Some((
+            Rc::new(BuildIn::eprint_str.as_str().to_string()),
+            Rc::new(VOID_TYPE.to_string()),
+        )),
+        ("eprint", [STRING_TYPE]) => Some((
+            Rc::new(BuildIn::eprint_string.as_str().to_string()),
+            Rc::new(VOID_TYPE.to_string()),
+        )),
+        ("print", [STRING_TYPE]) => Some((
+            Rc::new(BuildIn::print_string.as_str().to_string()),
+            Rc::new(VOID_TYPE.to_string()),
+        )),
+        ("len", [STRING_TYPE]) => Some((
+            Rc::new(BuildIn::len_string.as_str().to_string()),
+            Rc::new(I32_TYPE.to_string()),
+        )),
+        ("char_at", [STRING_TYPE, I32_TYPE]) => Some((
+            Rc::new(BuildIn::char_at_string.as_str().to_string()),
+            Rc::new(U8_TYPE.to_string()),
+        )),
+        ("concat", [STRING_TYPE, STRING_TYPE]) | ("add", [STRING_TYPE, STRING_TYPE]) => Some((
+            Rc::new(BuildIn::concat_string.as_str().to_string()),
+            Rc::new(STRING_TYPE.to_string()),
+        )),
+        ("slice", [STRING_TYPE, I32_TYPE, I32_TYPE]) => Some((
+            Rc::new(BuildIn::slice_string.as_str().to_string()),
+            Rc::new(STRING_TYPE.to_string()),
+        )),
+        ("find", [STRING_TYPE, STRING_TYPE]) => Some((
+            Rc::new(BuildIn::find_string.as_str().to_string()),
+            Rc::new(I32_TYPE.to_string()),
+        )),
+        ("to_i32", [STRING_TYPE]) => Some((
+            Rc::new(BuildIn::to_i32_string.as_str().to_string()),
+            Rc::new(I32_TYPE.to_string()),
+        )),
+        ("eq", [STRING_TYPE, STRING_TYPE]) => Some((
+            Rc::new(BuildIn::eq_string.as_str().to_string()),
+            Rc::new(BOOL_TYPE.to_string()),
+        )),
+        ("neq", [STRING_TYPE, STRING_TYPE]) => Some((
+            Rc::new(BuildIn::neq_string.as_str().to_string()),
+            Rc::new(BOOL_TYPE.to_string()),
+        )),
+        ("lt", [STRING_TYPE, STRING_TYPE]) => Some((
+            Rc::new(BuildIn::lt_string.as_str().to_string()),
+            Rc::new(BOOL_TYPE.to_string()),
+        )),
+        ("le", [STRING_TYPE, STRING_TYPE]) => Some((
+            Rc::new(BuildIn::le_string.as_str().to_string()),
+            Rc::new(BOOL_TYPE.to_string()),
+        )),
+        ("gt", [STRING_TYPE, STRING_TYPE]) => Some((
+            Rc::new(BuildIn::gt_string.as_str().to_string()),
+            Rc::new(BOOL_TYPE.to_string()),
+        )),
+        ("ge", [STRING_TYPE, STRING_TYPE]) => Some((
+            Rc::new(BuildIn::ge_string.as_str().to_string()),
+            Rc::new(BOOL_TYPE.to_string()),
+        )),
+        ("len", [array]) if array_element(array).is_some() => Some((
+            Rc::new(BuildIn::len_array.as_str().to_string()),
+            Rc::new(I32_TYPE.to_string()),
+        )),
+        ("get", [array, I32_TYPE]) if array_element(array).is_some() => Some((
+            Rc::new(BuildIn::get_array.as_str().to_string()),
+            array_element(array).unwrap(),
+        )),
+        ("set", [array, I32_TYPE, element])
+            if array_element(array).is_some_and(|e| e.as_str() == *element) =>
+        {
+            Some((
+                Rc::new(BuildIn::set_array.as_str().to_string()),
+                Rc::new(array.to_string()),
+            ))
+        }
+        ("push", [array, element])
+            if array_element(array).is_some_and(|e| e.as_str() == *element) =>
+        {
+            Some((
+                Rc::new(BuildIn::push_array.as_str().to_string()),
+                Rc::new(array.to_string()),
+            ))
+        }
         _ => None,
     }
 }
 
-pub fn get_build_in_func_call<Gc: GC>(
+pub(crate) fn get_build_in_func_call<Gc: GC>(
     cc: &CodegenContext,
     func_id: &Rc<String>,
     computed_params: &mut Vec<*mut llvm::LLVMValue>,
@@ -238,6 +413,27 @@ pub fn get_build_in_func_call<Gc: GC>(
 ) -> Option<*mut llvm::LLVMValue> {
     match BuildIn::from_str(func_id.as_str()) {
         BuildIn::printf
+        | BuildIn::memcpy
+        | BuildIn::strlen
+        | BuildIn::string_compare
+        | BuildIn::string_find
+        | BuildIn::string_to_i32
+        | BuildIn::i32_string_len
+        | BuildIn::i32_string_write
+        | BuildIn::arena_panic
+        | BuildIn::user_panic
+        | BuildIn::user_panic_str
+        | BuildIn::init_args
+        | BuildIn::arg_count
+        | BuildIn::arg_at
+        | BuildIn::env_value
+        | BuildIn::io_take
+        | BuildIn::io_read_line
+        | BuildIn::io_read_file
+        | BuildIn::io_write_file
+        | BuildIn::io_last_error
+        | BuildIn::io_eprint
+        | BuildIn::io_eprint_str
         | BuildIn::exit
         | BuildIn::init_stack
         | BuildIn::stack_alloc
@@ -287,6 +483,22 @@ pub fn get_build_in_func_call<Gc: GC>(
             ))
         }
         BuildIn::char_at => unsafe {
+            if !cc.unchecked {
+                let len = create_func_call::<Gc>(
+                    cc,
+                    &Rc::new(BuildIn::strlen.as_str().to_string()),
+                    &mut vec![computed_params[0]],
+                    sp,
+                );
+                let len_name = CString::new("len").unwrap();
+                let len = llvm::core::LLVMBuildTrunc(
+                    cc.builder,
+                    len,
+                    llvm::core::LLVMTypeOf(computed_params[1]),
+                    len_name.as_ptr(),
+                );
+                check::index::<Gc>(cc, "char_at", "a string", computed_params[1], len, true, sp);
+            }
             let mut indices = vec![computed_params[1]];
             let char_ptr_name = CString::new("char_at_ptr").unwrap();
             let char_at_name = CString::new("char_at").unwrap();
@@ -363,7 +575,7 @@ pub fn get_build_in_func_call<Gc: GC>(
                 name.as_ptr(),
             ))
         },
-        BuildIn::add_u8 | BuildIn::add_i32 => unsafe {
+        BuildIn::add_u8 => unsafe {
             let name = CString::new("addtmp").unwrap();
             Some(llvm::core::LLVMBuildAdd(
                 cc.builder,
@@ -372,7 +584,25 @@ pub fn get_build_in_func_call<Gc: GC>(
                 name.as_ptr(),
             ))
         },
-        BuildIn::sub_u8 | BuildIn::sub_i32 => unsafe {
+        BuildIn::add_i32 => unsafe {
+            let name = CString::new("addtmp").unwrap();
+            let sum = llvm::core::LLVMBuildAdd(
+                cc.builder,
+                computed_params[0],
+                computed_params[1],
+                name.as_ptr(),
+            );
+            check::addition::<Gc>(
+                cc,
+                "add_i32",
+                computed_params[0],
+                computed_params[1],
+                sum,
+                sp,
+            );
+            Some(sum)
+        },
+        BuildIn::sub_u8 => unsafe {
             let name = CString::new("subtmp").unwrap();
             Some(llvm::core::LLVMBuildSub(
                 cc.builder,
@@ -381,7 +611,25 @@ pub fn get_build_in_func_call<Gc: GC>(
                 name.as_ptr(),
             ))
         },
-        BuildIn::mul_u8 | BuildIn::mul_i32 => unsafe {
+        BuildIn::sub_i32 => unsafe {
+            let name = CString::new("subtmp").unwrap();
+            let difference = llvm::core::LLVMBuildSub(
+                cc.builder,
+                computed_params[0],
+                computed_params[1],
+                name.as_ptr(),
+            );
+            check::subtraction::<Gc>(
+                cc,
+                "sub_i32",
+                computed_params[0],
+                computed_params[1],
+                difference,
+                sp,
+            );
+            Some(difference)
+        },
+        BuildIn::mul_u8 => unsafe {
             let name = CString::new("multmp").unwrap();
             Some(llvm::core::LLVMBuildMul(
                 cc.builder,
@@ -390,7 +638,26 @@ pub fn get_build_in_func_call<Gc: GC>(
                 name.as_ptr(),
             ))
         },
+        BuildIn::mul_i32 => unsafe {
+            let name = CString::new("multmp").unwrap();
+            let product = llvm::core::LLVMBuildMul(
+                cc.builder,
+                computed_params[0],
+                computed_params[1],
+                name.as_ptr(),
+            );
+            check::multiplication::<Gc>(
+                cc,
+                "mul_i32",
+                computed_params[0],
+                computed_params[1],
+                product,
+                sp,
+            );
+            Some(product)
+        },
         BuildIn::div_u8 | BuildIn::div_i32 => unsafe {
+            check::division::<Gc>(cc, func_id, "/", computed_params[0], computed_params[1], sp);
             let name = CString::new("divtmp").unwrap();
             Some(llvm::core::LLVMBuildSDiv(
                 cc.builder,
@@ -400,6 +667,14 @@ pub fn get_build_in_func_call<Gc: GC>(
             ))
         },
         BuildIn::mod_u8 | BuildIn::mod_i32 => unsafe {
+            check::division::<Gc>(
+                cc,
+                func_id,
+                "%%",
+                computed_params[0],
+                computed_params[1],
+                sp,
+            );
             let name = CString::new("modtmp").unwrap();
             Some(llvm::core::LLVMBuildSRem(
                 cc.builder,
@@ -424,23 +699,196 @@ pub fn get_build_in_func_call<Gc: GC>(
                 name.as_ptr(),
             ))
         },
+        BuildIn::string_str => Some(string::from_str::<Gc>(cc, computed_params[0], sp)),
+        BuildIn::env_str => {
+            let value = create_func_call::<Gc>(
+                cc,
+                &Rc::new(BuildIn::env_value.as_str().to_string()),
+                computed_params,
+                sp,
+            );
+            Some(string::from_str::<Gc>(cc, value, sp))
+        }
+        BuildIn::read_line => Some(string::read_line::<Gc>(cc, sp)),
+        BuildIn::read_file_string => Some(string::read_file::<Gc>(cc, computed_params[0], sp)),
+        BuildIn::write_file_string => Some(string::write_file::<Gc>(
+            cc,
+            computed_params[0],
+            computed_params[1],
+            sp,
+        )),
+        BuildIn::io_error => Some(string::io_error::<Gc>(cc, sp)),
+        BuildIn::eprint_str => Some(create_func_call::<Gc>(
+            cc,
+            &Rc::new(BuildIn::io_eprint_str.as_str().to_string()),
+            computed_params,
+            sp,
+        )),
+        BuildIn::eprint_string => Some(string::eprint::<Gc>(cc, computed_params[0], sp)),
+        BuildIn::panic_str => Some(create_func_call::<Gc>(
+            cc,
+            &Rc::new(BuildIn::user_panic_str.as_str().to_string()),
+            computed_params,
+            sp,
+        )),
+        BuildIn::panic_string => Some(string::panic::<Gc>(
+            cc,
+            computed_params[0],
+            computed_params[1],
+            sp,
+        )),
+        BuildIn::string_u8 => Some(string::from_u8::<Gc>(cc, computed_params[0], sp)),
+        BuildIn::string_i32 => Some(string::from_i32::<Gc>(cc, computed_params[0], sp)),
+        BuildIn::print_string => Some(string::print::<Gc>(cc, computed_params[0], sp)),
+        BuildIn::len_string => Some(string::len::<Gc>(cc, computed_params[0], sp)),
+        BuildIn::char_at_string => Some(string::char_at::<Gc>(
+            cc,
+            computed_params[0],
+            computed_params[1],
+            sp,
+        )),
+        BuildIn::concat_string => Some(string::concat::<Gc>(
+            cc,
+            computed_params[0],
+            computed_params[1],
+            sp,
+        )),
+        BuildIn::slice_string => Some(string::slice::<Gc>(
+            cc,
+            computed_params[0],
+            computed_params[1],
+            computed_params[2],
+            sp,
+        )),
+        BuildIn::find_string => Some(string::find::<Gc>(
+            cc,
+            computed_params[0],
+            computed_params[1],
+            sp,
+        )),
+        BuildIn::to_i32_string => Some(string::to_i32::<Gc>(cc, computed_params[0], sp)),
+        BuildIn::eq_string => Some(string::compare::<Gc>(
+            cc,
+            llvm::LLVMIntPredicate::LLVMIntEQ,
+            computed_params[0],
+            computed_params[1],
+            sp,
+        )),
+        BuildIn::neq_string => Some(string::compare::<Gc>(
+            cc,
+            llvm::LLVMIntPredicate::LLVMIntNE,
+            computed_params[0],
+            computed_params[1],
+            sp,
+        )),
+        BuildIn::lt_string => Some(string::compare::<Gc>(
+            cc,
+            llvm::LLVMIntPredicate::LLVMIntSLT,
+            computed_params[0],
+            computed_params[1],
+            sp,
+        )),
+        BuildIn::le_string => Some(string::compare::<Gc>(
+            cc,
+            llvm::LLVMIntPredicate::LLVMIntSLE,
+            computed_params[0],
+            computed_params[1],
+            sp,
+        )),
+        BuildIn::gt_string => Some(string::compare::<Gc>(
+            cc,
+            llvm::LLVMIntPredicate::LLVMIntSGT,
+            computed_params[0],
+            computed_params[1],
+            sp,
+        )),
+        BuildIn::ge_string => Some(string::compare::<Gc>(
+            cc,
+            llvm::LLVMIntPredicate::LLVMIntSGE,
+            computed_params[0],
+            computed_params[1],
+            sp,
+        )),
+        BuildIn::len_array => Some(array::len::<Gc>(cc, computed_params[0], sp)),
+        BuildIn::get_array => Some(array::get::<Gc>(
+            cc,
+            computed_params[0],
+            computed_params[1],
+            sp,
+        )),
+        BuildIn::set_array => Some(array::set::<Gc>(
+            cc,
+            computed_params[0],
+            computed_params[1],
+            computed_params[2],
+            sp,
+        )),
+        BuildIn::push_array => Some(array::push::<Gc>(
+            cc,
+            computed_params[0],
+            computed_params[1],
+            sp,
+        )),
     }
 }
 
+// Symbols of the generated code and the runtime, which extern functions can
+// not declare again
+pub fn is_reserved_symbol(symbol: &str) -> bool {
+    symbol == "main" || BuildIn::is_variant(symbol)
+}
+
 pub fn get_linked_func_signature(func_id: &Rc<String>) -> (Vec<&'static str>, &'static str, bool) {
     // returns arg types, ret type, is var arg
     match BuildIn::from_str(func_id.as_str()) {
         BuildIn::printf => (vec![STR_TYPE], I32_TYPE, true),
+        BuildIn::memcpy => (
+            vec![VOID_PTR_TYPE, VOID_PTR_TYPE, I64_TYPE],
+            VOID_PTR_TYPE,
+            false,
+        ),
+        BuildIn::strlen => (vec![STR_TYPE], I64_TYPE, false),
+        BuildIn::string_compare => (
+            vec![VOID_PTR_TYPE, I32_TYPE, VOID_PTR_TYPE, I32_TYPE],
+            I32_TYPE,
+            false,
+        ),
+        BuildIn::string_find => (
+            vec![VOID_PTR_TYPE, I32_TYPE, VOID_PTR_TYPE, I32_TYPE],
+            I32_TYPE,
+            false,
+        ),
+        BuildIn::string_to_i32 => (vec![VOID_PTR_TYPE, I32_TYPE], I32_TYPE, false),
+        BuildIn::i32_string_len => (vec![I32_TYPE], I32_TYPE, false),
+        BuildIn::i32_string_write => (vec![VOID_PTR_TYPE, I32_TYPE, I32_TYPE], VOID_TYPE, false),
+        BuildIn::arena_panic => (vec![STR_TYPE], VOID_TYPE, true),
+        BuildIn::user_panic => (vec![STR_TYPE, VOID_PTR_TYPE, I32_TYPE], EXIT_TYPE, false),
+        BuildIn::user_panic_str => (vec![STR_TYPE, STR_TYPE], EXIT_TYPE, false),
+        BuildIn::init_args => (vec![I32_TYPE, VOID_PTR_TYPE], VOID_TYPE, false),
+        BuildIn::arg_count => (Vec::new(), I32_TYPE, false),
+        BuildIn::arg_at => (vec![I32_TYPE], STR_TYPE, false),
+        BuildIn::env_value => (vec![STR_TYPE], STR_TYPE, false),
+        BuildIn::io_take => (vec![VOID_PTR_TYPE], VOID_TYPE, false),
+        BuildIn::io_read_line => (Vec::new(), I32_TYPE, false),
+        BuildIn::io_read_file => (vec![VOID_PTR_TYPE, I32_TYPE], I32_TYPE, false),
+        BuildIn::io_write_file => (
+            vec![VOID_PTR_TYPE, I32_TYPE, VOID_PTR_TYPE, I32_TYPE],
+            VOID_TYPE,
+            false,
+        ),
+        BuildIn::io_last_error => (Vec::new(), I32_TYPE, false),
+        BuildIn::io_eprint => (vec![VOID_PTR_TYPE, I32_TYPE], VOID_TYPE, false),
+        BuildIn::io_eprint_str => (vec![STR_TYPE], VOID_TYPE, false),
         BuildIn::exit => (vec![I32_TYPE], EXIT_TYPE, false),
         BuildIn::init_stack => (vec![I64_TYPE], VOID_PTR_TYPE, false),
         BuildIn::stack_alloc => (vec![VOID_PTR_TYPE], VOID_PTR_TYPE, false),
         BuildIn::close_stack => (Vec::new(), VOID_TYPE, false),
-        BuildIn::init_heap => (Vec::new(), VOID_TYPE, false),
+        BuildIn::init_heap => (vec![I64_TYPE], VOID_TYPE, false),
         BuildIn::type_alloc => (vec![I64_TYPE, VOID_PTR_TYPE], VOID_PTR_TYPE, false),
         BuildIn::close_heap => (Vec::new(), VOID_TYPE, false),
         BuildIn::arc_ptr_access => (vec![VOID_PTR_TYPE, VOID_PTR_TYPE], VOID_TYPE, false),
         BuildIn::arc_drop_ptr => (vec![VOID_PTR_TYPE, VOID_PTR_TYPE], VOID_TYPE, false),
-        BuildIn::tgc_init_heap => (Vec::new(), VOID_TYPE, false),
+        BuildIn::tgc_init_heap => (vec![I64_TYPE], VOID_TYPE, false),
         BuildIn::tgc_close_heap => (Vec::new(), VOID_TYPE, false),
         BuildIn::tgc_type_alloc => (vec![I64_TYPE, VOID_PTR_TYPE], VOID_PTR_TYPE, false),
         BuildIn::char_at
@@ -473,6 +921,35 @@ pub fn get_linked_func_signature(func_id: &Rc<String>) -> (Vec<&'static str>, &'
         | BuildIn::div_i32
         | BuildIn::mod_i32
         | BuildIn::neg_i32
-        | BuildIn::not_bool => unreachable!("{} is not a dynamically linked function", func_id),
+        | BuildIn::not_bool
+        | BuildIn::string_str
+        | BuildIn::string_u8
+        | BuildIn::string_i32
+        | BuildIn::print_string
+        | BuildIn::len_string
+        | BuildIn::char_at_string
+        | BuildIn::concat_string
+        | BuildIn::slice_string
+        | BuildIn::find_string
+        | BuildIn::to_i32_string
+        | BuildIn::eq_string
+        | BuildIn::neq_string
+        | BuildIn::lt_string
+        | BuildIn::le_string
+        | BuildIn::gt_string
+        | BuildIn::ge_string
+        | BuildIn::env_str
+        | BuildIn::read_line
+        | BuildIn::read_file_string
+        | BuildIn::write_file_string
+        | BuildIn::io_error
+        | BuildIn::eprint_str
+        | BuildIn::eprint_string
+        | BuildIn::panic_str
+        | BuildIn::panic_string
+        | BuildIn::len_array
+        | BuildIn::get_array
+        | BuildIn::set_array
+        | BuildIn::push_array => unreachable!("{} is not a dynamically linked function", func_id),
     }
 }
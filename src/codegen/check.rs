@@ -0,0 +1,279 @@
+// Code generation of the runtime checks of the build in functions. A failed
+// check calls arena_panic of the runtime, which prints the message and exits
+// with code 1. Programs compiled with --unchecked leave the checks out.
+
+use llvm_sys as llvm;
+use std::ffi::CString;
+use std::rc::Rc;
+
+use crate::codegen::build_in::BuildIn;
+use crate::codegen::function::create_func_call;
+use crate::codegen::garbage_collection::GC;
+use crate::codegen::CodegenContext;
+
+// Checks 0 <= index < len, or 0 <= index <= len if the end can be read like
+// the terminating NUL byte of a str. The message names the indexed sequence,
+// like "a string".
+pub fn index<Gc: GC>(
+    cc: &CodegenContext,
+    func_name: &str,
+    sequence: &str,
+    index: *mut llvm::LLVMValue,
+    len: *mut llvm::LLVMValue,
+    end_readable: bool,
+    sp: *mut llvm::LLVMValue,
+) {
+    if cc.unchecked {
+        return;
+    }
+    let predicate = if end_readable {
+        llvm::LLVMIntPredicate::LLVMIntULE
+    } else {
+        llvm::LLVMIntPredicate::LLVMIntULT
+    };
+    // A negative index is a large unsigned number
+    let name = CString::new("in_bounds").unwrap();
+    let ok = unsafe { llvm::core::LLVMBuildICmp(cc.builder, predicate, index, len, name.as_ptr()) };
+    let message = format!(
+        "{}: index %d is out of bounds for {} of length %d",
+        func_name, sequence
+    );
+    check::<Gc>(cc, ok, &message, vec![index, len], sp);
+}
+
+// Checks that the divisor is not zero and that the quotient fits into the
+// type, which only fails for the smallest value divided by -1.
+pub fn division<Gc: GC>(
+    cc: &CodegenContext,
+    func_name: &str,
+    operator: &str,
+    a: *mut llvm::LLVMValue,
+    b: *mut llvm::LLVMValue,
+    sp: *mut llvm::LLVMValue,
+) {
+    if cc.unchecked {
+        return;
+    }
+    let int_type = unsafe { llvm::core::LLVMTypeOf(a) };
+    let width = unsafe { llvm::core::LLVMGetIntTypeWidth(int_type) };
+    let (zero, min, minus_one) = unsafe {
+        (
+            llvm::core::LLVMConstInt(int_type, 0, 0),
+            llvm::core::LLVMConstInt(int_type, 1 << (width - 1), 0),
+            llvm::core::LLVMConstAllOnes(int_type),
+        )
+    };
+
+    let name = CString::new("non_zero").unwrap();
+    let ok = unsafe {
+        llvm::core::LLVMBuildICmp(
+            cc.builder,
+            llvm::LLVMIntPredicate::LLVMIntNE,
+            b,
+            zero,
+            name.as_ptr(),
+        )
+    };
+    let message = format!("{}: division of %d by zero", func_name);
+    check::<Gc>(cc, ok, &message, vec![printf_arg(cc, a)], sp);
+
+    let ok = unsafe {
+        let min_name = CString::new("is_min").unwrap();
+        let is_min = llvm::core::LLVMBuildICmp(
+            cc.builder,
+            llvm::LLVMIntPredicate::LLVMIntEQ,
+            a,
+            min,
+            min_name.as_ptr(),
+        );
+        let minus_one_name = CString::new("is_minus_one").unwrap();
+        let is_minus_one = llvm::core::LLVMBuildICmp(
+            cc.builder,
+            llvm::LLVMIntPredicate::LLVMIntEQ,
+            b,
+            minus_one,
+            minus_one_name.as_ptr(),
+        );
+        let overflow_name = CString::new("overflow").unwrap();
+        let overflow =
+            llvm::core::LLVMBuildAnd(cc.builder, is_min, is_minus_one, overflow_name.as_ptr());
+        let name = CString::new("no_overflow").unwrap();
+        llvm::core::LLVMBuildNot(cc.builder, overflow, name.as_ptr())
+    };
+    let message = format!("{}: overflow in %d {} %d", func_name, operator);
+    check::<Gc>(
+        cc,
+        ok,
+        &message,
+        vec![printf_arg(cc, a), printf_arg(cc, b)],
+        sp,
+    );
+}
+
+// Checks that the wrapped sum of a and b did not overflow, which is the case
+// if it has the sign of at least one of the summands.
+pub fn addition<Gc: GC>(
+    cc: &CodegenContext,
+    func_name: &str,
+    a: *mut llvm::LLVMValue,
+    b: *mut llvm::LLVMValue,
+    sum: *mut llvm::LLVMValue,
+    sp: *mut llvm::LLVMValue,
+) {
+    if cc.unchecked {
+        return;
+    }
+    let ok = unsafe {
+        let a_name = CString::new("a_sign").unwrap();
+        let a_sign = llvm::core::LLVMBuildXor(cc.builder, a, sum, a_name.as_ptr());
+        let b_name = CString::new("b_sign").unwrap();
+        let b_sign = llvm::core::LLVMBuildXor(cc.builder, b, sum, b_name.as_ptr());
+        let both_name = CString::new("sign_changed").unwrap();
+        let both = llvm::core::LLVMBuildAnd(cc.builder, a_sign, b_sign, both_name.as_ptr());
+        let zero = llvm::core::LLVMConstInt(llvm::core::LLVMTypeOf(sum), 0, 0);
+        let name = CString::new("no_overflow").unwrap();
+        llvm::core::LLVMBuildICmp(
+            cc.builder,
+            llvm::LLVMIntPredicate::LLVMIntSGE,
+            both,
+            zero,
+            name.as_ptr(),
+        )
+    };
+    let message = format!("{}: overflow in %d + %d", func_name);
+    check::<Gc>(cc, ok, &message, vec![a, b], sp);
+}
+
+// Checks that the wrapped difference of a and b did not overflow, which is the
+// case if a and b have the same sign or the difference has the sign of a.
+pub fn subtraction<Gc: GC>(
+    cc: &CodegenContext,
+    func_name: &str,
+    a: *mut llvm::LLVMValue,
+    b: *mut llvm::LLVMValue,
+    difference: *mut llvm::LLVMValue,
+    sp: *mut llvm::LLVMValue,
+) {
+    if cc.unchecked {
+        return;
+    }
+    let ok = unsafe {
+        let operands_name = CString::new("operand_signs").unwrap();
+        let operands = llvm::core::LLVMBuildXor(cc.builder, a, b, operands_name.as_ptr());
+        let a_name = CString::new("a_sign").unwrap();
+        let a_sign = llvm::core::LLVMBuildXor(cc.builder, a, difference, a_name.as_ptr());
+        let both_name = CString::new("sign_changed").unwrap();
+        let both = llvm::core::LLVMBuildAnd(cc.builder, operands, a_sign, both_name.as_ptr());
+        let zero = llvm::core::LLVMConstInt(llvm::core::LLVMTypeOf(difference), 0, 0);
+        let name = CString::new("no_overflow").unwrap();
+        llvm::core::LLVMBuildICmp(
+            cc.builder,
+            llvm::LLVMIntPredicate::LLVMIntSGE,
+            both,
+            zero,
+            name.as_ptr(),
+        )
+    };
+    let message = format!("{}: overflow in %d - %d", func_name);
+    check::<Gc>(cc, ok, &message, vec![a, b], sp);
+}
+
+// Checks that the wrapped product of a and b did not overflow by comparing it
+// to the product computed with twice the width.
+pub fn multiplication<Gc: GC>(
+    cc: &CodegenContext,
+    func_name: &str,
+    a: *mut llvm::LLVMValue,
+    b: *mut llvm::LLVMValue,
+    product: *mut llvm::LLVMValue,
+    sp: *mut llvm::LLVMValue,
+) {
+    if cc.unchecked {
+        return;
+    }
+    let ok = unsafe {
+        let width = llvm::core::LLVMGetIntTypeWidth(llvm::core::LLVMTypeOf(product));
+        let wide_type = llvm::core::LLVMIntTypeInContext(cc.context, 2 * width);
+        let a_name = CString::new("wide_a").unwrap();
+        let wide_a = llvm::core::LLVMBuildSExt(cc.builder, a, wide_type, a_name.as_ptr());
+        let b_name = CString::new("wide_b").unwrap();
+        let wide_b = llvm::core::LLVMBuildSExt(cc.builder, b, wide_type, b_name.as_ptr());
+        let exact_name = CString::new("exact_product").unwrap();
+        let exact = llvm::core::LLVMBuildMul(cc.builder, wide_a, wide_b, exact_name.as_ptr());
+        let product_name = CString::new("wide_product").unwrap();
+        let wide_product =
+            llvm::core::LLVMBuildSExt(cc.builder, product, wide_type, product_name.as_ptr());
+        let name = CString::new("no_overflow").unwrap();
+        llvm::core::LLVMBuildICmp(
+            cc.builder,
+            llvm::LLVMIntPredicate::LLVMIntEQ,
+            wide_product,
+            exact,
+            name.as_ptr(),
+        )
+    };
+    let message = format!("{}: overflow in %d * %d", func_name);
+    check::<Gc>(cc, ok, &message, vec![a, b], sp);
+}
+
+// Continues in a new block if ok is true and panics otherwise
+fn check<Gc: GC>(
+    cc: &CodegenContext,
+    ok: *mut llvm::LLVMValue,
+    message: &str,
+    mut args: Vec<*mut llvm::LLVMValue>,
+    sp: *mut llvm::LLVMValue,
+) {
+    unsafe {
+        let current_func =
+            llvm::core::LLVMGetBasicBlockParent(llvm::core::LLVMGetInsertBlock(cc.builder));
+        let failed_name = CString::new("check_failed").unwrap();
+        let failed_block = llvm::core::LLVMAppendBasicBlockInContext(
+            cc.context,
+            current_func,
+            failed_name.as_ptr(),
+        );
+        let passed_name = CString::new("check_passed").unwrap();
+        let passed_block = llvm::core::LLVMAppendBasicBlockInContext(
+            cc.context,
+            current_func,
+            passed_name.as_ptr(),
+        );
+        llvm::core::LLVMBuildCondBr(cc.builder, ok, passed_block, failed_block);
+
+        llvm::core::LLVMPositionBuilderAtEnd(cc.builder, failed_block);
+        let c_str = CString::new(message).unwrap();
+        let name = CString::new(".str").unwrap();
+        let format =
+            llvm::core::LLVMBuildGlobalStringPtr(cc.builder, c_str.as_ptr(), name.as_ptr());
+        let mut params = vec![format];
+        params.append(&mut args);
+        create_func_call::<Gc>(
+            cc,
+            &Rc::new(BuildIn::arena_panic.as_str().to_string()),
+            &mut params,
+            sp,
+        );
+        llvm::core::LLVMBuildUnreachable(cc.builder);
+
+        llvm::core::LLVMPositionBuilderAtEnd(cc.builder, passed_block);
+    }
+}
+
+// Variadic arguments are passed as at least an int. u8 values are shown
+// unsigned like in the messages of the interpreter.
+fn printf_arg(cc: &CodegenContext, value: *mut llvm::LLVMValue) -> *mut llvm::LLVMValue {
+    unsafe {
+        let int_type = llvm::core::LLVMTypeOf(value);
+        if llvm::core::LLVMGetIntTypeWidth(int_type) >= 32 {
+            return value;
+        }
+        let name = CString::new("printf_arg").unwrap();
+        llvm::core::LLVMBuildZExt(
+            cc.builder,
+            value,
+            llvm::core::LLVMInt32TypeInContext(cc.context),
+            name.as_ptr(),
+        )
+    }
+}
@@ -5,6 +5,7 @@ use std::rc::Rc;
 
 use crate::binary::BinExpr::*;
 use crate::binary::*;
+use crate::codegen::array;
 use crate::codegen::build_in::BuildIn::stack_alloc;
 use crate::codegen::function::create_func_call;
 use crate::codegen::garbage_collection::GC;
@@ -21,6 +22,7 @@ pub fn build_expression<'input, Gc: GC>(
     ast: &'input TypedExpr,
 ) -> *mut llvm::LLVMValue {
     match &ast.expr {
+        Array(elements) => build_array::<Gc>(cc, current_func, vars, current_sp, &ast.expr_type, elements),
         Const(c) => build_const(cc, c),
         FuncCall(func_id, params) => {
             let (computed_params, stored_params, new_sp) = compute_params::<Gc>(cc, current_func, vars, current_sp, params);
@@ -29,6 +31,17 @@ pub fn build_expression<'input, Gc: GC>(
         }
         GetTypeCaseField(obj, case, field_index) => {
             let obj_ptr = build_expression::<Gc>(cc, current_func, vars, current_sp, obj);
+            if is_unboxed_tuple(&obj.expr_type) {
+                let field_name = CString::new("tuple_field".to_string()).unwrap();
+                return unsafe {
+                    llvm::core::LLVMBuildExtractValue(
+                        cc.builder,
+                        obj_ptr,
+                        (*field_index).try_into().unwrap(),
+                        field_name.as_ptr(),
+                    )
+                };
+            }
 
             let (_, case_fields, _) = get_case_id_case_indices_pointer_indices::<Gc>(
                 cc.context,
@@ -250,9 +263,10 @@ fn build_if<'input, Gc: GC>(
     // Continuation:
     unsafe { llvm::core::LLVMPositionBuilderAtEnd(cc.builder, continuation_block) };
     match (then_ast.expr_type.as_str(), else_ast.expr_type.as_str()) {
-        (VOID_TYPE, VOID_TYPE) | (VOID_TYPE, EXIT_TYPE) | (EXIT_TYPE, VOID_TYPE) => {
-            std::ptr::null_mut()
-        }
+        (VOID_TYPE, VOID_TYPE)
+        | (VOID_TYPE, EXIT_TYPE)
+        | (EXIT_TYPE, VOID_TYPE)
+        | (EXIT_TYPE, EXIT_TYPE) => std::ptr::null_mut(),
         (EXIT_TYPE, _) => {
             let phi_name = CString::new("res").unwrap();
             let phi = unsafe { llvm::core::LLVMBuildPhi(cc.builder, ret_type, phi_name.as_ptr()) };
@@ -301,7 +315,7 @@ fn build_if<'input, Gc: GC>(
     }
 }
 
-fn get_next_stack_element<Gc: GC>(
+pub fn get_next_stack_element<Gc: GC>(
     current_sp: *mut llvm::LLVMValue,
     cc: &CodegenContext,
     var_name: &CString,
@@ -391,6 +405,25 @@ fn build_type_case<'input, Gc: GC>(
 ) -> *mut llvm::LLVMValue {
     let (computed_params, stored_params, sp) = compute_params::<Gc>(cc, current_func, vars, current_sp, fields);
 
+    if is_unboxed_tuple(ty) {
+        // Tuples without pointers are values and need no allocation
+        let fs = load_params(cc, computed_params, stored_params);
+        let tuple_name = CString::new("tuple".to_string()).unwrap();
+        let llvm_type = type_to_llvm_type(cc.context, &cc.llvm_structs, ty);
+        return fs.into_iter().enumerate().fold(
+            unsafe { llvm::core::LLVMGetUndef(llvm_type) },
+            |tuple, (index, field)| unsafe {
+                llvm::core::LLVMBuildInsertValue(
+                    cc.builder,
+                    tuple,
+                    field,
+                    index.try_into().unwrap(),
+                    tuple_name.as_ptr(),
+                )
+            },
+        );
+    }
+
     let llvm_type = type_to_llvm_type(cc.context, &cc.llvm_structs, ty);
     let size = get_struct_size(&cc.llvm_structs, ty);
     let malloc_ret = Gc::type_allocation(cc, size, sp);
@@ -463,6 +496,29 @@ fn build_type_case<'input, Gc: GC>(
     heap_ptr
 }
 
+fn build_array<'input, Gc: GC>(
+    cc: &CodegenContext,
+    current_func: *mut llvm::LLVMValue,
+    vars: &mut HashMap<&'input str, *mut llvm::LLVMValue>,
+    current_sp: *mut llvm::LLVMValue,
+    ty: &Rc<String>,
+    elements: &'input Vec<TypedExpr<'input>>,
+) -> *mut llvm::LLVMValue {
+    let (computed_params, stored_params, sp) = compute_params::<Gc>(cc, current_func, vars, current_sp, elements);
+
+    let int32_type =
+        type_to_llvm_type(cc.context, &cc.llvm_structs, &Rc::new(I32_TYPE.to_string()));
+    let len = unsafe { llvm::core::LLVMConstInt(int32_type, elements.len().try_into().unwrap(), 0) };
+    let heap_ptr = array::alloc::<Gc>(cc, ty, len, sp);
+
+    let es = load_params(cc, computed_params, stored_params);
+    for (index, element) in es.into_iter().enumerate() {
+        let index = unsafe { llvm::core::LLVMConstInt(int32_type, index.try_into().unwrap(), 0) };
+        array::store::<Gc>(cc, heap_ptr, index, element);
+    }
+    heap_ptr
+}
+
 fn compute_params<'input, Gc: GC>(
     cc: &CodegenContext,
     current_func: *mut llvm::LLVMValue,
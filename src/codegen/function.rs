@@ -4,7 +4,7 @@ use crate::codegen::build_in::{get_build_in_func_call, get_linked_func_signature
 use crate::codegen::expression::build_expression;
 use crate::codegen::garbage_collection::GC;
 use crate::codegen::CodegenContext;
-use crate::types::{type_to_llvm_type, VOID_PTR_TYPE, VOID_TYPE};
+use crate::types::{type_to_llvm_type, BOOL_TYPE, U8_TYPE, VOID_PTR_TYPE, VOID_TYPE};
 
 use llvm_sys as llvm;
 use std::collections::HashMap;
@@ -44,6 +44,19 @@ pub fn create_func_call<Gc: GC>(
             false,
         );
         append_func_call(cc, new_func, computed_params, sp)
+    } else if let Some(extern_def) = cc.binary.externs.get(func_id) {
+        // Extern functions are linked C functions without the stack pointer
+        let new_func = init_function(
+            cc,
+            func_id.as_str(),
+            &extern_def.args,
+            &extern_def.ret_type,
+            false,
+            true,
+            false,
+        );
+        add_zero_ext(cc, new_func, &extern_def.args, &extern_def.ret_type);
+        append_func_call(cc, new_func, computed_params, sp)
     } else {
         match get_build_in_func_call::<Gc>(cc, func_id, computed_params, sp) {
             Some(val) => val,
@@ -307,6 +320,30 @@ fn init_function(
     }
 }
 
+// C expects u8 and bool values to be extended to the width of its registers
+fn add_zero_ext(
+    cc: &CodegenContext,
+    func: *mut llvm::LLVMValue,
+    args: &[Rc<String>],
+    ret_type: &Rc<String>,
+) {
+    let zero_ext_name = CString::new("zeroext").unwrap();
+    let narrow = |ty: &Rc<String>| [U8_TYPE, BOOL_TYPE].contains(&ty.as_str());
+    unsafe {
+        let zero_ext = llvm::core::LLVMGetEnumAttributeKindForName(zero_ext_name.as_ptr(), 7);
+        let attr = llvm::core::LLVMCreateEnumAttribute(cc.context, zero_ext, 0);
+        if narrow(ret_type) {
+            llvm::core::LLVMAddAttributeAtIndex(func, llvm::LLVMAttributeReturnIndex, attr);
+        }
+        for (index, arg) in args.iter().enumerate() {
+            if narrow(arg) {
+                let index = (index + 1).try_into().unwrap();
+                llvm::core::LLVMAddAttributeAtIndex(func, index, attr);
+            }
+        }
+    }
+}
+
 fn create_entry(cc: &CodegenContext, func: *mut llvm::LLVMValue) {
     let entry_name = CString::new("entry").unwrap();
     unsafe {
@@ -49,7 +49,7 @@ impl GC for Spill {
         create_func_call::<Self>(
             cc,
             &Rc::new(BuildIn::init_heap.as_str().to_string()),
-            &mut Vec::new(),
+            &mut vec![cc.gc_stats_format],
             std::ptr::null_mut(),
         );
     }
@@ -117,7 +117,7 @@ impl GC for ARC {
         create_func_call::<Self>(
             cc,
             &Rc::new(BuildIn::init_heap.as_str().to_string()),
-            &mut Vec::new(),
+            &mut vec![cc.gc_stats_format],
             std::ptr::null_mut(),
         );
     }
@@ -226,7 +226,7 @@ impl GC for TGC {
         create_func_call::<Self>(
             cc,
             &Rc::new(BuildIn::tgc_init_heap.as_str().to_string()),
-            &mut Vec::new(),
+            &mut vec![cc.gc_stats_format],
             std::ptr::null_mut(),
         );
     }
@@ -1,24 +1,69 @@
+mod array;
 pub mod build_in;
+mod check;
 mod expression;
 mod function;
-pub mod garbage_collection;
+pub(crate) mod garbage_collection;
 mod module;
+mod string;
 
 use llvm_sys as llvm;
 use std::collections::HashMap;
-use std::ffi::CString;
-use std::ptr;
+use std::ffi::CStr;
 use std::rc::Rc;
 
 use crate::binary::Binary;
 use crate::codegen::garbage_collection::GC;
 
-pub fn codegen<Gc: GC>(binary: Binary, out_ll: &str, print_llvm_code: bool, profiling_frequency: u64) {
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Backend {
+    Arc,
+    Tgc,
+    Spill,
+}
+
+impl Backend {
+    pub const ALL: [Backend; 3] = [Backend::Arc, Backend::Tgc, Backend::Spill];
+
+    pub fn flag(&self) -> &'static str {
+        match self {
+            Backend::Arc => "--arc",
+            Backend::Tgc => "--tgc",
+            Backend::Spill => "--spill",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Backend::Arc => "arc",
+            Backend::Tgc => "tgc",
+            Backend::Spill => "spill",
+        }
+    }
+}
+
+// Returns the LLVM IR of the program or the error reported by the LLVM
+// verifier
+pub(crate) fn codegen<Gc: GC>(
+    binary: Binary,
+    profiling_frequency: u64,
+    gc_stats_format: u64,
+    unchecked: bool,
+) -> Result<String, String> {
     let (context, builder) = llvm_setup();
 
-    let module = module::build_module::<Gc>(context, builder, binary, profiling_frequency);
+    let module = module::build_module::<Gc>(
+        context,
+        builder,
+        binary,
+        profiling_frequency,
+        gc_stats_format,
+        unchecked,
+    );
 
-    llvm_cleanup(context, module, builder, out_ll, print_llvm_code);
+    let result = verify_and_print(module);
+    llvm_cleanup(context, module, builder);
+    result
 }
 
 fn llvm_setup() -> (*mut llvm::LLVMContext, *mut llvm::LLVMBuilder) {
@@ -33,22 +78,35 @@ fn llvm_cleanup(
     context: *mut llvm::LLVMContext,
     module: *mut llvm::LLVMModule,
     builder: *mut llvm::LLVMBuilder,
-    out_ll: &str,
-    print_llvm_code: bool,
 ) {
-    let stdout = CString::new("/dev/stdout").unwrap();
-    let out_ll = CString::new(out_ll).unwrap();
     unsafe {
-        if print_llvm_code {
-            llvm::core::LLVMPrintModuleToFile(module, stdout.as_ptr(), ptr::null_mut());
-        }
-        llvm::core::LLVMPrintModuleToFile(module, out_ll.as_ptr(), ptr::null_mut());
         llvm::core::LLVMDisposeBuilder(builder);
         llvm::core::LLVMDisposeModule(module);
         llvm::core::LLVMContextDispose(context);
     }
 }
 
+fn verify_and_print(module: *mut llvm::LLVMModule) -> Result<String, String> {
+    unsafe {
+        let mut message = std::ptr::null_mut();
+        let failed = llvm::analysis::LLVMVerifyModule(
+            module,
+            llvm::analysis::LLVMVerifierFailureAction::LLVMReturnStatusAction,
+            &mut message,
+        ) != 0;
+        let result = if failed {
+            Err(CStr::from_ptr(message).to_string_lossy().into_owned())
+        } else {
+            let code = llvm::core::LLVMPrintModuleToString(module);
+            let result = CStr::from_ptr(code).to_string_lossy().into_owned();
+            llvm::core::LLVMDisposeMessage(code);
+            Ok(result)
+        };
+        llvm::core::LLVMDisposeMessage(message);
+        result
+    }
+}
+
 pub struct CodegenContext<'input> {
     pub binary: Binary<'input>,
     pub llvm_module: *mut llvm::LLVMModule,
@@ -56,4 +114,7 @@ pub struct CodegenContext<'input> {
     pub builder: *mut llvm::LLVMBuilder,
     pub llvm_structs: HashMap<Rc<String>, *mut llvm::LLVMType>,
     pub profiling_frequency: *mut llvm::LLVMValue,
+    pub gc_stats_format: *mut llvm::LLVMValue,
+    // Whether the runtime bounds and overflow checks are left out
+    pub unchecked: bool,
 }
@@ -13,12 +13,20 @@ pub fn build_module<'input, Gc: GC>(
     builder: *mut llvm::LLVMBuilder,
     binary: Binary<'input>,
     profiling_frequency: u64,
+    gc_stats_format: u64,
+    unchecked: bool,
 ) -> *mut llvm::LLVMModule {
     unsafe {
         let module_name = CString::new("ArenaBinary").unwrap();
-        let llvm_module = llvm::core::LLVMModuleCreateWithName(module_name.as_ptr());
+        let llvm_module =
+            llvm::core::LLVMModuleCreateWithNameInContext(module_name.as_ptr(), context);
         let llvm_structs = create_structs::<Gc>(&binary, context);
         let profiling_frequency = llvm::core::LLVMConstInt(llvm::core::LLVMInt64TypeInContext(context), profiling_frequency, 0);
+        let gc_stats_format = llvm::core::LLVMConstInt(
+            llvm::core::LLVMInt64TypeInContext(context),
+            gc_stats_format,
+            0,
+        );
         let cc = CodegenContext {
             binary,
             llvm_module,
@@ -26,6 +34,8 @@ pub fn build_module<'input, Gc: GC>(
             builder,
             llvm_structs,
             profiling_frequency,
+            gc_stats_format,
+            unchecked,
         };
         let main_func = &cc
             .binary
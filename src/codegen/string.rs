@@ -0,0 +1,522 @@
+// Code generation of the build in string functions. A string is an object on
+// the heap with the GC header, a case id without pointers, the length and its
+// bytes, which are not terminated by NUL. Strings are allocated through the
+// GC like user defined types. Like functions, the build in functions drop
+// the strings passed to them.
+
+use llvm_sys as llvm;
+use std::ffi::CString;
+use std::rc::Rc;
+
+use crate::codegen::build_in::BuildIn;
+use crate::codegen::check;
+use crate::codegen::expression::get_next_stack_element;
+use crate::codegen::function::create_func_call;
+use crate::codegen::garbage_collection::GC;
+use crate::codegen::CodegenContext;
+use crate::types::{get_struct_size, type_to_llvm_type, I32_TYPE, I64_TYPE, STRING_TYPE};
+
+pub fn from_str<Gc: GC>(
+    cc: &CodegenContext,
+    s: *mut llvm::LLVMValue,
+    sp: *mut llvm::LLVMValue,
+) -> *mut llvm::LLVMValue {
+    let c_len = call::<Gc>(cc, BuildIn::strlen, vec![s], sp);
+    let name = CString::new("len").unwrap();
+    let len =
+        unsafe { llvm::core::LLVMBuildTrunc(cc.builder, c_len, int32_type(cc), name.as_ptr()) };
+    let string = alloc::<Gc>(cc, len, sp);
+    copy_bytes::<Gc>(cc, bytes::<Gc>(cc, string), s, len, sp);
+    string
+}
+
+pub fn from_u8<Gc: GC>(
+    cc: &CodegenContext,
+    c: *mut llvm::LLVMValue,
+    sp: *mut llvm::LLVMValue,
+) -> *mut llvm::LLVMValue {
+    let string = alloc::<Gc>(cc, const_i32(cc, 1), sp);
+    unsafe { llvm::core::LLVMBuildStore(cc.builder, c, bytes::<Gc>(cc, string)) };
+    string
+}
+
+pub fn from_i32<Gc: GC>(
+    cc: &CodegenContext,
+    i: *mut llvm::LLVMValue,
+    sp: *mut llvm::LLVMValue,
+) -> *mut llvm::LLVMValue {
+    let len = call::<Gc>(cc, BuildIn::i32_string_len, vec![i], sp);
+    let string = alloc::<Gc>(cc, len, sp);
+    call::<Gc>(
+        cc,
+        BuildIn::i32_string_write,
+        vec![bytes::<Gc>(cc, string), len, i],
+        sp,
+    );
+    string
+}
+
+pub fn print<Gc: GC>(
+    cc: &CodegenContext,
+    string: *mut llvm::LLVMValue,
+    sp: *mut llvm::LLVMValue,
+) -> *mut llvm::LLVMValue {
+    // The bytes are not terminated by NUL, so printf needs the length
+    let c_str = CString::new("%.*s").unwrap();
+    let name = CString::new(".str").unwrap();
+    let format =
+        unsafe { llvm::core::LLVMBuildGlobalStringPtr(cc.builder, c_str.as_ptr(), name.as_ptr()) };
+    let len = len_of::<Gc>(cc, string);
+    let res = call::<Gc>(
+        cc,
+        BuildIn::printf,
+        vec![format, len, bytes::<Gc>(cc, string)],
+        sp,
+    );
+    Gc::type_ptr_drop(cc, string, sp);
+    res
+}
+
+pub fn len<Gc: GC>(
+    cc: &CodegenContext,
+    string: *mut llvm::LLVMValue,
+    sp: *mut llvm::LLVMValue,
+) -> *mut llvm::LLVMValue {
+    let len = len_of::<Gc>(cc, string);
+    Gc::type_ptr_drop(cc, string, sp);
+    len
+}
+
+pub fn char_at<Gc: GC>(
+    cc: &CodegenContext,
+    string: *mut llvm::LLVMValue,
+    index: *mut llvm::LLVMValue,
+    sp: *mut llvm::LLVMValue,
+) -> *mut llvm::LLVMValue {
+    check::index::<Gc>(
+        cc,
+        "char_at",
+        "a string",
+        index,
+        len_of::<Gc>(cc, string),
+        false,
+        sp,
+    );
+    let name = CString::new("char_at").unwrap();
+    let c = unsafe {
+        llvm::core::LLVMBuildLoad(
+            cc.builder,
+            byte_ptr(cc, bytes::<Gc>(cc, string), index),
+            name.as_ptr(),
+        )
+    };
+    Gc::type_ptr_drop(cc, string, sp);
+    c
+}
+
+pub fn concat<Gc: GC>(
+    cc: &CodegenContext,
+    a: *mut llvm::LLVMValue,
+    b: *mut llvm::LLVMValue,
+    sp: *mut llvm::LLVMValue,
+) -> *mut llvm::LLVMValue {
+    let a_len = len_of::<Gc>(cc, a);
+    let b_len = len_of::<Gc>(cc, b);
+    let name = CString::new("len").unwrap();
+    let len = unsafe { llvm::core::LLVMBuildAdd(cc.builder, a_len, b_len, name.as_ptr()) };
+    let (a_slot, new_sp) = save::<Gc>(cc, a, sp);
+    let (b_slot, new_sp) = save::<Gc>(cc, b, new_sp);
+    let string = alloc::<Gc>(cc, len, new_sp);
+    let a = load(cc, a_slot);
+    let b = load(cc, b_slot);
+    let string_bytes = bytes::<Gc>(cc, string);
+    copy_bytes::<Gc>(cc, string_bytes, bytes::<Gc>(cc, a), a_len, sp);
+    copy_bytes::<Gc>(
+        cc,
+        byte_ptr(cc, string_bytes, a_len),
+        bytes::<Gc>(cc, b),
+        b_len,
+        sp,
+    );
+    Gc::type_ptr_drop(cc, a, sp);
+    Gc::type_ptr_drop(cc, b, sp);
+    string
+}
+
+// Indices outside of the string are moved to its start or end
+pub fn slice<Gc: GC>(
+    cc: &CodegenContext,
+    string: *mut llvm::LLVMValue,
+    start: *mut llvm::LLVMValue,
+    end: *mut llvm::LLVMValue,
+    sp: *mut llvm::LLVMValue,
+) -> *mut llvm::LLVMValue {
+    let string_len = len_of::<Gc>(cc, string);
+    let start = clamp(cc, start, const_i32(cc, 0), string_len);
+    let end = clamp(cc, end, start, string_len);
+    let name = CString::new("len").unwrap();
+    let len = unsafe { llvm::core::LLVMBuildSub(cc.builder, end, start, name.as_ptr()) };
+    let (slot, new_sp) = save::<Gc>(cc, string, sp);
+    let slice = alloc::<Gc>(cc, len, new_sp);
+    let string = load(cc, slot);
+    copy_bytes::<Gc>(
+        cc,
+        bytes::<Gc>(cc, slice),
+        byte_ptr(cc, bytes::<Gc>(cc, string), start),
+        len,
+        sp,
+    );
+    Gc::type_ptr_drop(cc, string, sp);
+    slice
+}
+
+// Index of the first occurrence of pattern in the string or -1
+pub fn find<Gc: GC>(
+    cc: &CodegenContext,
+    string: *mut llvm::LLVMValue,
+    pattern: *mut llvm::LLVMValue,
+    sp: *mut llvm::LLVMValue,
+) -> *mut llvm::LLVMValue {
+    let index = call::<Gc>(
+        cc,
+        BuildIn::string_find,
+        vec![
+            bytes::<Gc>(cc, string),
+            len_of::<Gc>(cc, string),
+            bytes::<Gc>(cc, pattern),
+            len_of::<Gc>(cc, pattern),
+        ],
+        sp,
+    );
+    Gc::type_ptr_drop(cc, string, sp);
+    Gc::type_ptr_drop(cc, pattern, sp);
+    index
+}
+
+pub fn to_i32<Gc: GC>(
+    cc: &CodegenContext,
+    string: *mut llvm::LLVMValue,
+    sp: *mut llvm::LLVMValue,
+) -> *mut llvm::LLVMValue {
+    let i = call::<Gc>(
+        cc,
+        BuildIn::string_to_i32,
+        vec![bytes::<Gc>(cc, string), len_of::<Gc>(cc, string)],
+        sp,
+    );
+    Gc::type_ptr_drop(cc, string, sp);
+    i
+}
+
+// Compares the strings lexicographically by their bytes
+pub fn compare<Gc: GC>(
+    cc: &CodegenContext,
+    predicate: llvm::LLVMIntPredicate,
+    a: *mut llvm::LLVMValue,
+    b: *mut llvm::LLVMValue,
+    sp: *mut llvm::LLVMValue,
+) -> *mut llvm::LLVMValue {
+    let order = call::<Gc>(
+        cc,
+        BuildIn::string_compare,
+        vec![
+            bytes::<Gc>(cc, a),
+            len_of::<Gc>(cc, a),
+            bytes::<Gc>(cc, b),
+            len_of::<Gc>(cc, b),
+        ],
+        sp,
+    );
+    let name = CString::new("cmptmp").unwrap();
+    let cmp = unsafe {
+        llvm::core::LLVMBuildICmp(
+            cc.builder,
+            predicate,
+            order,
+            const_i32(cc, 0),
+            name.as_ptr(),
+        )
+    };
+    Gc::type_ptr_drop(cc, a, sp);
+    Gc::type_ptr_drop(cc, b, sp);
+    cmp
+}
+
+// Reads the next line of stdin including its newline, the string is empty at
+// the end of the input
+pub fn read_line<Gc: GC>(cc: &CodegenContext, sp: *mut llvm::LLVMValue) -> *mut llvm::LLVMValue {
+    let len = call::<Gc>(cc, BuildIn::io_read_line, Vec::new(), sp);
+    take_pending::<Gc>(cc, len, sp)
+}
+
+// The content of the file or an empty string if it can not be read, which
+// io_error tells apart
+pub fn read_file<Gc: GC>(
+    cc: &CodegenContext,
+    path: *mut llvm::LLVMValue,
+    sp: *mut llvm::LLVMValue,
+) -> *mut llvm::LLVMValue {
+    let len = call::<Gc>(
+        cc,
+        BuildIn::io_read_file,
+        vec![bytes::<Gc>(cc, path), len_of::<Gc>(cc, path)],
+        sp,
+    );
+    Gc::type_ptr_drop(cc, path, sp);
+    take_pending::<Gc>(cc, len, sp)
+}
+
+pub fn write_file<Gc: GC>(
+    cc: &CodegenContext,
+    path: *mut llvm::LLVMValue,
+    content: *mut llvm::LLVMValue,
+    sp: *mut llvm::LLVMValue,
+) -> *mut llvm::LLVMValue {
+    let res = call::<Gc>(
+        cc,
+        BuildIn::io_write_file,
+        vec![
+            bytes::<Gc>(cc, path),
+            len_of::<Gc>(cc, path),
+            bytes::<Gc>(cc, content),
+            len_of::<Gc>(cc, content),
+        ],
+        sp,
+    );
+    Gc::type_ptr_drop(cc, path, sp);
+    Gc::type_ptr_drop(cc, content, sp);
+    res
+}
+
+// The message of the last failed I/O operation or an empty string if the last
+// one succeeded
+pub fn io_error<Gc: GC>(cc: &CodegenContext, sp: *mut llvm::LLVMValue) -> *mut llvm::LLVMValue {
+    let len = call::<Gc>(cc, BuildIn::io_last_error, Vec::new(), sp);
+    take_pending::<Gc>(cc, len, sp)
+}
+
+pub fn eprint<Gc: GC>(
+    cc: &CodegenContext,
+    string: *mut llvm::LLVMValue,
+    sp: *mut llvm::LLVMValue,
+) -> *mut llvm::LLVMValue {
+    let res = call::<Gc>(
+        cc,
+        BuildIn::io_eprint,
+        vec![bytes::<Gc>(cc, string), len_of::<Gc>(cc, string)],
+        sp,
+    );
+    Gc::type_ptr_drop(cc, string, sp);
+    res
+}
+
+// Stops the program with the location of the panic and the message
+pub fn panic<Gc: GC>(
+    cc: &CodegenContext,
+    location: *mut llvm::LLVMValue,
+    message: *mut llvm::LLVMValue,
+    sp: *mut llvm::LLVMValue,
+) -> *mut llvm::LLVMValue {
+    call::<Gc>(
+        cc,
+        BuildIn::user_panic,
+        vec![
+            location,
+            bytes::<Gc>(cc, message),
+            len_of::<Gc>(cc, message),
+        ],
+        sp,
+    )
+}
+
+// Moves the bytes the runtime read by the last I/O operation into a new string
+fn take_pending<Gc: GC>(
+    cc: &CodegenContext,
+    len: *mut llvm::LLVMValue,
+    sp: *mut llvm::LLVMValue,
+) -> *mut llvm::LLVMValue {
+    let string = alloc::<Gc>(cc, len, sp);
+    call::<Gc>(cc, BuildIn::io_take, vec![bytes::<Gc>(cc, string)], sp);
+    string
+}
+
+// Allocates a string of the given length, its bytes still have to be written
+fn alloc<Gc: GC>(
+    cc: &CodegenContext,
+    len: *mut llvm::LLVMValue,
+    sp: *mut llvm::LLVMValue,
+) -> *mut llvm::LLVMValue {
+    let string_type = Rc::new(STRING_TYPE.to_string());
+    let name = CString::new("size").unwrap();
+    let size = unsafe {
+        let len = llvm::core::LLVMBuildZExt(cc.builder, len, int64_type(cc), name.as_ptr());
+        llvm::core::LLVMBuildAdd(
+            cc.builder,
+            get_struct_size(&cc.llvm_structs, &string_type),
+            len,
+            name.as_ptr(),
+        )
+    };
+    let malloc_ret = Gc::type_allocation(cc, size, sp);
+    let struct_name = CString::new(format!("{}*", STRING_TYPE)).unwrap();
+    let string = unsafe {
+        llvm::core::LLVMBuildBitCast(
+            cc.builder,
+            malloc_ret,
+            type_to_llvm_type(cc.context, &cc.llvm_structs, &string_type),
+            struct_name.as_ptr(),
+        )
+    };
+    Gc::init_header(cc, string, size);
+    // Strings have a single case without pointers
+    unsafe {
+        llvm::core::LLVMBuildStore(cc.builder, const_i32(cc, 0), field::<Gc>(cc, string, 0));
+        llvm::core::LLVMBuildStore(cc.builder, len, field::<Gc>(cc, string, 1));
+    }
+    string
+}
+
+// Puts the string on the arena stack for the case that the next allocation
+// leads to a garbage collection. Returns the stack element and the new SP.
+fn save<Gc: GC>(
+    cc: &CodegenContext,
+    string: *mut llvm::LLVMValue,
+    sp: *mut llvm::LLVMValue,
+) -> (*mut llvm::LLVMValue, *mut llvm::LLVMValue) {
+    let name = CString::new("saved_string").unwrap();
+    let (slot, new_sp) =
+        get_next_stack_element::<Gc>(sp, cc, &name, &Rc::new(STRING_TYPE.to_string()));
+    unsafe { llvm::core::LLVMBuildStore(cc.builder, string, slot) };
+    (slot, new_sp)
+}
+
+fn load(cc: &CodegenContext, slot: *mut llvm::LLVMValue) -> *mut llvm::LLVMValue {
+    let name = CString::new("saved_string").unwrap();
+    unsafe { llvm::core::LLVMBuildLoad(cc.builder, slot, name.as_ptr()) }
+}
+
+// Pointer to the field after the GC header with the given index, 0 is the
+// case id and 1 the length
+fn field<Gc: GC>(
+    cc: &CodegenContext,
+    string: *mut llvm::LLVMValue,
+    index: u64,
+) -> *mut llvm::LLVMValue {
+    let name = CString::new("string_field").unwrap();
+    unsafe {
+        llvm::core::LLVMBuildGEP(
+            cc.builder,
+            string,
+            vec![
+                const_i32(cc, 0),
+                const_i32(cc, Gc::get_type_header_length() + index),
+            ]
+            .as_mut_ptr(),
+            2,
+            name.as_ptr(),
+        )
+    }
+}
+
+fn len_of<Gc: GC>(cc: &CodegenContext, string: *mut llvm::LLVMValue) -> *mut llvm::LLVMValue {
+    let name = CString::new("len").unwrap();
+    unsafe { llvm::core::LLVMBuildLoad(cc.builder, field::<Gc>(cc, string, 1), name.as_ptr()) }
+}
+
+// Pointer to the first byte of the string
+fn bytes<Gc: GC>(cc: &CodegenContext, string: *mut llvm::LLVMValue) -> *mut llvm::LLVMValue {
+    let name = CString::new("bytes").unwrap();
+    unsafe {
+        llvm::core::LLVMBuildGEP(
+            cc.builder,
+            string,
+            vec![
+                const_i32(cc, 0),
+                const_i32(cc, Gc::get_type_header_length() + 2),
+                const_i32(cc, 0),
+            ]
+            .as_mut_ptr(),
+            3,
+            name.as_ptr(),
+        )
+    }
+}
+
+fn byte_ptr(
+    cc: &CodegenContext,
+    bytes: *mut llvm::LLVMValue,
+    index: *mut llvm::LLVMValue,
+) -> *mut llvm::LLVMValue {
+    let name = CString::new("byte_ptr").unwrap();
+    unsafe {
+        llvm::core::LLVMBuildGEP(
+            cc.builder,
+            bytes,
+            vec![index].as_mut_ptr(),
+            1,
+            name.as_ptr(),
+        )
+    }
+}
+
+fn copy_bytes<Gc: GC>(
+    cc: &CodegenContext,
+    dest: *mut llvm::LLVMValue,
+    src: *mut llvm::LLVMValue,
+    len: *mut llvm::LLVMValue,
+    sp: *mut llvm::LLVMValue,
+) {
+    let name = CString::new("copy_len").unwrap();
+    let len = unsafe { llvm::core::LLVMBuildZExt(cc.builder, len, int64_type(cc), name.as_ptr()) };
+    call::<Gc>(cc, BuildIn::memcpy, vec![dest, src, len], sp);
+}
+
+// value if it is between low and high, otherwise the closer one of them
+fn clamp(
+    cc: &CodegenContext,
+    value: *mut llvm::LLVMValue,
+    low: *mut llvm::LLVMValue,
+    high: *mut llvm::LLVMValue,
+) -> *mut llvm::LLVMValue {
+    let cmp_name = CString::new("cmptmp").unwrap();
+    let name = CString::new("clamped").unwrap();
+    unsafe {
+        let below = llvm::core::LLVMBuildICmp(
+            cc.builder,
+            llvm::LLVMIntPredicate::LLVMIntSLT,
+            value,
+            low,
+            cmp_name.as_ptr(),
+        );
+        let value = llvm::core::LLVMBuildSelect(cc.builder, below, low, value, name.as_ptr());
+        let above = llvm::core::LLVMBuildICmp(
+            cc.builder,
+            llvm::LLVMIntPredicate::LLVMIntSGT,
+            value,
+            high,
+            cmp_name.as_ptr(),
+        );
+        llvm::core::LLVMBuildSelect(cc.builder, above, high, value, name.as_ptr())
+    }
+}
+
+fn call<Gc: GC>(
+    cc: &CodegenContext,
+    build_in: BuildIn,
+    mut params: Vec<*mut llvm::LLVMValue>,
+    sp: *mut llvm::LLVMValue,
+) -> *mut llvm::LLVMValue {
+    create_func_call::<Gc>(cc, &Rc::new(build_in.as_str().to_string()), &mut params, sp)
+}
+
+fn const_i32(cc: &CodegenContext, i: u64) -> *mut llvm::LLVMValue {
+    unsafe { llvm::core::LLVMConstInt(int32_type(cc), i, 0) }
+}
+
+fn int32_type(cc: &CodegenContext) -> *mut llvm::LLVMType {
+    type_to_llvm_type(cc.context, &cc.llvm_structs, &Rc::new(I32_TYPE.to_string()))
+}
+
+fn int64_type(cc: &CodegenContext) -> *mut llvm::LLVMType {
+    type_to_llvm_type(cc.context, &cc.llvm_structs, &Rc::new(I64_TYPE.to_string()))
+}
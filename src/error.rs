@@ -0,0 +1,129 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::sources::display_path;
+
+#[derive(Debug)]
+pub enum Error {
+    // A source file could not be read
+    Io {
+        path: PathBuf,
+        message: String,
+    },
+    // Syntax error, line and column start at 1
+    Parse {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    // The Arena.toml manifest of the program is invalid, line starts at 1
+    Manifest {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+    // An import could not be resolved from the module at path, which is
+    // imported by the chain of modules starting at the main module. searched
+    // holds the files the import was looked for at.
+    Import {
+        path: PathBuf,
+        import: String,
+        imported_by: Vec<PathBuf>,
+        searched: Vec<PathBuf>,
+    },
+    // The modules import each other. The chain of imports starts at the main
+    // module and ends with the import that closes the cycle.
+    ImportCycle {
+        chain: Vec<PathBuf>,
+    },
+    // The type check failed, in the body of function if it is set
+    Type {
+        message: String,
+        function: Option<FunctionLocation>,
+    },
+    // LLVM rejected the generated module
+    Codegen {
+        message: String,
+    },
+    // The interpreter stopped the program
+    Runtime {
+        message: String,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io { path, message } => {
+                write!(f, "Could not read {}: {}", path.display(), message)
+            }
+            Error::Parse {
+                path,
+                line,
+                column,
+                message,
+            } => write!(f, "{}:{}:{}: {}", path.display(), line, column, message),
+            Error::Manifest {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", path.display(), line, message),
+            Error::Import {
+                path,
+                import,
+                imported_by,
+                searched,
+            } => {
+                write!(
+                    f,
+                    "{}: Could not resolve import path of {:?}",
+                    path.display(),
+                    import
+                )?;
+                if !imported_by.is_empty() {
+                    write!(f, " (imported by {})", import_chain(imported_by))?;
+                }
+                write!(f, ", searched:")?;
+                for path in searched.iter() {
+                    write!(f, "\n  {}", path.display())?;
+                }
+                Ok(())
+            }
+            Error::ImportCycle { chain } => write!(f, "Import cycle: {}", import_chain(chain)),
+            Error::Type {
+                message,
+                function: Some(function),
+            } => write!(
+                f,
+                "Type checking failed in function {} of {}: {}",
+                function.name, function.file, message
+            ),
+            Error::Type { message, .. } => write!(f, "Type checking failed: {}", message),
+            Error::Codegen { message } => write!(f, "Code generation failed: {}", message),
+            Error::Runtime { message } => write!(f, "Runtime error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+// The function a type error was found in
+#[derive(Clone, Debug)]
+pub struct FunctionLocation {
+    pub module_id: Rc<String>,
+    // The file of the module as Module::file
+    pub file: Rc<String>,
+    pub name: String,
+    // Byte offset of the name in the code of the module
+    pub location: usize,
+}
+
+fn import_chain(chain: &[PathBuf]) -> String {
+    chain
+        .iter()
+        .map(|path| display_path(path))
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
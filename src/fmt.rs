@@ -0,0 +1,71 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use arena::sources;
+
+use crate::input::FmtArgs;
+
+// Returns the exit code of the fmt command
+pub fn run_formatter(args: &FmtArgs) -> i32 {
+    let roots = if args.paths.is_empty() {
+        vec![PathBuf::from(".")]
+    } else {
+        args.paths.clone()
+    };
+    let mut files = Vec::new();
+    for root in roots.iter() {
+        collect_source_files(root, &mut files);
+    }
+    files.sort();
+
+    let mut exit_code = 0;
+    for path in files.iter() {
+        let code = match fs::read_to_string(path) {
+            Ok(code) => code,
+            Err(error) => {
+                eprintln!("Could not read {}: {}", path.display(), error);
+                exit_code = 1;
+                continue;
+            }
+        };
+        let formatted = match arena::format::format(&code) {
+            Ok(formatted) => formatted,
+            Err(error) => {
+                eprintln!("{}", sources::parse_error(path, &code, error));
+                exit_code = 1;
+                continue;
+            }
+        };
+        if formatted == code {
+            continue;
+        }
+        if args.check {
+            println!("{} is not formatted", path.display());
+            exit_code = 1;
+        } else if let Err(error) = fs::write(path, formatted) {
+            eprintln!("Could not write {}: {}", path.display(), error);
+            exit_code = 1;
+        }
+    }
+    exit_code
+}
+
+// Collects the .arena files under path, skipping hidden folders and symlinks
+fn collect_source_files(path: &Path, files: &mut Vec<PathBuf>) {
+    if path.is_dir() {
+        let entries = fs::read_dir(path)
+            .unwrap_or_else(|_| panic!("Could not read folder {}", path.display()));
+        for entry in entries {
+            let entry = entry.expect("Could not read folder entry");
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+            let symlink = entry
+                .file_type()
+                .map_or(true, |file_type| file_type.is_symlink());
+            if !hidden && !symlink {
+                collect_source_files(&entry.path(), files);
+            }
+        }
+    } else if path.extension().map_or(false, |ext| ext == "arena") {
+        files.push(path.to_path_buf());
+    }
+}
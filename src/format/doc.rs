@@ -0,0 +1,168 @@
+// Layout of formatted code, following "A prettier printer" by Philip Wadler.
+// Each group is printed on one line if it fits, otherwise its lines break.
+
+pub const INDENT: usize = 4;
+pub const WIDTH: usize = 100;
+
+pub enum Doc {
+    Text(String),
+    // A space, or a newline if the group breaks
+    Line,
+    // Nothing, or a newline if the group breaks
+    SoftLine,
+    // Always a newline, which breaks all enclosing groups
+    HardLine,
+    // A newline followed by an empty line
+    BlankLine,
+    // Printed at the end of the line, like a // comment
+    LineSuffix(String),
+    // Breaks all enclosing groups
+    BreakParent,
+    Nest(Vec<Doc>),
+    Group(Vec<Doc>),
+    Concat(Vec<Doc>),
+}
+
+pub fn text(text: impl Into<String>) -> Doc {
+    Doc::Text(text.into())
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+pub fn render(doc: &Doc) -> String {
+    let mut printer = Printer {
+        out: String::new(),
+        column: 0,
+        pending_newlines: 0,
+        pending_indent: 0,
+        line_suffix: Vec::new(),
+    };
+    let mut commands = vec![(0, Mode::Break, doc)];
+    while let Some((indent, mode, doc)) = commands.pop() {
+        match doc {
+            Doc::Text(text) => printer.text(text),
+            Doc::Line | Doc::SoftLine if mode == Mode::Flat => {
+                if matches!(doc, Doc::Line) {
+                    printer.text(" ");
+                }
+            }
+            Doc::Line | Doc::SoftLine | Doc::HardLine => printer.newlines(1, indent),
+            Doc::BlankLine => printer.newlines(2, indent),
+            Doc::LineSuffix(suffix) => printer.line_suffix.push(suffix.clone()),
+            Doc::BreakParent => {}
+            Doc::Nest(docs) => {
+                commands.extend(docs.iter().rev().map(|doc| (indent + INDENT, mode, doc)))
+            }
+            Doc::Concat(docs) => commands.extend(docs.iter().rev().map(|doc| (indent, mode, doc))),
+            Doc::Group(docs) => {
+                let mode = if mode == Mode::Flat || fits(docs, &commands, printer.column()) {
+                    Mode::Flat
+                } else {
+                    Mode::Break
+                };
+                commands.extend(docs.iter().rev().map(|doc| (indent, mode, doc)));
+            }
+        }
+    }
+    printer.finish()
+}
+
+// Whether docs fit flat on the current line, together with the commands
+// after them up to the next newline
+fn fits(docs: &[Doc], rest: &[(usize, Mode, &Doc)], column: usize) -> bool {
+    let mut width = column;
+    let mut stack: Vec<(Mode, &Doc)> = docs.iter().rev().map(|doc| (Mode::Flat, doc)).collect();
+    let mut rest = rest.iter().rev();
+    loop {
+        let (mode, doc) = match stack.pop() {
+            Some(command) => command,
+            None => match rest.next() {
+                Some((_, mode, doc)) => (*mode, *doc),
+                None => return true,
+            },
+        };
+        match doc {
+            Doc::Text(text) => {
+                width += text.lines().last().unwrap_or("").chars().count();
+                if text.contains('\n') {
+                    return mode == Mode::Break || width <= WIDTH;
+                }
+            }
+            Doc::Line | Doc::SoftLine if mode == Mode::Break => return true,
+            Doc::Line => width += 1,
+            Doc::SoftLine | Doc::LineSuffix(_) => {}
+            Doc::HardLine | Doc::BlankLine | Doc::BreakParent => return mode == Mode::Break,
+            Doc::Nest(docs) | Doc::Group(docs) | Doc::Concat(docs) => {
+                stack.extend(docs.iter().rev().map(|doc| (mode, doc)))
+            }
+        }
+        if width > WIDTH {
+            return false;
+        }
+    }
+}
+
+struct Printer {
+    out: String,
+    column: usize,
+    // Newlines are written before the next text, so that consecutive lines
+    // do not add empty lines
+    pending_newlines: usize,
+    pending_indent: usize,
+    line_suffix: Vec<String>,
+}
+
+impl Printer {
+    fn column(&self) -> usize {
+        match self.pending_newlines {
+            0 => self.column,
+            _ => self.pending_indent,
+        }
+    }
+
+    fn newlines(&mut self, count: usize, indent: usize) {
+        self.pending_newlines = self.pending_newlines.max(count);
+        self.pending_indent = indent;
+    }
+
+    fn text(&mut self, text: &str) {
+        if self.pending_newlines > 0 {
+            // Spaces at the start of a line are replaced by the indentation
+            if text.trim().is_empty() {
+                return;
+            }
+            self.end_line();
+            if !self.out.is_empty() {
+                self.out.push_str(&"\n".repeat(self.pending_newlines));
+            }
+            self.out.push_str(&" ".repeat(self.pending_indent));
+            self.column = self.pending_indent;
+            self.pending_newlines = 0;
+        }
+        self.out.push_str(text);
+        match text.rfind('\n') {
+            Some(newline) => self.column = text[newline + 1..].chars().count(),
+            None => self.column += text.chars().count(),
+        }
+    }
+
+    fn end_line(&mut self) {
+        for suffix in std::mem::take(&mut self.line_suffix) {
+            self.out.push_str(&suffix);
+        }
+        let trimmed = self.out.trim_end_matches(' ').len();
+        self.out.truncate(trimmed);
+    }
+
+    fn finish(mut self) -> String {
+        self.end_line();
+        if !self.out.is_empty() {
+            self.out.push('\n');
+        }
+        self.out
+    }
+}
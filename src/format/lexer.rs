@@ -0,0 +1,182 @@
+use crate::parser::SyntaxError;
+
+pub const KEYWORDS: [&str; 18] = [
+    "import", "as", "type", "derive", "fn", "trait", "impl", "for", "let", "match", "if", "then",
+    "else", "true", "false", "with", "extern", "pub",
+];
+
+// Longer punctuation first, so that it is matched before its prefixes
+const PUNCTUATION: [&str; 29] = [
+    "->", "=>", "::", "||", "&&", "==", "!=", "<=", ">=", "{", "}", "(", ")", "[", "]", ",", ":",
+    "=", ";", ".", "<", ">", "+", "-", "*", "/", "%", "!", "_",
+];
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TokenKind {
+    Keyword,
+    Id,
+    // i32 and u8 literals, including characters like 'a'
+    Integer,
+    Str,
+    Punctuation,
+    Eof,
+}
+
+#[derive(Debug)]
+pub struct Comment<'input> {
+    pub text: &'input str,
+    pub newline_before: bool,
+    pub newline_after: bool,
+    pub blank_line_before: bool,
+}
+
+impl Comment<'_> {
+    pub fn is_line_comment(&self) -> bool {
+        self.text.starts_with("//")
+    }
+}
+
+// A token with the comments around it. Comments on the line of the previous
+// token belong to that token, all others to the next token.
+#[derive(Debug)]
+pub struct Token<'input> {
+    pub kind: TokenKind,
+    pub text: &'input str,
+    pub leading: Vec<Comment<'input>>,
+    pub trailing: Vec<Comment<'input>>,
+    // There is a blank line between the token and the comment or token before it
+    pub blank_line_before: bool,
+}
+
+impl<'input> Token<'input> {
+    pub fn is(&self, text: &str) -> bool {
+        self.text == text && self.kind != TokenKind::Str
+    }
+
+    // A token that does not appear in the code, like an added trailing comma
+    pub fn synthetic(text: &'input str) -> Self {
+        Token {
+            kind: TokenKind::Punctuation,
+            text,
+            leading: Vec::new(),
+            trailing: Vec::new(),
+            blank_line_before: false,
+        }
+    }
+}
+
+// Splits the code into tokens, ending with an Eof token that holds the
+// comments at the end of the code
+pub fn lex(code: &str) -> Result<Vec<Token<'_>>, SyntaxError> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut comments: Vec<Comment> = Vec::new();
+    let mut newlines = 0;
+    let mut position = 0;
+    while position < code.len() {
+        let rest = &code[position..];
+        let c = rest.chars().next().unwrap();
+        if c.is_whitespace() {
+            if c == '\n' {
+                newlines += 1;
+            }
+            position += c.len_utf8();
+            continue;
+        }
+
+        let comment_len = if rest.starts_with("//") {
+            Some(rest.find(['\n', '\r']).unwrap_or(rest.len()))
+        } else if let Some(body) = rest.strip_prefix("/*") {
+            Some(body.find("*/").map(|end| end + 4).ok_or(SyntaxError {
+                location: position,
+                message: "Unterminated comment".to_string(),
+            })?)
+        } else {
+            None
+        };
+        if let Some(len) = comment_len {
+            if let Some(previous) = comments.last_mut() {
+                previous.newline_after = newlines > 0;
+            }
+            let comment = Comment {
+                text: &rest[..len],
+                newline_before: newlines > 0 || tokens.is_empty(),
+                newline_after: false,
+                blank_line_before: newlines > 1,
+            };
+            match tokens.last_mut() {
+                // Comments on the line of the previous token
+                Some(token) if newlines == 0 && comments.is_empty() => token.trailing.push(comment),
+                _ => comments.push(comment),
+            }
+            newlines = 0;
+            position += len;
+            continue;
+        }
+
+        let (kind, len) = token_at(rest).ok_or(SyntaxError {
+            location: position,
+            message: "Invalid token".to_string(),
+        })?;
+        if let Some(previous) = comments.last_mut() {
+            previous.newline_after = newlines > 0;
+        }
+        tokens.push(Token {
+            kind,
+            text: &rest[..len],
+            leading: std::mem::take(&mut comments),
+            trailing: Vec::new(),
+            blank_line_before: newlines > 1,
+        });
+        newlines = 0;
+        position += len;
+    }
+    if let Some(previous) = comments.last_mut() {
+        previous.newline_after = true;
+    }
+    tokens.push(Token {
+        kind: TokenKind::Eof,
+        text: "",
+        leading: comments,
+        trailing: Vec::new(),
+        blank_line_before: newlines > 1,
+    });
+    Ok(tokens)
+}
+
+// Kind and length of the token at the start of code, like the lexer generated
+// from parser.lalrpop
+fn token_at(code: &str) -> Option<(TokenKind, usize)> {
+    let bytes = code.as_bytes();
+    let is_id_char = |c: &u8| c.is_ascii_alphanumeric() || *c == b'_';
+    if bytes[0].is_ascii_digit() {
+        let digits = bytes.iter().take_while(|c| c.is_ascii_digit()).count();
+        let len = if code[digits..].starts_with("u8") {
+            digits + 2
+        } else {
+            digits
+        };
+        return Some((TokenKind::Integer, len));
+    }
+    if bytes[0] == b'\'' {
+        let c = code[1..].chars().next()?;
+        let len = 1 + c.len_utf8();
+        return code[len..]
+            .starts_with('\'')
+            .then_some((TokenKind::Integer, len + 1));
+    }
+    if bytes[0] == b'"' {
+        return code[1..].find('"').map(|end| (TokenKind::Str, end + 2));
+    }
+    if bytes[0].is_ascii_alphabetic() || bytes[0] == b'_' {
+        let len = bytes.iter().take_while(|c| is_id_char(c)).count();
+        return Some(match &code[..len] {
+            "_" => (TokenKind::Punctuation, 1),
+            id if KEYWORDS.contains(&id) => (TokenKind::Keyword, len),
+            _ => (TokenKind::Id, len),
+        });
+    }
+    PUNCTUATION
+        .iter()
+        .find(|punctuation| code.starts_with(*punctuation))
+        .map(|punctuation| (TokenKind::Punctuation, punctuation.len()))
+}
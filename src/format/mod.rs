@@ -0,0 +1,66 @@
+//! The canonical formatter behind `arena fmt`.
+//!
+//! The code is split into tokens that keep the comments around them, parsed
+//! into a concrete syntax tree and printed again in a fixed style. Formatting
+//! formatted code does not change it.
+
+mod doc;
+mod lexer;
+mod printer;
+mod tree;
+
+use crate::parser::{self, SyntaxError};
+use lexer::{Token, TokenKind};
+
+// Files with this text in a comment before their first item are not formatted
+pub const SKIP_MARKER: &str = "arena-fmt: skip";
+
+/// Formats the code of a module. Fails on syntax errors, like the compiler.
+pub fn format(code: &str) -> Result<String, SyntaxError> {
+    parser::parse(code)?;
+    let tokens = lexer::lex(code)?;
+    if tokens[0]
+        .leading
+        .iter()
+        .any(|comment| comment.text.contains(SKIP_MARKER))
+    {
+        return Ok(code.to_string());
+    }
+    let tree = tree::parse(tokens).map_err(internal_error)?;
+    let formatted = doc::render(&printer::file(&tree));
+
+    // Only whitespace, trailing commas and the location of comments may change
+    if significant_tokens(code) != significant_tokens(&formatted) {
+        return Err(internal_error(SyntaxError {
+            location: 0,
+            message: "The formatted code has different tokens".to_string(),
+        }));
+    }
+    Ok(formatted)
+}
+
+fn internal_error(error: SyntaxError) -> SyntaxError {
+    SyntaxError {
+        location: error.location,
+        message: format!("Could not format the code: {}", error.message),
+    }
+}
+
+// The tokens and comments of code, without the optional commas before }
+fn significant_tokens(code: &str) -> Option<(Vec<&str>, Vec<&str>)> {
+    let tokens = lexer::lex(code).ok()?;
+    let is_optional_comma =
+        |index: usize, token: &Token| token.is(",") && tokens[index + 1].is("}");
+    let texts = tokens
+        .iter()
+        .enumerate()
+        .filter(|(index, token)| token.kind != TokenKind::Eof && !is_optional_comma(*index, token))
+        .map(|(_, token)| token.text)
+        .collect();
+    let comments = tokens
+        .iter()
+        .flat_map(|token| token.leading.iter().chain(token.trailing.iter()))
+        .map(|comment| comment.text.trim_end())
+        .collect();
+    Some((texts, comments))
+}
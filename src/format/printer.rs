@@ -0,0 +1,628 @@
+// Lays out the syntax tree in the canonical style. Every token of the tree is
+// printed exactly once, so that no comment is lost.
+
+use super::doc::{text, Doc};
+use super::lexer::{Token, TokenKind};
+use super::tree::{
+    precedence, Arm, Branch, Delimited, Expr, Field, File, Function, Item, Param, Pattern,
+    TypeExpr, TypeRef,
+};
+
+pub fn file(file: &File) -> Doc {
+    let mut docs = Vec::new();
+    for (index, item) in file.items.iter().enumerate() {
+        if index > 0 {
+            // Imports and externs are grouped without blank lines
+            let grouped = matches!(
+                (item, &file.items[index - 1]),
+                (Item::Import { .. }, Item::Import { .. })
+                    | (Item::Extern { .. }, Item::Extern { .. })
+            );
+            docs.push(if grouped {
+                line_before(item.first_token())
+            } else {
+                Doc::BlankLine
+            });
+        }
+        docs.push(self::item(item));
+    }
+    docs.push(token(&file.eof));
+    Doc::Concat(docs)
+}
+
+fn token(token: &Token) -> Doc {
+    token_as(token, token.text)
+}
+
+// Prints the comments of token around text, which replaces the token. An
+// empty text removes the token but keeps its comments.
+fn token_as(token: &Token, token_text: &str) -> Doc {
+    Doc::Concat(vec![
+        leading_comments(token),
+        without_leading_comments(token, token_text),
+    ])
+}
+
+fn leading_comments(token: &Token) -> Doc {
+    let mut docs = Vec::new();
+    for comment in token.leading.iter() {
+        if comment.newline_before {
+            docs.push(line(comment.blank_line_before));
+        }
+        docs.push(text(comment.text));
+        if !comment.newline_after {
+            docs.push(text(" "));
+        }
+    }
+    if token
+        .leading
+        .last()
+        .is_some_and(|comment| comment.newline_after)
+    {
+        docs.push(line(token.blank_line_before));
+    }
+    Doc::Concat(docs)
+}
+
+fn without_leading_comments(token: &Token, token_text: &str) -> Doc {
+    let mut docs = vec![text(token_text)];
+    for comment in token.trailing.iter() {
+        if comment.is_line_comment() {
+            // A comment after a value or closing delimiter may move to the
+            // end of the line, but after separators it breaks the line
+            docs.push(Doc::LineSuffix(format!(" {}", comment.text)));
+            if !ends_value(token) {
+                docs.push(Doc::BreakParent);
+            }
+        } else {
+            docs.push(text(format!(" {}", comment.text)));
+        }
+    }
+    Doc::Concat(docs)
+}
+
+// A closing delimiter on its own line after the nested docs, which also hold
+// the comments before the delimiter
+fn close_nested(mut nested: Vec<Doc>, close: &Token) -> [Doc; 3] {
+    nested.push(leading_comments(close));
+    [
+        Doc::Nest(nested),
+        Doc::HardLine,
+        without_leading_comments(close, close.text),
+    ]
+}
+
+fn ends_value(token: &Token) -> bool {
+    matches!(
+        token.kind,
+        TokenKind::Id | TokenKind::Integer | TokenKind::Str
+    ) || [")", "}", "true", "false"]
+        .iter()
+        .any(|text| token.is(text))
+}
+
+fn line(blank: bool) -> Doc {
+    if blank {
+        Doc::BlankLine
+    } else {
+        Doc::HardLine
+    }
+}
+
+// A newline before token, keeping an empty line before it or its comments
+fn line_before(token: &Token) -> Doc {
+    line(
+        token
+            .leading
+            .first()
+            .map_or(token.blank_line_before, |comment| comment.blank_line_before),
+    )
+}
+
+fn item(item: &Item) -> Doc {
+    match item {
+        Item::Import {
+            import,
+            path,
+            alias,
+        } => {
+            let mut docs = vec![self::token(import), text(" "), self::token(path)];
+            if let Some((as_, alias)) = alias {
+                docs.extend([text(" "), token(as_), text(" "), token(alias)]);
+            }
+            Doc::Concat(docs)
+        }
+        Item::Type {
+            derive,
+            public,
+            keyword,
+            name,
+            open,
+            cases,
+            close,
+        } => {
+            let mut docs = Vec::new();
+            if let Some((derive, traits)) = derive {
+                docs.extend([token(derive), delimited(traits, token), Doc::HardLine]);
+            }
+            let mut case_docs = Vec::new();
+            for (case, comma) in cases.iter() {
+                match &case.public {
+                    Some(public) => {
+                        case_docs.extend([line_before(public), token(public), text(" ")])
+                    }
+                    None => case_docs.push(line_before(&case.name)),
+                }
+                case_docs.push(token(&case.name));
+                if let Some(fields) = &case.fields {
+                    case_docs.push(delimited(fields, field));
+                }
+                case_docs.push(comma.as_ref().map_or(text(","), token));
+            }
+            if let Some(public) = public {
+                docs.extend([token(public), text(" ")]);
+            }
+            docs.extend([
+                token(keyword),
+                text(" "),
+                token(name),
+                text(" "),
+                token(open),
+                Doc::Concat(close_nested(case_docs, close).into()),
+            ]);
+            Doc::Concat(docs)
+        }
+        Item::Function {
+            public,
+            function: function_def,
+        } => match public {
+            Some(public) => Doc::Concat(vec![token(public), text(" "), function(function_def)]),
+            None => function(function_def),
+        },
+        Item::Trait {
+            keyword,
+            name,
+            open,
+            methods,
+            close,
+        } => Doc::Concat(vec![
+            token(keyword),
+            text(" "),
+            token(name),
+            text(" "),
+            token(open),
+            Doc::Concat(close_nested(functions(methods), close).into()),
+        ]),
+        Item::Impl {
+            keyword,
+            trait_name,
+            for_,
+            type_name,
+            open,
+            functions: impl_functions,
+            close,
+        } => Doc::Concat(vec![
+            token(keyword),
+            text(" "),
+            type_ref(trait_name),
+            text(" "),
+            token(for_),
+            text(" "),
+            type_ref(type_name),
+            text(" "),
+            token(open),
+            Doc::Concat(close_nested(functions(impl_functions), close).into()),
+        ]),
+        Item::Extern {
+            keyword,
+            function: function_def,
+        } => Doc::Concat(vec![token(keyword), text(" "), function(function_def)]),
+    }
+}
+
+// The functions of a trait or an implementation, each on its own line
+fn functions(functions: &[Function]) -> Vec<Doc> {
+    let mut docs = Vec::new();
+    for function_def in functions.iter() {
+        docs.push(line_before(&function_def.keyword));
+        docs.push(function(function_def));
+    }
+    docs
+}
+
+fn function(function: &Function) -> Doc {
+    let mut docs = vec![
+        token(&function.keyword),
+        text(" "),
+        token(&function.name),
+        delimited(&function.params, param),
+    ];
+    if let Some((arrow, ret_type)) = &function.ret_type {
+        docs.extend([text(" "), token(arrow), text(" "), type_expr(ret_type)]);
+    }
+    if let Some((eq, body)) = &function.body {
+        docs.extend([text(" "), token(eq), body_doc(body)]);
+    }
+    Doc::Concat(docs)
+}
+
+// The body of a function or match arm after = or =>. Sequences always start
+// on the next line, other expressions only if they do not fit.
+fn body_doc(body: &Expr) -> Doc {
+    if has_statements(body) {
+        Doc::Nest(vec![Doc::HardLine, statements(body, true)])
+    } else {
+        Doc::Group(vec![Doc::Nest(vec![Doc::Line, expr(body)])])
+    }
+}
+
+fn param(param: &Param) -> Doc {
+    let mut docs = vec![token(&param.name)];
+    if let Some((colon, param_type)) = &param.param_type {
+        docs.extend([token(colon), text(" "), type_expr(param_type)]);
+    }
+    Doc::Concat(docs)
+}
+
+fn field(field: &Field) -> Doc {
+    let mut docs = Vec::new();
+    if let Some((name, colon)) = &field.name {
+        docs.extend([token(name), token(colon), text(" ")]);
+    }
+    docs.push(type_expr(&field.field_type));
+    Doc::Concat(docs)
+}
+
+fn type_ref(type_ref: &TypeRef) -> Doc {
+    let mut docs = Vec::new();
+    if let Some((module, colons)) = &type_ref.module {
+        docs.extend([token(module), token(colons)]);
+    }
+    docs.push(token(&type_ref.name));
+    Doc::Concat(docs)
+}
+
+fn type_expr(type_expr: &TypeExpr) -> Doc {
+    match type_expr {
+        TypeExpr::Ref(type_ref) => self::type_ref(type_ref),
+        TypeExpr::Tuple(fields) => delimited(fields, self::type_expr),
+        TypeExpr::Array {
+            open,
+            element,
+            close,
+        } => Doc::Concat(vec![token(open), self::type_expr(element), token(close)]),
+    }
+}
+
+fn delimited<T>(delimited: &Delimited<T>, item: impl Fn(&T) -> Doc) -> Doc {
+    let close = &delimited.close;
+    if delimited.items.is_empty() {
+        if close.leading.is_empty() {
+            return Doc::Concat(vec![token(&delimited.open), token(close)]);
+        }
+        let mut docs = vec![token(&delimited.open)];
+        docs.extend(close_nested(Vec::new(), close));
+        return Doc::Concat(docs);
+    }
+    let mut items = vec![Doc::SoftLine];
+    for (index, value) in delimited.items.iter().enumerate() {
+        if index > 0 {
+            items.extend([token(&delimited.commas[index - 1]), Doc::Line]);
+        }
+        items.push(item(value));
+    }
+    items.push(leading_comments(close));
+    Doc::Group(vec![
+        token(&delimited.open),
+        Doc::Nest(items),
+        Doc::SoftLine,
+        without_leading_comments(close, close.text),
+    ])
+}
+
+// Whether expr consists of more than one statement
+fn has_statements(expr: &Expr) -> bool {
+    matches!(expr, Expr::Let { .. } | Expr::Seq { rest: Some(_), .. })
+}
+
+fn ends_with_semicolon(expr: &Expr) -> bool {
+    match expr {
+        Expr::Let { body, .. } => ends_with_semicolon(body),
+        Expr::Seq {
+            rest: Some(rest), ..
+        } => ends_with_semicolon(rest),
+        Expr::Seq { rest: None, .. } => true,
+        _ => false,
+    }
+}
+
+fn first_token<'a, 'input>(expr: &'a Expr<'input>) -> &'a Token<'input> {
+    match expr {
+        Expr::Let { keyword, .. } | Expr::Match { keyword, .. } | Expr::If { keyword, .. } => {
+            keyword
+        }
+        Expr::Seq { first, .. } | Expr::GetField { obj: first, .. } => first_token(first),
+        Expr::Binary { lhs, .. } => first_token(lhs),
+        Expr::Unary { op, .. } => op,
+        Expr::Atom(token) => token,
+        Expr::FuncCall {
+            function: type_ref, ..
+        }
+        | Expr::TypeCase { type_ref, .. } => type_ref_first_token(type_ref),
+        Expr::Parenthesized { open, .. }
+        | Expr::UpdateFields { open, .. }
+        | Expr::Tuple(Delimited { open, .. })
+        | Expr::Array(Delimited { open, .. }) => open,
+    }
+}
+
+fn type_ref_first_token<'a, 'input>(type_ref: &'a TypeRef<'input>) -> &'a Token<'input> {
+    type_ref
+        .module
+        .as_ref()
+        .map_or(&type_ref.name, |(module, _)| module)
+}
+
+fn pattern_first_token<'a, 'input>(pattern: &'a Pattern<'input>) -> &'a Token<'input> {
+    match pattern {
+        Pattern::Atom(token) | Pattern::Negative(token, _) => token,
+        Pattern::TypeCase { type_ref, .. } => type_ref_first_token(type_ref),
+        Pattern::Tuple(fields) => &fields.open,
+    }
+}
+
+// The statements of a sequence, each on its own line if hard is set
+fn statements(mut expr: &Expr, hard: bool) -> Doc {
+    let mut docs = Vec::new();
+    loop {
+        if !docs.is_empty() {
+            docs.push(if hard {
+                line_before(first_token(expr))
+            } else {
+                Doc::Line
+            });
+        }
+        match expr {
+            Expr::Let {
+                keyword,
+                pattern,
+                eq,
+                def,
+                else_,
+                semicolon,
+                body,
+            } => {
+                let mut let_docs = vec![
+                    token(keyword),
+                    text(" "),
+                    self::pattern(pattern),
+                    text(" "),
+                    token(eq),
+                    text(" "),
+                    self::expr(def),
+                ];
+                if let Some((else_, else_expr)) = else_ {
+                    let_docs.extend([text(" "), token(else_), text(" "), self::expr(else_expr)]);
+                }
+                let_docs.push(token(semicolon));
+                docs.push(Doc::Concat(let_docs));
+                expr = body;
+            }
+            Expr::Seq {
+                first,
+                semicolon,
+                rest,
+            } => {
+                docs.push(Doc::Concat(vec![self::expr(first), token(semicolon)]));
+                match rest {
+                    Some(rest) => expr = rest,
+                    None => break,
+                }
+            }
+            _ => {
+                docs.push(self::expr(expr));
+                break;
+            }
+        }
+    }
+    Doc::Concat(docs)
+}
+
+fn expr(expr: &Expr) -> Doc {
+    match expr {
+        Expr::Let { .. } | Expr::Seq { .. } => Doc::Group(vec![statements(expr, false)]),
+        Expr::Match {
+            keyword,
+            obj,
+            open,
+            arms,
+            close,
+        } => {
+            let mut arm_docs = Vec::new();
+            for (index, arm) in arms.iter().enumerate() {
+                arm_docs.push(line_before(pattern_first_token(&arm.pattern)));
+                arm_docs.push(self::arm(arm, index == arms.len() - 1));
+            }
+            Doc::Concat(vec![
+                token(keyword),
+                text(" "),
+                self::expr(obj),
+                text(" "),
+                token(open),
+                Doc::Concat(close_nested(arm_docs, close).into()),
+            ])
+        }
+        Expr::If { .. } => Doc::Group(if_chain(expr)),
+        Expr::Binary { op, .. } => {
+            let level = precedence(op);
+            let mut operands = Vec::new();
+            let mut first = expr;
+            while let Expr::Binary { lhs, op, rhs } = first {
+                if precedence(op) != level {
+                    break;
+                }
+                operands.push((op, rhs));
+                first = lhs;
+            }
+            let mut rest = Vec::new();
+            for (op, rhs) in operands.into_iter().rev() {
+                rest.extend([text(" "), token(op), Doc::Line, self::expr(rhs)]);
+            }
+            Doc::Group(vec![self::expr(first), Doc::Nest(rest)])
+        }
+        Expr::Unary { op, operand } => Doc::Concat(vec![token(op), self::expr(operand)]),
+        Expr::Atom(atom) => token(atom),
+        Expr::FuncCall { function, args } => {
+            Doc::Concat(vec![type_ref(function), delimited(args, self::expr)])
+        }
+        Expr::TypeCase {
+            type_ref,
+            dot,
+            case,
+            args,
+        } => {
+            let mut docs = vec![self::type_ref(type_ref), token(dot), token(case)];
+            if let Some(args) = args {
+                docs.push(delimited(args, self::expr));
+            }
+            Doc::Concat(docs)
+        }
+        Expr::GetField { obj, dot, name } => {
+            Doc::Concat(vec![self::expr(obj), token(dot), token(name)])
+        }
+        Expr::UpdateFields {
+            open,
+            obj,
+            with,
+            updates,
+            close,
+        } => {
+            let mut update_docs = Vec::new();
+            for update in updates.iter() {
+                update_docs.extend([
+                    Doc::Line,
+                    token(&update.name),
+                    text(" "),
+                    token(&update.eq),
+                    text(" "),
+                    self::expr(&update.value),
+                ]);
+                if let Some(comma) = &update.comma {
+                    update_docs.push(token(comma));
+                }
+            }
+            Doc::Group(vec![
+                token(open),
+                text(" "),
+                self::expr(obj),
+                text(" "),
+                token(with),
+                Doc::Nest(update_docs),
+                Doc::Line,
+                token(close),
+            ])
+        }
+        Expr::Parenthesized { open, expr, close } => {
+            let inner = if has_statements(expr) {
+                statements(expr, false)
+            } else {
+                self::expr(expr)
+            };
+            Doc::Group(vec![
+                token(open),
+                Doc::Nest(vec![Doc::SoftLine, inner]),
+                Doc::SoftLine,
+                token(close),
+            ])
+        }
+        Expr::Tuple(fields) | Expr::Array(fields) => delimited(fields, self::expr),
+    }
+}
+
+// An if expression with the ifs of its else branches, which break together
+fn if_chain(expr: &Expr) -> Vec<Doc> {
+    let Expr::If {
+        keyword,
+        cond,
+        then,
+        then_branch,
+        else_,
+        else_branch,
+    } = expr
+    else {
+        unreachable!("Expected if expression");
+    };
+    let mut docs = vec![
+        token(keyword),
+        text(" "),
+        self::expr(cond),
+        text(" "),
+        token(then),
+    ];
+    match then_branch {
+        Branch::Block { open, expr, close } => {
+            docs.extend(block(open, expr, close));
+            docs.extend([text(" "), token(else_)]);
+        }
+        Branch::Expr(expr) => {
+            docs.push(Doc::Nest(vec![Doc::Line, self::expr(expr)]));
+            docs.extend([Doc::Line, token(else_)]);
+        }
+    }
+    match else_branch {
+        Branch::Block { open, expr, close } => docs.extend(block(open, expr, close)),
+        Branch::Expr(expr) if matches!(**expr, Expr::If { .. }) => {
+            docs.push(text(" "));
+            docs.extend(if_chain(expr));
+        }
+        Branch::Expr(expr) => docs.push(Doc::Nest(vec![Doc::Line, self::expr(expr)])),
+    }
+    docs
+}
+
+fn block(open: &Token, expr: &Expr, close: &Token) -> [Doc; 3] {
+    [
+        text(" "),
+        token(open),
+        Doc::Concat(close_nested(vec![Doc::HardLine, statements(expr, true)], close).into()),
+    ]
+}
+
+fn arm(arm: &Arm, last: bool) -> Doc {
+    // The last arm has a trailing comma, unless its sequence ends with a
+    // semicolon
+    let omit_comma = last && ends_with_semicolon(&arm.expr);
+    let comma = match (&arm.comma, omit_comma) {
+        (Some(comma), true) => token_as(comma, ""),
+        (Some(comma), false) => token(comma),
+        (None, true) => text(""),
+        (None, false) => text(","),
+    };
+    Doc::Concat(vec![
+        pattern(&arm.pattern),
+        text(" "),
+        token(&arm.arrow),
+        body_doc(&arm.expr),
+        comma,
+    ])
+}
+
+fn pattern(pattern: &Pattern) -> Doc {
+    match pattern {
+        Pattern::Atom(atom) => token(atom),
+        Pattern::Negative(minus, literal) => Doc::Concat(vec![token(minus), token(literal)]),
+        Pattern::TypeCase {
+            type_ref,
+            dot,
+            case,
+            fields,
+        } => {
+            let mut docs = vec![self::type_ref(type_ref), token(dot), token(case)];
+            if let Some(fields) = fields {
+                docs.push(delimited(fields, self::pattern));
+            }
+            Doc::Concat(docs)
+        }
+        Pattern::Tuple(fields) => delimited(fields, self::pattern),
+    }
+}
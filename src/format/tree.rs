@@ -0,0 +1,791 @@
+// A concrete syntax tree that keeps every token of the code, and with them
+// the comments. It follows the grammar in parser/parser.lalrpop, which checks
+// the code before it is parsed into this tree.
+
+use std::collections::VecDeque;
+
+use super::lexer::{Token, TokenKind};
+use crate::parser::SyntaxError;
+
+pub struct File<'input> {
+    pub items: Vec<Item<'input>>,
+    pub eof: Token<'input>,
+}
+
+// The tree only lives while a file is formatted, so variants are not boxed
+#[allow(clippy::large_enum_variant)]
+pub enum Item<'input> {
+    Import {
+        import: Token<'input>,
+        path: Token<'input>,
+        alias: Option<(Token<'input>, Token<'input>)>,
+    },
+    Type {
+        derive: Option<(Token<'input>, Delimited<'input, Token<'input>>)>,
+        public: Option<Token<'input>>,
+        keyword: Token<'input>,
+        name: Token<'input>,
+        open: Token<'input>,
+        cases: Vec<(TypeCase<'input>, Option<Token<'input>>)>,
+        close: Token<'input>,
+    },
+    Function {
+        public: Option<Token<'input>>,
+        function: Function<'input>,
+    },
+    Trait {
+        keyword: Token<'input>,
+        name: Token<'input>,
+        open: Token<'input>,
+        methods: Vec<Function<'input>>,
+        close: Token<'input>,
+    },
+    Impl {
+        keyword: Token<'input>,
+        trait_name: TypeRef<'input>,
+        for_: Token<'input>,
+        type_name: TypeRef<'input>,
+        open: Token<'input>,
+        functions: Vec<Function<'input>>,
+        close: Token<'input>,
+    },
+    // The function has no body
+    Extern {
+        keyword: Token<'input>,
+        function: Function<'input>,
+    },
+}
+
+impl<'input> Item<'input> {
+    pub fn first_token(&self) -> &Token<'input> {
+        match self {
+            Item::Import { import, .. } => import,
+            Item::Type {
+                derive: Some((derive, _)),
+                ..
+            } => derive,
+            Item::Type {
+                public: Some(public),
+                ..
+            }
+            | Item::Function {
+                public: Some(public),
+                ..
+            } => public,
+            Item::Type { keyword, .. }
+            | Item::Function {
+                function: Function { keyword, .. },
+                ..
+            }
+            | Item::Trait { keyword, .. }
+            | Item::Impl { keyword, .. }
+            | Item::Extern { keyword, .. } => keyword,
+        }
+    }
+}
+
+// The body is only missing in methods of traits without a default
+pub struct Function<'input> {
+    pub keyword: Token<'input>,
+    pub name: Token<'input>,
+    pub params: Delimited<'input, Param<'input>>,
+    pub ret_type: Option<(Token<'input>, TypeExpr<'input>)>,
+    pub body: Option<(Token<'input>, Expr<'input>)>,
+}
+
+pub struct TypeCase<'input> {
+    pub public: Option<Token<'input>>,
+    pub name: Token<'input>,
+    pub fields: Option<Delimited<'input, Field<'input>>>,
+}
+
+pub struct Field<'input> {
+    pub name: Option<(Token<'input>, Token<'input>)>,
+    pub field_type: TypeExpr<'input>,
+}
+
+pub struct Param<'input> {
+    pub name: Token<'input>,
+    pub param_type: Option<(Token<'input>, TypeExpr<'input>)>,
+}
+
+// An identifier that may be qualified by a module like M::id
+pub struct TypeRef<'input> {
+    pub module: Option<(Token<'input>, Token<'input>)>,
+    pub name: Token<'input>,
+}
+
+pub enum TypeExpr<'input> {
+    Ref(TypeRef<'input>),
+    Tuple(Delimited<'input, TypeExpr<'input>>),
+    Array {
+        open: Token<'input>,
+        element: Box<TypeExpr<'input>>,
+        close: Token<'input>,
+    },
+}
+
+// Comma separated items in parentheses or brackets
+pub struct Delimited<'input, T> {
+    pub open: Token<'input>,
+    pub items: Vec<T>,
+    pub commas: Vec<Token<'input>>,
+    pub close: Token<'input>,
+}
+
+pub enum Expr<'input> {
+    Let {
+        keyword: Token<'input>,
+        pattern: Box<Pattern<'input>>,
+        eq: Token<'input>,
+        def: Box<Expr<'input>>,
+        else_: Option<(Token<'input>, Box<Expr<'input>>)>,
+        semicolon: Token<'input>,
+        body: Box<Expr<'input>>,
+    },
+    // A missing rest is a sequence ending with a semicolon
+    Seq {
+        first: Box<Expr<'input>>,
+        semicolon: Token<'input>,
+        rest: Option<Box<Expr<'input>>>,
+    },
+    Match {
+        keyword: Token<'input>,
+        obj: Box<Expr<'input>>,
+        open: Token<'input>,
+        arms: Vec<Arm<'input>>,
+        close: Token<'input>,
+    },
+    If {
+        keyword: Token<'input>,
+        cond: Box<Expr<'input>>,
+        then: Token<'input>,
+        then_branch: Branch<'input>,
+        else_: Token<'input>,
+        else_branch: Branch<'input>,
+    },
+    Binary {
+        lhs: Box<Expr<'input>>,
+        op: Token<'input>,
+        rhs: Box<Expr<'input>>,
+    },
+    Unary {
+        op: Token<'input>,
+        operand: Box<Expr<'input>>,
+    },
+    // Literals and variables
+    Atom(Token<'input>),
+    FuncCall {
+        function: TypeRef<'input>,
+        args: Delimited<'input, Expr<'input>>,
+    },
+    TypeCase {
+        type_ref: TypeRef<'input>,
+        dot: Token<'input>,
+        case: Token<'input>,
+        args: Option<Delimited<'input, Expr<'input>>>,
+    },
+    GetField {
+        obj: Box<Expr<'input>>,
+        dot: Token<'input>,
+        name: Token<'input>,
+    },
+    UpdateFields {
+        open: Token<'input>,
+        obj: Box<Expr<'input>>,
+        with: Token<'input>,
+        updates: Vec<FieldUpdate<'input>>,
+        close: Token<'input>,
+    },
+    Parenthesized {
+        open: Token<'input>,
+        expr: Box<Expr<'input>>,
+        close: Token<'input>,
+    },
+    Tuple(Delimited<'input, Expr<'input>>),
+    Array(Delimited<'input, Expr<'input>>),
+}
+
+pub enum Branch<'input> {
+    Expr(Box<Expr<'input>>),
+    Block {
+        open: Token<'input>,
+        expr: Box<Expr<'input>>,
+        close: Token<'input>,
+    },
+}
+
+pub struct FieldUpdate<'input> {
+    pub name: Token<'input>,
+    pub eq: Token<'input>,
+    pub value: Expr<'input>,
+    pub comma: Option<Token<'input>>,
+}
+
+pub struct Arm<'input> {
+    pub pattern: Pattern<'input>,
+    pub arrow: Token<'input>,
+    pub expr: Expr<'input>,
+    pub comma: Option<Token<'input>>,
+}
+
+#[allow(clippy::large_enum_variant)]
+pub enum Pattern<'input> {
+    // Literals, variables and the wildcard
+    Atom(Token<'input>),
+    Negative(Token<'input>, Token<'input>),
+    TypeCase {
+        type_ref: TypeRef<'input>,
+        dot: Token<'input>,
+        case: Token<'input>,
+        fields: Option<Delimited<'input, Pattern<'input>>>,
+    },
+    Tuple(Delimited<'input, Pattern<'input>>),
+}
+
+// Binary operators by precedence, from lowest to highest
+pub const BINARY_OPERATORS: [&[&str]; 6] = [
+    &["||"],
+    &["&&"],
+    &["==", "!="],
+    &["<", "<=", ">", ">="],
+    &["+", "-"],
+    &["*", "/", "%"],
+];
+
+pub fn precedence(op: &Token) -> usize {
+    BINARY_OPERATORS
+        .iter()
+        .position(|ops| ops.contains(&op.text))
+        .expect("Unknown binary operator")
+}
+
+pub fn parse(tokens: Vec<Token<'_>>) -> Result<File<'_>, SyntaxError> {
+    let mut parser = Parser {
+        tokens: tokens.into(),
+    };
+    let mut items = Vec::new();
+    while parser.peek().kind != TokenKind::Eof {
+        items.push(parser.item()?);
+    }
+    Ok(File {
+        items,
+        eof: parser.tokens.pop_front().unwrap(),
+    })
+}
+
+struct Parser<'input> {
+    // Always ends with the Eof token
+    tokens: VecDeque<Token<'input>>,
+}
+
+impl<'input> Parser<'input> {
+    fn peek(&self) -> &Token<'input> {
+        &self.tokens[0]
+    }
+
+    fn peek_is(&self, text: &str) -> bool {
+        self.peek().is(text)
+    }
+
+    fn next(&mut self) -> Token<'input> {
+        if self.tokens.len() == 1 {
+            return Token::synthetic("");
+        }
+        self.tokens.pop_front().unwrap()
+    }
+
+    fn error(&self) -> SyntaxError {
+        SyntaxError {
+            location: 0,
+            message: format!("Unexpected token \"{}\"", self.peek().text),
+        }
+    }
+
+    fn expect(&mut self, text: &str) -> Result<Token<'input>, SyntaxError> {
+        if self.peek_is(text) {
+            Ok(self.next())
+        } else {
+            Err(self.error())
+        }
+    }
+
+    fn id(&mut self) -> Result<Token<'input>, SyntaxError> {
+        if self.peek().kind == TokenKind::Id {
+            Ok(self.next())
+        } else {
+            Err(self.error())
+        }
+    }
+
+    fn next_if(&mut self, text: &str) -> Option<Token<'input>> {
+        self.peek_is(text).then(|| self.next())
+    }
+
+    fn item(&mut self) -> Result<Item<'input>, SyntaxError> {
+        if let Some(import) = self.next_if("import") {
+            let path = self.next();
+            let alias = match self.next_if("as") {
+                Some(as_) => Some((as_, self.id()?)),
+                None => None,
+            };
+            return Ok(Item::Import {
+                import,
+                path,
+                alias,
+            });
+        }
+        let derive = match self.next_if("derive") {
+            Some(derive) => Some((derive, self.delimited(Self::id)?)),
+            None => None,
+        };
+        let public = self.next_if("pub");
+        if let Some(keyword) = self.next_if("type") {
+            let name = self.id()?;
+            let open = self.expect("{")?;
+            let mut cases = Vec::new();
+            loop {
+                let case = TypeCase {
+                    public: self.next_if("pub"),
+                    name: self.id()?,
+                    fields: if self.peek_is("(") {
+                        Some(self.delimited(Self::field)?)
+                    } else {
+                        None
+                    },
+                };
+                let comma = self.next_if(",");
+                let last = comma.is_none() || self.peek_is("}");
+                cases.push((case, comma));
+                if last {
+                    break;
+                }
+            }
+            return Ok(Item::Type {
+                derive,
+                public,
+                keyword,
+                name,
+                open,
+                cases,
+                close: self.expect("}")?,
+            });
+        }
+        if let Some(keyword) = self.next_if("trait") {
+            let name = self.id()?;
+            let open = self.expect("{")?;
+            let mut methods = Vec::new();
+            while !self.peek_is("}") {
+                methods.push(self.function()?);
+            }
+            return Ok(Item::Trait {
+                keyword,
+                name,
+                open,
+                methods,
+                close: self.expect("}")?,
+            });
+        }
+        if let Some(keyword) = self.next_if("impl") {
+            let trait_name = self.type_ref()?;
+            let for_ = self.expect("for")?;
+            let type_name = self.type_ref()?;
+            let open = self.expect("{")?;
+            let mut functions = Vec::new();
+            while !self.peek_is("}") {
+                functions.push(self.function()?);
+            }
+            return Ok(Item::Impl {
+                keyword,
+                trait_name,
+                for_,
+                type_name,
+                open,
+                functions,
+                close: self.expect("}")?,
+            });
+        }
+        if let Some(keyword) = self.next_if("extern") {
+            return Ok(Item::Extern {
+                keyword,
+                function: self.function()?,
+            });
+        }
+        Ok(Item::Function {
+            public,
+            function: self.function()?,
+        })
+    }
+
+    fn function(&mut self) -> Result<Function<'input>, SyntaxError> {
+        let keyword = self.expect("fn")?;
+        let name = self.id()?;
+        let params = self.delimited(|parser| {
+            let name = parser.id()?;
+            let param_type = match parser.next_if(":") {
+                Some(colon) => Some((colon, parser.type_expr()?)),
+                None => None,
+            };
+            Ok(Param { name, param_type })
+        })?;
+        let ret_type = match self.next_if("->") {
+            Some(arrow) => Some((arrow, self.type_expr()?)),
+            None => None,
+        };
+        let body = match self.next_if("=") {
+            Some(eq) => Some((eq, self.expr()?)),
+            None => None,
+        };
+        Ok(Function {
+            keyword,
+            name,
+            params,
+            ret_type,
+            body,
+        })
+    }
+
+    fn delimited<T>(
+        &mut self,
+        item: impl Fn(&mut Self) -> Result<T, SyntaxError>,
+    ) -> Result<Delimited<'input, T>, SyntaxError> {
+        self.delimited_by("(", ")", item)
+    }
+
+    fn delimited_by<T>(
+        &mut self,
+        open: &str,
+        close: &str,
+        item: impl Fn(&mut Self) -> Result<T, SyntaxError>,
+    ) -> Result<Delimited<'input, T>, SyntaxError> {
+        let open = self.expect(open)?;
+        let mut items = Vec::new();
+        let mut commas = Vec::new();
+        if !self.peek_is(close) {
+            items.push(item(self)?);
+            while let Some(comma) = self.next_if(",") {
+                commas.push(comma);
+                items.push(item(self)?);
+            }
+        }
+        Ok(Delimited {
+            open,
+            items,
+            commas,
+            close: self.expect(close)?,
+        })
+    }
+
+    fn type_ref(&mut self) -> Result<TypeRef<'input>, SyntaxError> {
+        let first = self.id()?;
+        Ok(match self.next_if("::") {
+            Some(colons) => TypeRef {
+                module: Some((first, colons)),
+                name: self.id()?,
+            },
+            None => TypeRef {
+                module: None,
+                name: first,
+            },
+        })
+    }
+
+    fn type_expr(&mut self) -> Result<TypeExpr<'input>, SyntaxError> {
+        if self.peek_is("(") {
+            Ok(TypeExpr::Tuple(self.delimited(Self::type_expr)?))
+        } else if let Some(open) = self.next_if("[") {
+            Ok(TypeExpr::Array {
+                open,
+                element: Box::new(self.type_expr()?),
+                close: self.expect("]")?,
+            })
+        } else {
+            Ok(TypeExpr::Ref(self.type_ref()?))
+        }
+    }
+
+    fn field(&mut self) -> Result<Field<'input>, SyntaxError> {
+        let name = if self.peek().kind == TokenKind::Id && self.tokens[1].is(":") {
+            Some((self.next(), self.next()))
+        } else {
+            None
+        };
+        Ok(Field {
+            name,
+            field_type: self.type_expr()?,
+        })
+    }
+
+    fn starts_expr(&self) -> bool {
+        let token = self.peek();
+        match token.kind {
+            TokenKind::Id | TokenKind::Integer | TokenKind::Str => true,
+            TokenKind::Keyword | TokenKind::Punctuation => {
+                ["let", "match", "if", "true", "false", "-", "!", "(", "{"]
+                    .iter()
+                    .any(|text| token.is(text))
+            }
+            TokenKind::Eof => false,
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr<'input>, SyntaxError> {
+        if let Some(keyword) = self.next_if("let") {
+            return Ok(Expr::Let {
+                keyword,
+                pattern: Box::new(self.pattern()?),
+                eq: self.expect("=")?,
+                def: Box::new(self.match_expr()?),
+                else_: match self.next_if("else") {
+                    Some(else_) => Some((else_, Box::new(self.match_expr()?))),
+                    None => None,
+                },
+                semicolon: self.expect(";")?,
+                body: Box::new(self.expr()?),
+            });
+        }
+        let first = self.match_expr()?;
+        let semicolon = match self.next_if(";") {
+            Some(semicolon) => semicolon,
+            None => return Ok(first),
+        };
+        let rest = if self.starts_expr() {
+            Some(Box::new(self.expr()?))
+        } else {
+            None
+        };
+        Ok(Expr::Seq {
+            first: Box::new(first),
+            semicolon,
+            rest,
+        })
+    }
+
+    fn match_expr(&mut self) -> Result<Expr<'input>, SyntaxError> {
+        let keyword = match self.next_if("match") {
+            Some(keyword) => keyword,
+            None => return self.if_expr(),
+        };
+        let obj = Box::new(self.match_expr()?);
+        let open = self.expect("{")?;
+        let mut arms = Vec::new();
+        loop {
+            let pattern = self.pattern()?;
+            let arrow = self.expect("=>")?;
+            let expr = self.expr()?;
+            let comma = self.next_if(",");
+            let last = comma.is_none() || self.peek_is("}");
+            arms.push(Arm {
+                pattern,
+                arrow,
+                expr,
+                comma,
+            });
+            if last {
+                break;
+            }
+        }
+        Ok(Expr::Match {
+            keyword,
+            obj,
+            open,
+            arms,
+            close: self.expect("}")?,
+        })
+    }
+
+    fn if_expr(&mut self) -> Result<Expr<'input>, SyntaxError> {
+        let keyword = match self.next_if("if") {
+            Some(keyword) => keyword,
+            None => return self.binary(0),
+        };
+        Ok(Expr::If {
+            keyword,
+            cond: Box::new(self.match_expr()?),
+            then: self.expect("then")?,
+            then_branch: self.branch()?,
+            else_: self.expect("else")?,
+            else_branch: self.branch()?,
+        })
+    }
+
+    fn branch(&mut self) -> Result<Branch<'input>, SyntaxError> {
+        Ok(match self.next_if("{") {
+            Some(open) => {
+                let expr = self.expr()?;
+                if self.peek_is("with") {
+                    Branch::Expr(Box::new(self.update_fields(open, expr)?))
+                } else {
+                    Branch::Block {
+                        open,
+                        expr: Box::new(expr),
+                        close: self.expect("}")?,
+                    }
+                }
+            }
+            None => Branch::Expr(Box::new(self.match_expr()?)),
+        })
+    }
+
+    fn binary(&mut self, level: usize) -> Result<Expr<'input>, SyntaxError> {
+        if level == BINARY_OPERATORS.len() {
+            return self.unary();
+        }
+        let mut lhs = self.binary(level + 1)?;
+        while BINARY_OPERATORS[level].iter().any(|op| self.peek_is(op)) {
+            let op = self.next();
+            lhs = Expr::Binary {
+                lhs: Box::new(lhs),
+                op,
+                rhs: Box::new(self.binary(level + 1)?),
+            };
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr<'input>, SyntaxError> {
+        if self.peek_is("-") || self.peek_is("!") {
+            let op = self.next();
+            let operand = match self.peek().kind {
+                TokenKind::Integer => Expr::Atom(self.next()),
+                _ => self.term()?,
+            };
+            return Ok(Expr::Unary {
+                op,
+                operand: Box::new(operand),
+            });
+        }
+        match self.peek().kind {
+            TokenKind::Integer => Ok(Expr::Atom(self.next())),
+            _ => self.term(),
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr<'input>, SyntaxError> {
+        let mut term = self.atom()?;
+        while let Some(dot) = self.next_if(".") {
+            term = Expr::GetField {
+                obj: Box::new(term),
+                dot,
+                name: self.id()?,
+            };
+        }
+        Ok(term)
+    }
+
+    fn atom(&mut self) -> Result<Expr<'input>, SyntaxError> {
+        if let Some(open) = self.next_if("{") {
+            let obj = self.match_expr()?;
+            return self.update_fields(open, obj);
+        }
+        if self.peek_is("true") || self.peek_is("false") || self.peek().kind == TokenKind::Str {
+            return Ok(Expr::Atom(self.next()));
+        }
+        if let Some(open) = self.next_if("(") {
+            let expr = self.expr()?;
+            if !self.peek_is(",") {
+                return Ok(Expr::Parenthesized {
+                    open,
+                    expr: Box::new(expr),
+                    close: self.expect(")")?,
+                });
+            }
+            let mut items = vec![expr];
+            let mut commas = Vec::new();
+            while let Some(comma) = self.next_if(",") {
+                commas.push(comma);
+                items.push(self.expr()?);
+            }
+            return Ok(Expr::Tuple(Delimited {
+                open,
+                items,
+                commas,
+                close: self.expect(")")?,
+            }));
+        }
+        if self.peek_is("[") {
+            return Ok(Expr::Array(self.delimited_by("[", "]", Self::expr)?));
+        }
+        let type_ref = self.type_ref()?;
+        if let Some(dot) = self.next_if(".") {
+            return Ok(Expr::TypeCase {
+                type_ref,
+                dot,
+                case: self.id()?,
+                args: if self.peek_is("(") {
+                    Some(self.delimited(Self::expr)?)
+                } else {
+                    None
+                },
+            });
+        }
+        if self.peek_is("(") || type_ref.module.is_some() {
+            return Ok(Expr::FuncCall {
+                function: type_ref,
+                args: self.delimited(Self::expr)?,
+            });
+        }
+        Ok(Expr::Atom(type_ref.name))
+    }
+
+    // The rest of { obj with name = value, ... } after obj
+    fn update_fields(
+        &mut self,
+        open: Token<'input>,
+        obj: Expr<'input>,
+    ) -> Result<Expr<'input>, SyntaxError> {
+        let with = self.expect("with")?;
+        let mut updates = Vec::new();
+        loop {
+            let name = self.id()?;
+            let eq = self.expect("=")?;
+            let value = self.match_expr()?;
+            let comma = self.next_if(",");
+            let last = comma.is_none();
+            updates.push(FieldUpdate {
+                name,
+                eq,
+                value,
+                comma,
+            });
+            if last {
+                break;
+            }
+        }
+        Ok(Expr::UpdateFields {
+            open,
+            obj: Box::new(obj),
+            with,
+            updates,
+            close: self.expect("}")?,
+        })
+    }
+
+    fn pattern(&mut self) -> Result<Pattern<'input>, SyntaxError> {
+        if let Some(minus) = self.next_if("-") {
+            return Ok(Pattern::Negative(minus, self.next()));
+        }
+        if self.peek_is("(") {
+            return Ok(Pattern::Tuple(self.delimited(Self::pattern)?));
+        }
+        if self.peek().kind != TokenKind::Id {
+            return Ok(Pattern::Atom(self.next()));
+        }
+        let type_ref = self.type_ref()?;
+        let dot = match self.next_if(".") {
+            Some(dot) => dot,
+            None if type_ref.module.is_none() => return Ok(Pattern::Atom(type_ref.name)),
+            None => return Err(self.error()),
+        };
+        Ok(Pattern::TypeCase {
+            type_ref,
+            dot,
+            case: self.id()?,
+            fields: if self.peek_is("(") {
+                Some(self.delimited(Self::pattern)?)
+            } else {
+                None
+            },
+        })
+    }
+}
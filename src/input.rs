@@ -1,43 +1,52 @@
-use clap::Parser;
-use std::collections::hash_map::DefaultHasher;
-use std::collections::HashMap;
-use std::fs;
-use std::hash::{Hash, Hasher};
+use clap::{ArgEnum, Args, Parser, Subcommand};
 use std::path::PathBuf;
-use std::rc::Rc;
 
-use crate::module::Module;
-use crate::parser::parse_imports;
+pub use arena::codegen::Backend;
 
 #[derive(Parser)]
 #[clap(author, version, about)] // TODO: Add author and about to toml
-#[clap(override_usage = "arena [OPTIONS] [--arc (Default) | --tgc | --spill] <FILE>")]
-#[clap(group(
-            clap::ArgGroup::new("GC")
-                .required(false)
-                .args(&["spill", "arc", "tgc"]),
-        ))]
+pub struct Cli {
+    #[clap(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Compile a program to an executable
+    Build(BuildArgs),
+    /// Compile a program to a temporary executable and run it
+    Run(RunArgs),
+    /// Parse and type check a program without compiling it
+    Check(CheckArgs),
+    /// Emit an intermediate representation of a program
+    Emit(EmitArgs),
+    /// Run a program in the interpreter without compiling it
+    Interpret(InterpretArgs),
+    /// Evaluate declarations and expressions interactively
+    Repl(ReplArgs),
+    /// Run a language server communicating over stdin and stdout
+    Lsp,
+    /// Format source files in the canonical style
+    Fmt(FmtArgs),
+    /// Compile and run the tests under every GC backend
+    Test(TestArgs),
+    /// Benchmark a program under every GC backend
+    Bench(BenchArgs),
+}
+
+#[derive(Args)]
 #[clap(group(
             clap::ArgGroup::new("Profiling")
                 .required(false)
-                .args(&["stack-profiling", "heap-profiling"]),
+                .args(&["stack-profiling", "heap-profiling", "gc-stats"]),
         ))]
-pub struct Cli {
+pub struct CompileArgs {
     /// Path of code to be compiled
     #[clap(parse(from_os_str), value_name = "FILE")]
     pub file_path: PathBuf,
 
-    /// Spill all allocated objects
-    #[clap(long)]
-    pub spill: bool,
-
-    /// Use Automatic Reference counting
-    #[clap(long)]
-    pub arc: bool,
-
-    /// Use Tracing Garbage Collection
-    #[clap(long)]
-    pub tgc: bool,
+    #[clap(flatten)]
+    pub gc: GcArgs,
 
     /// Enable stack profiling
     #[clap(long)]
@@ -47,139 +56,278 @@ pub struct Cli {
     #[clap(long)]
     pub heap_profiling: bool,
 
+    /// Print GC pause-time statistics to stderr at exit
+    #[clap(long, arg_enum, value_name = "FORMAT", require_equals = true)]
+    pub gc_stats: Option<Option<GcStatsFormat>>,
+
     /// Profiling Frequency to use
     #[clap(short, long)]
     pub profiling_frequency: Option<u64>,
 
-    /// Name of executable
-    #[clap(short, parse(from_os_str), value_name = "file")]
-    pub o: Option<PathBuf>,
+    /// Skip the runtime bounds and overflow checks
+    #[clap(long)]
+    pub unchecked: bool,
 
-    /// Keep temporary .ll and .s files
-    #[clap(short, long)]
-    pub keep_temporaries: bool,
+    #[clap(flatten)]
+    pub include: IncludeArgs,
+
+    #[clap(flatten)]
+    pub link: LinkArgs,
 
     /// Print Code, AST and LLVM Code
     #[clap(short, long)]
     pub verbose: bool,
+}
+
+#[derive(Args)]
+pub struct IncludeArgs {
+    /// Search for imported modules in the folder, before ARENA_PATH and the
+    /// library folders
+    #[clap(
+        short = 'I',
+        parse(from_os_str),
+        value_name = "DIR",
+        multiple_occurrences = true
+    )]
+    pub include_folders: Vec<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct LinkArgs {
+    /// Link the library, for extern functions that are not in libc
+    #[clap(short = 'l', value_name = "LIBRARY", multiple_occurrences = true)]
+    pub libraries: Vec<String>,
 
-    /// Print AST
+    /// Search for libraries in the folder
+    #[clap(
+        short = 'L',
+        parse(from_os_str),
+        value_name = "DIR",
+        multiple_occurrences = true
+    )]
+    pub library_paths: Vec<PathBuf>,
+}
+
+// The GC backend of compiled programs, or the heap model of the interpreter
+#[derive(Args)]
+#[clap(group(
+            clap::ArgGroup::new("GC")
+                .required(false)
+                .args(&["spill", "arc", "tgc"]),
+        ))]
+pub struct GcArgs {
+    /// Never free allocated objects
     #[clap(long)]
-    pub print_ast: bool,
+    pub spill: bool,
 
-    /// Print Typed AST
+    /// Use Automatic Reference Counting (Default)
     #[clap(long)]
-    pub print_typed_ast: bool,
+    pub arc: bool,
 
-    /// Print LLVM Code
+    /// Use Tracing Garbage Collection
     #[clap(long)]
-    pub print_llvm: bool,
-}
-
-pub fn input() -> (HashMap<PathBuf, String>, Cli) {
-    let mut cli = Cli::parse();
-    cli.file_path = fs::canonicalize(cli.file_path).expect("Incorrect Path");
-    let mut files = HashMap::new();
-    import_files(&mut files, vec![cli.file_path.clone()]);
-    (files, cli)
-}
-
-fn import_files(files: &mut HashMap<PathBuf, String>, mut import_queue: Vec<PathBuf>) {
-    let tail = import_queue.pop();
-    match tail {
-        None => (),
-        Some(path) => {
-            let code = fs::read_to_string(&path).expect("Something went wrong reading the file");
-            let imports = parse_imports(code.as_str())
-                .into_iter()
-                .map(|i| resolve_import(&path, &i))
-                .collect::<Vec<PathBuf>>();
-            files.insert(path, code);
-            for imp in imports {
-                if !files.contains_key(&imp) && !import_queue.contains(&imp) {
-                    import_queue.push(imp)
-                }
-            }
-            import_files(files, import_queue);
+    pub tgc: bool,
+}
+
+impl GcArgs {
+    pub fn backend(&self) -> Backend {
+        if self.spill {
+            Backend::Spill
+        } else if self.tgc {
+            Backend::Tgc
+        } else {
+            Backend::Arc
         }
     }
 }
 
-pub fn resolve_import(current_file: &PathBuf, import: &str) -> PathBuf {
-    let import_path = PathBuf::from(import).with_extension("arena");
-
-    // Check at current_file location:
-    let current_folder = current_file.parent().unwrap_or_else(|| {
-        panic!(
-            "Could not get folder containing {}",
-            current_file.to_str().unwrap()
-        )
-    });
-    fs::canonicalize(current_folder.join(&import_path)).unwrap_or_else(|_| {
-        // Check at ~/.arena/lib:
-        let home = std::env::var("HOME").expect("Could not get HOME directory");
-        let user_lib_folder = PathBuf::from(home).join(".arena/lib").join(&import_path);
-        fs::canonicalize(&user_lib_folder).unwrap_or_else(|_| {
-            let exe_path = std::env::current_exe().expect("Could not get executable path");
-            let lib_folder = exe_path
-                .parent()
-                .expect("Could not get executable folder")
-                .join("lib")
-                .join(&import_path);
-            fs::canonicalize(&lib_folder)
-                .unwrap_or_else(|_| panic!("Could not resolve import path of {:?}", import))
-        })
-    })
-}
-
-pub fn resolve_all_imports<'input>(
-    asts: HashMap<&PathBuf, Module<'input>>,
-    main_module_path: &PathBuf,
-) -> (HashMap<Rc<String>, Module<'input>>, Rc<String>) {
-    // returns (modules, main_module_id)
-
-    let mut module_prefixes = HashMap::new();
-    (
-        asts.into_iter()
-            .map(|(path, module)| {
-                (
-                    get_module_prefix(path, &mut module_prefixes),
-                    Module {
-                        imports: module
-                            .imports
-                            .into_iter()
-                            .map(|(name, imp_path)| {
-                                (
-                                    name,
-                                    get_module_prefix(
-                                        &resolve_import(path, &imp_path),
-                                        &mut module_prefixes,
-                                    ),
-                                )
-                            })
-                            .collect(),
-                        types: module.types,
-                        functions: module.functions,
-                    },
-                )
-            })
-            .collect(),
-        Rc::clone(&module_prefixes[main_module_path]),
-    )
-}
-
-fn get_module_prefix(
-    module: &PathBuf,
-    module_prefixes: &mut HashMap<PathBuf, Rc<String>>,
-) -> Rc<String> {
-    match module_prefixes.get(module) {
-        None => {
-            let mut hasher = DefaultHasher::new();
-            module.hash(&mut hasher);
-            let hash = format!("${:x}$", hasher.finish());
-            module_prefixes.insert(module.to_path_buf(), Rc::new(hash));
-            Rc::clone(module_prefixes.get(module).unwrap())
+#[derive(Args)]
+pub struct BuildArgs {
+    #[clap(flatten)]
+    pub compile: CompileArgs,
+
+    /// Name of executable
+    #[clap(short, parse(from_os_str), value_name = "file", default_value = "out")]
+    pub o: PathBuf,
+}
+
+#[derive(Args)]
+pub struct RunArgs {
+    #[clap(flatten)]
+    pub compile: CompileArgs,
+
+    /// Arguments passed to the program
+    #[clap(last = true, value_name = "ARGS")]
+    pub args: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct CheckArgs {
+    /// Path of code to be checked
+    #[clap(parse(from_os_str), value_name = "FILE")]
+    pub file_path: PathBuf,
+
+    #[clap(flatten)]
+    pub include: IncludeArgs,
+}
+
+#[derive(Args)]
+pub struct EmitArgs {
+    #[clap(flatten)]
+    pub compile: CompileArgs,
+
+    /// Representation to emit
+    #[clap(long, arg_enum)]
+    pub kind: EmitKind,
+
+    /// Output file [default: stdout, out.o for obj]
+    #[clap(short, parse(from_os_str), value_name = "file")]
+    pub o: Option<PathBuf>,
+}
+
+#[derive(ArgEnum, Clone, Copy, PartialEq)]
+pub enum EmitKind {
+    Ast,
+    TypedAst,
+    Llvm,
+    Asm,
+    Obj,
+}
+
+#[derive(Args)]
+pub struct InterpretArgs {
+    /// Path of code to be interpreted
+    #[clap(parse(from_os_str), value_name = "FILE")]
+    pub file_path: PathBuf,
+
+    #[clap(flatten)]
+    pub include: IncludeArgs,
+
+    #[clap(flatten)]
+    pub gc: GcArgs,
+
+    /// Arguments passed to the program
+    #[clap(last = true, value_name = "ARGS")]
+    pub args: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct ReplArgs {
+    /// Program whose definitions are loaded at startup
+    #[clap(parse(from_os_str), value_name = "FILE")]
+    pub file_path: Option<PathBuf>,
+
+    #[clap(flatten)]
+    pub include: IncludeArgs,
+
+    #[clap(flatten)]
+    pub gc: GcArgs,
+}
+
+#[derive(Args)]
+pub struct FmtArgs {
+    /// Files or folders to format [default: .]
+    #[clap(parse(from_os_str), value_name = "PATH")]
+    pub paths: Vec<PathBuf>,
+
+    /// Do not write files, but list the ones that are not formatted and
+    /// exit with code 1 if there are any
+    #[clap(long)]
+    pub check: bool,
+}
+
+#[derive(Args)]
+pub struct TestArgs {
+    /// Test files or folders containing tests [default: tests]
+    #[clap(parse(from_os_str), value_name = "PATH")]
+    pub paths: Vec<PathBuf>,
+
+    /// Only test the given GC backends [default: all]
+    #[clap(long, arg_enum, value_name = "GC")]
+    pub gc: Vec<GcBackend>,
+
+    /// Number of tests to run in parallel [default: number of CPUs]
+    #[clap(short, long)]
+    pub jobs: Option<usize>,
+
+    /// Also run every test in the interpreter with the heap model of each
+    /// GC backend
+    #[clap(long)]
+    pub interpret: bool,
+
+    /// Fail a test if compiling or running it takes longer than the given
+    /// number of seconds
+    #[clap(long, value_name = "SECONDS", default_value = "60")]
+    pub timeout: u64,
+}
+
+#[derive(Args)]
+pub struct BenchArgs {
+    /// Path of the program to benchmark
+    #[clap(parse(from_os_str), value_name = "FILE")]
+    pub file_path: PathBuf,
+
+    /// Number of runs per GC backend
+    #[clap(short = 'n', long, default_value = "5")]
+    pub runs: usize,
+
+    /// Only benchmark the given GC backends [default: all]
+    #[clap(long, arg_enum, value_name = "GC")]
+    pub gc: Vec<GcBackend>,
+
+    /// Write the results as CSV to the given file [default: stdout]
+    #[clap(long, parse(from_os_str), value_name = "FILE")]
+    pub csv: Option<PathBuf>,
+
+    /// Write the results as JSON to the given file
+    #[clap(long, parse(from_os_str), value_name = "FILE")]
+    pub json: Option<PathBuf>,
+
+    /// Compile the program without runtime bounds and overflow checks
+    #[clap(long)]
+    pub unchecked: bool,
+
+    #[clap(flatten)]
+    pub include: IncludeArgs,
+
+    #[clap(flatten)]
+    pub link: LinkArgs,
+
+    /// Arguments passed to the program, like the size of the problem
+    #[clap(last = true, value_name = "ARGS")]
+    pub args: Vec<String>,
+}
+
+#[derive(ArgEnum, Clone, Copy)]
+pub enum GcStatsFormat {
+    Text,
+    Json,
+}
+
+// The GC backends selectable with --gc, which are mapped to the backends of
+// the library
+#[derive(ArgEnum, Clone, Copy)]
+pub enum GcBackend {
+    Arc,
+    Tgc,
+    Spill,
+}
+
+impl From<GcBackend> for Backend {
+    fn from(gc: GcBackend) -> Self {
+        match gc {
+            GcBackend::Arc => Backend::Arc,
+            GcBackend::Tgc => Backend::Tgc,
+            GcBackend::Spill => Backend::Spill,
         }
-        Some(name) => Rc::clone(name),
+    }
+}
+
+// The backends selected with --gc, or all of them if none are
+pub fn selected_backends(gc: &[GcBackend]) -> Vec<Backend> {
+    if gc.is_empty() {
+        Backend::ALL.to_vec()
+    } else {
+        gc.iter().map(|&gc| gc.into()).collect()
     }
 }
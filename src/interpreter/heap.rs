@@ -0,0 +1,269 @@
+use std::collections::HashSet;
+
+use crate::interpreter::{ObjRef, Object, RuntimeError, Value};
+
+// Initial size of the TGC nursery, has to match NURSERY_LEN_BITS in
+// runtime/libarena.c
+const NURSERY_LEN: u64 = 1 << 15;
+
+// Heap model of the interpreter. The interpreter calls it at the same points
+// where the code generator calls the corresponding GC function, so each model
+// frees objects exactly when the runtime of its GC backend would.
+pub trait Heap {
+    // Mirrors GC::type_allocation. roots are the objects on the arena stack.
+    fn alloc(&mut self, object: Object, roots: &[ObjRef]) -> ObjRef;
+
+    fn get(&self, obj: ObjRef) -> Result<&Object, RuntimeError>;
+
+    // Mirrors GC::type_ptr_access
+    fn ptr_access(&mut self, obj: ObjRef) -> Result<(), RuntimeError>;
+
+    // Mirrors GC::type_ptr_drop
+    fn ptr_drop(&mut self, obj: ObjRef) -> Result<(), RuntimeError>;
+
+    // Mirrors GC::close_heap
+    fn close(&mut self);
+}
+
+struct Slot {
+    generation: u32,
+    object: Option<Object>,
+}
+
+// Object storage shared by the heap models. Freed slots are reused with a new
+// generation, so references to freed objects are detected instead of reading
+// whatever object took their place.
+#[derive(Default)]
+struct Slots {
+    slots: Vec<Slot>,
+    free: Vec<usize>,
+}
+
+impl Slots {
+    fn alloc(&mut self, object: Object) -> ObjRef {
+        match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index];
+                slot.generation += 1;
+                slot.object = Some(object);
+                ObjRef {
+                    index,
+                    generation: slot.generation,
+                }
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    object: Some(object),
+                });
+                ObjRef {
+                    index: self.slots.len() - 1,
+                    generation: 0,
+                }
+            }
+        }
+    }
+
+    fn get(&self, obj: ObjRef) -> Result<&Object, RuntimeError> {
+        self.slots
+            .get(obj.index)
+            .filter(|slot| slot.generation == obj.generation)
+            .and_then(|slot| slot.object.as_ref())
+            .ok_or_else(|| dangling(obj))
+    }
+
+    fn get_mut(&mut self, obj: ObjRef) -> Result<&mut Object, RuntimeError> {
+        self.slots
+            .get_mut(obj.index)
+            .filter(|slot| slot.generation == obj.generation)
+            .and_then(|slot| slot.object.as_mut())
+            .ok_or_else(|| dangling(obj))
+    }
+
+    fn free(&mut self, obj: ObjRef) -> Result<Object, RuntimeError> {
+        self.get(obj)?;
+        self.free.push(obj.index);
+        Ok(self.slots[obj.index].object.take().unwrap())
+    }
+
+    fn live(&self) -> impl Iterator<Item = ObjRef> + '_ {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.object.is_some())
+            .map(|(index, slot)| ObjRef {
+                index,
+                generation: slot.generation,
+            })
+    }
+}
+
+fn dangling(obj: ObjRef) -> RuntimeError {
+    RuntimeError {
+        message: format!("Access to freed object {}", obj.index),
+    }
+}
+
+// Never frees an object, like the Spill backend
+#[derive(Default)]
+pub struct SpillHeap {
+    slots: Slots,
+}
+
+impl Heap for SpillHeap {
+    fn alloc(&mut self, object: Object, _roots: &[ObjRef]) -> ObjRef {
+        self.slots.alloc(object)
+    }
+
+    fn get(&self, obj: ObjRef) -> Result<&Object, RuntimeError> {
+        self.slots.get(obj)
+    }
+
+    fn ptr_access(&mut self, _obj: ObjRef) -> Result<(), RuntimeError> {
+        Ok(())
+    }
+
+    fn ptr_drop(&mut self, _obj: ObjRef) -> Result<(), RuntimeError> {
+        Ok(())
+    }
+
+    fn close(&mut self) {}
+}
+
+// Reference counting like arc_ptr_access and arc_drop_ptr. Counts start at 1
+// and wrap around like the uint32_t header of the runtime.
+#[derive(Default)]
+pub struct ArcHeap {
+    slots: Slots,
+}
+
+impl Heap for ArcHeap {
+    fn alloc(&mut self, mut object: Object, _roots: &[ObjRef]) -> ObjRef {
+        object.header = 1;
+        self.slots.alloc(object)
+    }
+
+    fn get(&self, obj: ObjRef) -> Result<&Object, RuntimeError> {
+        self.slots.get(obj)
+    }
+
+    fn ptr_access(&mut self, obj: ObjRef) -> Result<(), RuntimeError> {
+        let object = self.slots.get_mut(obj)?;
+        object.header = object.header.wrapping_add(1);
+        Ok(())
+    }
+
+    fn ptr_drop(&mut self, obj: ObjRef) -> Result<(), RuntimeError> {
+        let object = self.slots.get_mut(obj)?;
+        object.header = object.header.wrapping_sub(1);
+        if object.header == 0 {
+            // arc_free_obj
+            let object = self.slots.free(obj)?;
+            for field in object.fields {
+                if let Value::Ref(field) = field {
+                    self.ptr_drop(field)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn close(&mut self) {}
+}
+
+// Copying collection like tgc_type_alloc. Objects are collected once the
+// allocated bytes would exceed the nursery, keeping everything reachable from
+// the arena stack. If the object still does not fit, the nursery is doubled
+// like in tgc_grow_nursery. Object sizes are approximated from their fields.
+pub struct TgcHeap {
+    slots: Slots,
+    allocated: u64,
+    nursery_len: u64,
+}
+
+impl Default for TgcHeap {
+    fn default() -> Self {
+        TgcHeap {
+            slots: Slots::default(),
+            allocated: 0,
+            nursery_len: NURSERY_LEN,
+        }
+    }
+}
+
+impl TgcHeap {
+    fn collect(&mut self, roots: &[ObjRef]) {
+        let mut reachable = HashSet::new();
+        let mut worklist = roots.to_vec();
+        while let Some(obj) = worklist.pop() {
+            if reachable.insert(obj) {
+                if let Ok(object) = self.slots.get(obj) {
+                    worklist.extend(object.fields.iter().filter_map(|field| match field {
+                        Value::Ref(field) => Some(*field),
+                        _ => None,
+                    }));
+                }
+            }
+        }
+        let garbage = self
+            .slots
+            .live()
+            .filter(|obj| !reachable.contains(obj))
+            .collect::<Vec<_>>();
+        for obj in garbage {
+            let _ = self.slots.free(obj);
+        }
+        self.allocated = self
+            .slots
+            .live()
+            .map(|obj| object_size(self.slots.get(obj).unwrap()))
+            .sum();
+    }
+}
+
+impl Heap for TgcHeap {
+    fn alloc(&mut self, object: Object, roots: &[ObjRef]) -> ObjRef {
+        let size = object_size(&object);
+        if self.allocated + size >= self.nursery_len {
+            self.collect(roots);
+            while self.allocated + size >= self.nursery_len {
+                self.nursery_len *= 2;
+            }
+        }
+        self.allocated += size;
+        self.slots.alloc(object)
+    }
+
+    fn get(&self, obj: ObjRef) -> Result<&Object, RuntimeError> {
+        self.slots.get(obj)
+    }
+
+    fn ptr_access(&mut self, _obj: ObjRef) -> Result<(), RuntimeError> {
+        Ok(())
+    }
+
+    fn ptr_drop(&mut self, _obj: ObjRef) -> Result<(), RuntimeError> {
+        Ok(())
+    }
+
+    fn close(&mut self) {
+        self.collect(&[]);
+    }
+}
+
+// Header, case id, fields and bytes of strings padded to 8 bytes like
+// tgc_type_alloc
+fn object_size(object: &Object) -> u64 {
+    let fields = object.fields.iter().map(value_size).sum::<u64>();
+    (8 + fields + object.bytes.len() as u64).div_ceil(8) * 8
+}
+
+fn value_size(value: &Value) -> u64 {
+    match value {
+        Value::Bool(_) | Value::U8(_) => 1,
+        Value::I32(_) => 4,
+        Value::Str(_) | Value::Ref(_) => 8,
+        Value::Tuple(fields) => fields.iter().map(value_size).sum(),
+        Value::Void => 0,
+    }
+}
@@ -0,0 +1,946 @@
+mod heap;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{BuildHasherDefault, Hasher};
+use std::io::{BufRead, Write};
+use std::rc::Rc;
+
+use crate::binary::{BinExpr, BinFunction, Binary, TypedExpr};
+use crate::codegen::build_in::BuildIn;
+use crate::module::Const;
+use crate::types::{
+    array_element, is_unboxed_tuple, tuple_fields, EXIT_TYPE, STRING_TYPE, VOID_TYPE,
+};
+
+pub use heap::{ArcHeap, Heap, SpillHeap, TgcHeap};
+
+#[derive(Clone, Debug)]
+pub enum Value {
+    Bool(bool),
+    U8(u8),
+    I32(i32),
+    Str(Rc<String>),
+    Ref(ObjRef),
+    // A tuple without references, which is a value like in compiled code
+    Tuple(Rc<Vec<Value>>),
+    Void,
+}
+
+// Reference to an object of a user defined type
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ObjRef {
+    index: usize,
+    generation: u32,
+}
+
+pub struct Object {
+    // GC header, only used by the heap model
+    pub header: u32,
+    pub case: usize,
+    pub fields: Vec<Value>,
+    // Bytes of a string, whose only field is its length like in the runtime
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct RuntimeError {
+    pub message: String,
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+// Exit code of programs stopped by panic or assert, like in the runtime
+const PANIC_EXIT_CODE: i32 = 101;
+
+// Stops the evaluation of the program
+enum Interrupt {
+    Exit(i32),
+    Error(RuntimeError),
+}
+
+impl From<RuntimeError> for Interrupt {
+    fn from(error: RuntimeError) -> Self {
+        Interrupt::Error(error)
+    }
+}
+
+fn error(message: String) -> Interrupt {
+    Interrupt::Error(RuntimeError { message })
+}
+
+// Runs the main function of the binary and returns the exit code of the
+// program. The output of print is written to out, the output of eprint and the
+// messages of panics to err.
+pub fn interpret<H: Heap>(
+    binary: &Binary,
+    heap: H,
+    args: &[String],
+    out: &mut impl Write,
+    err: &mut impl Write,
+) -> Result<i32, RuntimeError> {
+    let mut interpreter = Interpreter {
+        binary,
+        callees: HashMap::default(),
+        heap,
+        stack: Vec::new(),
+        vars: Vec::new(),
+        args,
+        io_error: String::new(),
+        out,
+        err,
+    };
+    let main = binary
+        .functions
+        .get(&Rc::new("main".to_string()))
+        .ok_or_else(|| RuntimeError {
+            message: "Could not find main function".to_string(),
+        })?;
+    // The arguments of the C main function, which the interpreter does not
+    // need as it reads the arguments from args
+    interpreter.vars.push(("argc", Value::I32(args.len() as i32)));
+    interpreter.vars.push(("argv", Value::Void));
+    let result = match interpreter.call_function(main, 0, 0) {
+        Ok(value) => {
+            interpreter.heap.close();
+            match value {
+                Value::I32(code) => Ok(code),
+                _ => Ok(0),
+            }
+        }
+        Err(Interrupt::Exit(code)) => Ok(code),
+        Err(Interrupt::Error(error)) => Err(error),
+    };
+    interpreter.out.flush().map_err(|e| RuntimeError {
+        message: e.to_string(),
+    })?;
+    interpreter.err.flush().map_err(|e| RuntimeError {
+        message: e.to_string(),
+    })?;
+    result
+}
+
+// Evaluates a single expression of the binary, like an expression typed into
+// the REPL, and returns its value written as an Arena literal. Returns None if
+// the expression has no value.
+pub fn evaluate<H: Heap>(
+    binary: &Binary,
+    heap: H,
+    expr: &TypedExpr,
+    out: &mut impl Write,
+    err: &mut impl Write,
+) -> Result<Option<String>, RuntimeError> {
+    let mut interpreter = Interpreter {
+        binary,
+        callees: HashMap::default(),
+        heap,
+        stack: Vec::new(),
+        vars: Vec::new(),
+        args: &[],
+        io_error: String::new(),
+        out,
+        err,
+    };
+    let result = match interpreter.eval(expr, 0, 0) {
+        Ok(_) if [VOID_TYPE, EXIT_TYPE].contains(&expr.expr_type.as_str()) => Ok(None),
+        Ok(value) => interpreter.show(&value, &expr.expr_type).map(Some),
+        Err(Interrupt::Exit(code)) => Err(RuntimeError {
+            message: format!("Program exited with code {}", code),
+        }),
+        Err(Interrupt::Error(error)) => Err(error),
+    };
+    interpreter.out.flush().map_err(|e| RuntimeError {
+        message: e.to_string(),
+    })?;
+    interpreter.err.flush().map_err(|e| RuntimeError {
+        message: e.to_string(),
+    })?;
+    result
+}
+
+// Name of a type as written in the module that defines it. User defined type
+// ids are prefixed with the id of their module.
+pub fn type_name(type_id: &str) -> String {
+    if let Some(element) = array_element(type_id) {
+        return format!("[{}]", type_name(&element));
+    }
+    if let Some(fields) = tuple_fields(type_id) {
+        let fields = fields
+            .iter()
+            .map(|field| type_name(field))
+            .collect::<Vec<_>>();
+        return format!("({})", fields.join(", "));
+    }
+    match type_id.rsplit_once('$') {
+        Some((_, name)) if type_id.starts_with('$') && !name.is_empty() => name.to_string(),
+        _ => type_id.to_string(),
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Callee<'a> {
+    Function(&'a BinFunction<'a>),
+    BuildIn(BuildIn),
+    Extern,
+}
+
+// Function ids are shared Rcs, so callees are cached by the address of their
+// id instead of hashing the id on every call
+#[derive(Default)]
+struct AddressHasher(u64);
+
+impl Hasher for AddressHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.write_u8(*byte);
+        }
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.write_usize(i.into())
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.0 = (self.0 ^ i as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    }
+}
+
+struct Interpreter<'a, H: Heap, W: Write, E: Write> {
+    binary: &'a Binary<'a>,
+    callees: HashMap<*const String, Callee<'a>, BuildHasherDefault<AddressHasher>>,
+    heap: H,
+    // Mirrors the arena stack of the runtime, the objects on it are the roots
+    // of the tracing heap model
+    stack: Vec<ObjRef>,
+    // Variables of all active calls. The variables of a call start at its
+    // frame index, beginning with its parameters.
+    vars: Vec<(&'a str, Value)>,
+    // The command line arguments, starting with the name of the program
+    args: &'a [String],
+    // Message of the last failed I/O operation, empty if it succeeded
+    io_error: String,
+    out: &'a mut W,
+    err: &'a mut E,
+}
+
+fn is_user_type(type_id: &Rc<String>) -> bool {
+    // User defined types start with $
+    type_id.starts_with('$')
+}
+
+impl<'a, H: Heap, W: Write, E: Write> Interpreter<'a, H, W, E> {
+    // Mirrors stack_alloc, sp is the length of the used arena stack
+    fn stack_alloc(&mut self, sp: usize, value: &Value) -> usize {
+        if let Value::Ref(obj) = value {
+            self.stack.truncate(sp);
+            self.stack.push(*obj);
+        }
+        sp + 1
+    }
+
+    // The arguments are the variables starting at frame
+    fn call_function(
+        &mut self,
+        function: &'a BinFunction<'a>,
+        frame: usize,
+        mut sp: usize,
+    ) -> Result<Value, Interrupt> {
+        for (i, param) in function.args.iter().enumerate() {
+            self.vars[frame + i].0 = param.name;
+            if is_user_type(&param.param_type) {
+                let arg = self.vars[frame + i].1.clone();
+                sp = self.stack_alloc(sp, &arg);
+            }
+        }
+        let res = self.eval(&function.body, frame, sp)?;
+        for (i, param) in function.args.iter().enumerate() {
+            if let (true, Value::Ref(obj)) =
+                (is_user_type(&param.param_type), &self.vars[frame + i].1)
+            {
+                self.heap.ptr_drop(*obj)?;
+            }
+        }
+        self.vars.truncate(frame);
+        Ok(res)
+    }
+
+    fn eval(
+        &mut self,
+        ast: &'a TypedExpr<'a>,
+        frame: usize,
+        sp: usize,
+    ) -> Result<Value, Interrupt> {
+        match &ast.expr {
+            BinExpr::Array(elements) => {
+                let args = self.vars.len();
+                let new_sp = self.compute_params(elements, frame, sp)?;
+                let elements = self.vars.drain(args..).map(|(_, value)| value).collect();
+                Ok(self.alloc_array(elements, new_sp))
+            }
+            BinExpr::Const(c) => Ok(match c {
+                Const::Bool(b) => Value::Bool(*b),
+                Const::U8(i) => Value::U8(*i),
+                Const::I32(i) => Value::I32(*i),
+                Const::Str(s) => Value::Str(Rc::clone(s)),
+                Const::Void => Value::Void,
+            }),
+            BinExpr::FuncCall(func_id, params) => {
+                let args = self.vars.len();
+                let new_sp = self.compute_params(params, frame, sp)?;
+                match self.callee(func_id) {
+                    Callee::Function(function) => self.call_function(function, args, new_sp),
+                    Callee::Extern => Err(error(format!(
+                        "Extern function {} can not be interpreted",
+                        func_id
+                    ))),
+                    Callee::BuildIn(build_in) => {
+                        let res = match self.call_string_build_in(build_in, args, new_sp)? {
+                            Some(res) => Ok(res),
+                            None => match self.call_array_build_in(build_in, args, new_sp)? {
+                                Some(res) => Ok(res),
+                                None => match self.call_env_build_in(build_in, args, new_sp) {
+                                    Some(res) => Ok(res),
+                                    None => match self.call_io_build_in(build_in, args, new_sp)? {
+                                        Some(res) => Ok(res),
+                                        None => call_build_in(
+                                            build_in,
+                                            func_id,
+                                            &self.vars[args..],
+                                            self.out,
+                                            self.err,
+                                        ),
+                                    },
+                                },
+                            },
+                        };
+                        self.vars.truncate(args);
+                        res
+                    }
+                }
+            }
+            BinExpr::GetTypeCaseField(obj, _, field_index) if is_unboxed_tuple(&obj.expr_type) => {
+                match self.eval(obj, frame, sp)? {
+                    Value::Tuple(fields) => Ok(fields[*field_index].clone()),
+                    v => Err(error(format!("Expected tuple, got {:?}", v))),
+                }
+            }
+            BinExpr::GetTypeCaseField(obj, case, field_index) => {
+                let obj_ref = self.eval_ref(obj, frame, sp)?;
+                let object = self.heap.get(obj_ref)?;
+                if object.case != self.case_index(&obj.expr_type, case) {
+                    return Err(error(format!(
+                        "Accessing field of case {} on another case of {}",
+                        case, obj.expr_type
+                    )));
+                }
+                let field = object.fields[*field_index].clone();
+                if let Value::Ref(field_ref) = field {
+                    self.heap.ptr_access(field_ref)?;
+                }
+                self.heap.ptr_drop(obj_ref)?;
+                Ok(field)
+            }
+            BinExpr::If(cond, b1, b2) => match self.eval(cond, frame, sp)? {
+                Value::Bool(true) => self.eval(b1, frame, sp),
+                Value::Bool(false) => self.eval(b2, frame, sp),
+                v => Err(error(format!("Condition evaluated to {:?}", v))),
+            },
+            BinExpr::IsCase(obj, case) => {
+                let obj_ref = self.eval_ref(obj, frame, sp)?;
+                let is_case = self.heap.get(obj_ref)?.case == self.case_index(&obj.expr_type, case);
+                self.heap.ptr_drop(obj_ref)?;
+                Ok(Value::Bool(is_case))
+            }
+            BinExpr::Let(id, def, body) => {
+                let definition = self.eval(def, frame, sp)?;
+                let new_sp = if is_user_type(&def.expr_type) {
+                    self.stack_alloc(sp, &definition)
+                } else {
+                    sp
+                };
+                self.vars.push((id, definition.clone()));
+                let res = self.eval(body, frame, new_sp)?;
+                self.vars.pop();
+                if let Value::Ref(obj) = definition {
+                    self.heap.ptr_drop(obj)?;
+                }
+                Ok(res)
+            }
+            BinExpr::Seq(e1, e2) => {
+                if let Value::Ref(obj) = self.eval(e1, frame, sp)? {
+                    self.heap.ptr_drop(obj)?;
+                }
+                self.eval(e2, frame, sp)
+            }
+            BinExpr::TypeCase(ty, case, fields) => {
+                let args = self.vars.len();
+                let new_sp = self.compute_params(fields, frame, sp)?;
+                if is_unboxed_tuple(ty) {
+                    let fields = self.vars.drain(args..).map(|(_, value)| value).collect();
+                    return Ok(Value::Tuple(Rc::new(fields)));
+                }
+                let object = Object {
+                    header: 0,
+                    case: self.case_index(ty, case),
+                    fields: self.vars.drain(args..).map(|(_, value)| value).collect(),
+                    bytes: Vec::new(),
+                };
+                Ok(Value::Ref(self.heap.alloc(object, &self.stack[..new_sp])))
+            }
+            BinExpr::Var(id) => {
+                let var = self.vars[frame..]
+                    .iter()
+                    .rev()
+                    .find(|(name, _)| name == id)
+                    .map(|(_, value)| value.clone())
+                    .ok_or_else(|| error(format!("Could not find variable {}", id)))?;
+                if let Value::Ref(obj) = var {
+                    self.heap.ptr_access(obj)?;
+                }
+                Ok(var)
+            }
+        }
+    }
+
+    fn eval_ref(
+        &mut self,
+        ast: &'a TypedExpr<'a>,
+        frame: usize,
+        sp: usize,
+    ) -> Result<ObjRef, Interrupt> {
+        match self.eval(ast, frame, sp)? {
+            Value::Ref(obj) => Ok(obj),
+            v => Err(error(format!(
+                "Expected object of type {}, got {:?}",
+                ast.expr_type, v
+            ))),
+        }
+    }
+
+    // Evaluates the parameters and pushes them on the variables. Objects are
+    // put on the arena stack in case the next allocation leads to a garbage
+    // collection.
+    fn compute_params(
+        &mut self,
+        params: &'a [TypedExpr<'a>],
+        frame: usize,
+        mut sp: usize,
+    ) -> Result<usize, Interrupt> {
+        for param in params {
+            let computed_param = self.eval(param, frame, sp)?;
+            if is_user_type(&param.expr_type) {
+                sp = self.stack_alloc(sp, &computed_param);
+            }
+            self.vars.push(("", computed_param));
+        }
+        Ok(sp)
+    }
+
+    fn callee(&mut self, func_id: &Rc<String>) -> Callee<'a> {
+        let binary = self.binary;
+        *self.callees.entry(Rc::as_ptr(func_id)).or_insert_with(|| {
+            match binary.functions.get(func_id) {
+                Some(function) => Callee::Function(function),
+                None if binary.externs.contains_key(func_id) => Callee::Extern,
+                None => Callee::BuildIn(BuildIn::from_str(func_id.as_str())),
+            }
+        })
+    }
+
+    fn case_index(&self, type_id: &Rc<String>, case: &str) -> usize {
+        self.binary.types[type_id]
+            .cases
+            .iter()
+            .position(|c| c.name == case)
+            .unwrap_or_else(|| panic!("Type {} has no case {}", type_id, case))
+    }
+
+    fn show(&self, value: &Value, type_id: &Rc<String>) -> Result<String, RuntimeError> {
+        Ok(match value {
+            Value::Bool(b) => b.to_string(),
+            Value::U8(i) => format!("{}u8", i),
+            Value::I32(i) => i.to_string(),
+            Value::Str(s) => format!(
+                "\"{}\"",
+                s.replace('\\', "\\\\")
+                    .replace('\n', "\\n")
+                    .replace('\r', "\\r")
+                    .replace('\t', "\\t")
+            ),
+            Value::Tuple(fields) => self.show_tuple(fields, type_id)?,
+            Value::Ref(obj) if type_id.as_str() == STRING_TYPE => {
+                let bytes = &self.heap.get(*obj)?.bytes;
+                self.show(
+                    &Value::Str(Rc::new(String::from_utf8_lossy(bytes).to_string())),
+                    type_id,
+                )?
+            }
+            Value::Ref(obj) if tuple_fields(type_id).is_some() => {
+                self.show_tuple(&self.heap.get(*obj)?.fields, type_id)?
+            }
+            Value::Ref(obj) if array_element(type_id).is_some() => {
+                let element_type = array_element(type_id).unwrap();
+                let elements = self
+                    .heap
+                    .get(*obj)?
+                    .fields
+                    .iter()
+                    .map(|element| self.show(element, &element_type))
+                    .collect::<Result<Vec<_>, _>>()?;
+                format!("[{}]", elements.join(", "))
+            }
+            Value::Ref(obj) => {
+                let object = self.heap.get(*obj)?;
+                let case = &self.binary.types[type_id].cases[object.case];
+                let mut shown = format!("{}.{}", type_name(type_id), case.name);
+                if !object.fields.is_empty() {
+                    let fields = object
+                        .fields
+                        .iter()
+                        .zip(&case.fields)
+                        .map(|(field, field_type)| self.show(field, field_type))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    shown.push_str(&format!("({})", fields.join(", ")));
+                }
+                shown
+            }
+            Value::Void => "()".to_string(),
+        })
+    }
+
+    // Calls the build in functions of strings, which need the heap. Returns None
+    // for the other build in functions. Like compiled code they drop the
+    // strings passed to them.
+    fn call_string_build_in(
+        &mut self,
+        build_in: BuildIn,
+        args: usize,
+        sp: usize,
+    ) -> Result<Option<Value>, Interrupt> {
+        use Value::*;
+        if !matches!(
+            build_in,
+            BuildIn::string_str
+                | BuildIn::string_u8
+                | BuildIn::string_i32
+                | BuildIn::print_string
+                | BuildIn::len_string
+                | BuildIn::char_at_string
+                | BuildIn::concat_string
+                | BuildIn::slice_string
+                | BuildIn::find_string
+                | BuildIn::to_i32_string
+                | BuildIn::eq_string
+                | BuildIn::neq_string
+                | BuildIn::lt_string
+                | BuildIn::le_string
+                | BuildIn::gt_string
+                | BuildIn::ge_string
+                | BuildIn::panic_string
+        ) {
+            return Ok(None);
+        }
+        let values = self.vars[args..]
+            .iter()
+            .map(|(_, value)| value.clone())
+            .collect::<Vec<_>>();
+        let mut strings = Vec::new();
+        for value in values.iter() {
+            if let Ref(obj) = value {
+                strings.push(self.heap.get(*obj)?.bytes.clone());
+            }
+        }
+        let res = match (build_in, &values[..], &strings[..]) {
+            (BuildIn::string_str, [Str(s)], []) => self.alloc_string(s.as_bytes().to_vec(), sp),
+            (BuildIn::string_u8, [U8(c)], []) => self.alloc_string(vec![*c], sp),
+            (BuildIn::string_i32, [I32(i)], []) => {
+                self.alloc_string(i.to_string().into_bytes(), sp)
+            }
+            (BuildIn::print_string, _, [s]) => {
+                self.out.write_all(s).map_err(|e| error(e.to_string()))?;
+                Void
+            }
+            (BuildIn::len_string, _, [s]) => I32(s.len() as i32),
+            (BuildIn::char_at_string, [_, I32(i)], [s]) => {
+                match usize::try_from(*i).ok().and_then(|i| s.get(i)) {
+                    Some(c) => U8(*c),
+                    None => {
+                        return Err(error(format!(
+                            "char_at: index {} is out of bounds for a string of length {}",
+                            i,
+                            s.len()
+                        )))
+                    }
+                }
+            }
+            (BuildIn::concat_string, _, [a, b]) => self.alloc_string([&a[..], b].concat(), sp),
+            (BuildIn::slice_string, [_, I32(start), I32(end)], [s]) => {
+                // Indices outside of the string are moved to its start or end
+                let start = (*start).clamp(0, s.len() as i32) as usize;
+                let end = (*end).clamp(start as i32, s.len() as i32) as usize;
+                self.alloc_string(s[start..end].to_vec(), sp)
+            }
+            (BuildIn::find_string, _, [s, pattern]) => I32((0..=s.len())
+                .take_while(|i| i + pattern.len() <= s.len())
+                .find(|i| s[*i..].starts_with(pattern))
+                .map_or(-1, |i| i as i32)),
+            (BuildIn::to_i32_string, _, [s]) => I32(parse_i32(s)),
+            (BuildIn::eq_string, _, [a, b]) => Bool(a == b),
+            (BuildIn::neq_string, _, [a, b]) => Bool(a != b),
+            (BuildIn::lt_string, _, [a, b]) => Bool(a < b),
+            (BuildIn::le_string, _, [a, b]) => Bool(a <= b),
+            (BuildIn::gt_string, _, [a, b]) => Bool(a > b),
+            (BuildIn::ge_string, _, [a, b]) => Bool(a >= b),
+            (BuildIn::panic_string, [Str(location), _], [message]) => {
+                writeln!(self.err, "{}{}", location, String::from_utf8_lossy(message))
+                    .map_err(|e| error(e.to_string()))?;
+                return Err(Interrupt::Exit(PANIC_EXIT_CODE));
+            }
+            _ => return Ok(None),
+        };
+        for value in values {
+            if let Ref(obj) = value {
+                self.heap.ptr_drop(obj)?;
+            }
+        }
+        Ok(Some(res))
+    }
+
+    // Calls the build in functions of arrays like call_string_build_in. Arrays
+    // are objects whose fields are their elements.
+    fn call_array_build_in(
+        &mut self,
+        build_in: BuildIn,
+        args: usize,
+        sp: usize,
+    ) -> Result<Option<Value>, Interrupt> {
+        use Value::*;
+        if !matches!(
+            build_in,
+            BuildIn::len_array | BuildIn::get_array | BuildIn::set_array | BuildIn::push_array
+        ) {
+            return Ok(None);
+        }
+        let values = self.vars[args..]
+            .iter()
+            .map(|(_, value)| value.clone())
+            .collect::<Vec<_>>();
+        let array = match values.first() {
+            Some(Ref(obj)) => *obj,
+            _ => return Ok(None),
+        };
+        let elements = self.heap.get(array)?.fields.clone();
+        let index = |func_name: &str, i: i32| {
+            usize::try_from(i)
+                .ok()
+                .filter(|i| *i < elements.len())
+                .ok_or_else(|| {
+                    error(format!(
+                        "{}: index {} is out of bounds for an array of length {}",
+                        func_name,
+                        i,
+                        elements.len()
+                    ))
+                })
+        };
+        let res = match (build_in, &values[..]) {
+            (BuildIn::len_array, [_]) => I32(elements.len() as i32),
+            (BuildIn::get_array, [_, I32(i)]) => {
+                let element = elements[index("get", *i)?].clone();
+                if let Ref(element) = element {
+                    self.heap.ptr_access(element)?;
+                }
+                element
+            }
+            (BuildIn::set_array, [_, I32(i), element]) => {
+                let i = index("set", *i)?;
+                let mut elements = elements.clone();
+                elements[i] = element.clone();
+                for (index, element) in elements.iter().enumerate() {
+                    if let (true, Ref(element)) = (index != i, element) {
+                        self.heap.ptr_access(*element)?;
+                    }
+                }
+                self.alloc_array(elements, sp)
+            }
+            (BuildIn::push_array, [_, element]) => {
+                let mut elements = elements.clone();
+                for element in elements.iter() {
+                    if let Ref(element) = element {
+                        self.heap.ptr_access(*element)?;
+                    }
+                }
+                elements.push(element.clone());
+                self.alloc_array(elements, sp)
+            }
+            _ => return Ok(None),
+        };
+        self.heap.ptr_drop(array)?;
+        Ok(Some(res))
+    }
+
+    fn alloc_array(&mut self, elements: Vec<Value>, sp: usize) -> Value {
+        let object = Object {
+            header: 0,
+            case: 0,
+            fields: elements,
+            bytes: Vec::new(),
+        };
+        Value::Ref(self.heap.alloc(object, &self.stack[..sp]))
+    }
+
+    // The command line arguments and the environment variables
+    fn call_env_build_in(&mut self, build_in: BuildIn, args: usize, sp: usize) -> Option<Value> {
+        use Value::*;
+        Some(match (build_in, &self.vars[args..]) {
+            (BuildIn::init_args, _) => Void,
+            (BuildIn::arg_count, []) => I32(self.args.len() as i32),
+            (BuildIn::arg_at, [(_, I32(i))]) => Str(Rc::new(self.args[*i as usize].clone())),
+            (BuildIn::env_str, [(_, Str(name))]) => {
+                let value = std::env::var(name.as_str()).unwrap_or_default();
+                self.alloc_string(value.into_bytes(), sp)
+            }
+            _ => return None,
+        })
+    }
+
+    // Reading stdin and files and writing files and stderr like the runtime.
+    // Failed operations return empty strings and set the message of io_error.
+    fn call_io_build_in(
+        &mut self,
+        build_in: BuildIn,
+        args: usize,
+        sp: usize,
+    ) -> Result<Option<Value>, Interrupt> {
+        use Value::*;
+        if !matches!(
+            build_in,
+            BuildIn::read_line
+                | BuildIn::read_file_string
+                | BuildIn::write_file_string
+                | BuildIn::io_error
+                | BuildIn::eprint_str
+                | BuildIn::eprint_string
+        ) {
+            return Ok(None);
+        }
+        let values = self.vars[args..]
+            .iter()
+            .map(|(_, value)| value.clone())
+            .collect::<Vec<_>>();
+        let mut strings = Vec::new();
+        for value in values.iter() {
+            if let Ref(obj) = value {
+                strings.push(self.heap.get(*obj)?.bytes.clone());
+            }
+        }
+        let path = |bytes: &[u8]| String::from_utf8_lossy(bytes).to_string();
+        let res = match (build_in, &values[..], &strings[..]) {
+            (BuildIn::read_line, [], []) => {
+                let mut line = Vec::new();
+                self.io_error = match std::io::stdin().lock().read_until(b'\n', &mut line) {
+                    Ok(_) => String::new(),
+                    Err(e) => io_error_message("stdin", e),
+                };
+                self.alloc_string(line, sp)
+            }
+            (BuildIn::read_file_string, _, [file]) => {
+                let file = path(file);
+                let content = match std::fs::read(&file) {
+                    Ok(content) => {
+                        self.io_error = String::new();
+                        content
+                    }
+                    Err(e) => {
+                        self.io_error = io_error_message(&file, e);
+                        Vec::new()
+                    }
+                };
+                self.alloc_string(content, sp)
+            }
+            (BuildIn::write_file_string, _, [file, content]) => {
+                let file = path(file);
+                self.io_error = match std::fs::write(&file, content) {
+                    Ok(()) => String::new(),
+                    Err(e) => io_error_message(&file, e),
+                };
+                Void
+            }
+            (BuildIn::io_error, [], []) => {
+                self.alloc_string(self.io_error.clone().into_bytes(), sp)
+            }
+            (BuildIn::eprint_str, [Str(s)], []) => {
+                write!(self.err, "{}", s).map_err(|e| error(e.to_string()))?;
+                Void
+            }
+            (BuildIn::eprint_string, _, [s]) => {
+                self.err.write_all(s).map_err(|e| error(e.to_string()))?;
+                Void
+            }
+            _ => return Ok(None),
+        };
+        for value in values {
+            if let Ref(obj) = value {
+                self.heap.ptr_drop(obj)?;
+            }
+        }
+        Ok(Some(res))
+    }
+
+    fn alloc_string(&mut self, bytes: Vec<u8>, sp: usize) -> Value {
+        let object = Object {
+            header: 0,
+            case: 0,
+            fields: vec![Value::I32(bytes.len() as i32)],
+            bytes,
+        };
+        Value::Ref(self.heap.alloc(object, &self.stack[..sp]))
+    }
+
+    fn show_tuple(&self, fields: &[Value], type_id: &Rc<String>) -> Result<String, RuntimeError> {
+        let fields = fields
+            .iter()
+            .zip(tuple_fields(type_id).unwrap_or_default().iter())
+            .map(|(field, field_type)| self.show(field, field_type))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(format!("({})", fields.join(", ")))
+    }
+}
+
+// Parses an optional minus followed by digits like string_to_i32 of the
+// runtime. Overflows wrap around.
+fn parse_i32(s: &[u8]) -> i32 {
+    let (negative, digits) = match s.split_first() {
+        Some((b'-', digits)) => (true, digits),
+        _ => (false, s),
+    };
+    let res = digits
+        .iter()
+        .take_while(|c| c.is_ascii_digit())
+        .fold(0i32, |res, c| {
+            res.wrapping_mul(10).wrapping_add((c - b'0') as i32)
+        });
+    if negative {
+        res.wrapping_neg()
+    } else {
+        res
+    }
+}
+
+// Like the messages of strerror, which the runtime uses, without the error
+// number Rust appends
+fn io_error_message(name: &str, e: std::io::Error) -> String {
+    let message = e.to_string();
+    let message = match message.find(" (os error") {
+        Some(end) => &message[..end],
+        None => &message[..],
+    };
+    format!("{}: {}", name, message)
+}
+
+// The messages match the runtime checks of the code generator
+fn division_error(
+    func_id: &str,
+    operator: &str,
+    a: impl fmt::Display,
+    b: impl fmt::Display,
+    by_zero: bool,
+) -> Interrupt {
+    if by_zero {
+        error(format!("{}: division of {} by zero", func_id, a))
+    } else {
+        error(format!("{}: overflow in {} {} {}", func_id, a, operator, b))
+    }
+}
+
+fn call_build_in(
+    build_in: BuildIn,
+    func_id: &Rc<String>,
+    args: &[(&str, Value)],
+    out: &mut impl Write,
+    err: &mut impl Write,
+) -> Result<Value, Interrupt> {
+    use Value::*;
+    let print = |out: &mut _, bytes: &[u8]| {
+        Write::write_all(out, bytes).map_err(|e| error(e.to_string()))?;
+        Ok::<_, Interrupt>(Void)
+    };
+    let res = match (build_in, args) {
+        // printf interprets the string as format string
+        (BuildIn::print_str, [(_, Str(s))]) => print(out, s.replace("%%", "%").as_bytes())?,
+        (BuildIn::print_u8, [(_, U8(c))]) => print(out, &[*c])?,
+        (BuildIn::print_i32, [(_, I32(i))]) => print(out, i.to_string().as_bytes())?,
+        (BuildIn::char_at, [(_, Str(s)), (_, I32(i))]) => {
+            // The terminating NUL byte can be read like in C
+            match usize::try_from(*i).ok().filter(|i| *i <= s.len()) {
+                Some(i) => U8(s.as_bytes().get(i).copied().unwrap_or(0)),
+                None => {
+                    return Err(error(format!(
+                        "char_at: index {} is out of bounds for a string of length {}",
+                        i,
+                        s.len()
+                    )))
+                }
+            }
+        }
+        (BuildIn::exit, [(_, I32(code))]) => return Err(Interrupt::Exit(*code)),
+        (BuildIn::panic_str, [(_, Str(location)), (_, Str(message))]) => {
+            writeln!(err, "{}{}", location, message).map_err(|e| error(e.to_string()))?;
+            return Err(Interrupt::Exit(PANIC_EXIT_CODE));
+        }
+        (BuildIn::eq_i32, [(_, I32(a)), (_, I32(b))]) => Bool(a == b),
+        (BuildIn::eq_u8, [(_, U8(a)), (_, U8(b))]) => Bool(a == b),
+        (BuildIn::eq_bool, [(_, Bool(a)), (_, Bool(b))]) => Bool(a == b),
+        (BuildIn::neq_i32, [(_, I32(a)), (_, I32(b))]) => Bool(a != b),
+        (BuildIn::neq_u8, [(_, U8(a)), (_, U8(b))]) => Bool(a != b),
+        (BuildIn::neq_bool, [(_, Bool(a)), (_, Bool(b))]) => Bool(a != b),
+        (BuildIn::lt_i32, [(_, I32(a)), (_, I32(b))]) => Bool(a < b),
+        (BuildIn::le_i32, [(_, I32(a)), (_, I32(b))]) => Bool(a <= b),
+        (BuildIn::gt_i32, [(_, I32(a)), (_, I32(b))]) => Bool(a > b),
+        (BuildIn::ge_i32, [(_, I32(a)), (_, I32(b))]) => Bool(a >= b),
+        (BuildIn::add_i32, [(_, I32(a)), (_, I32(b))]) => I32(a
+            .checked_add(*b)
+            .ok_or_else(|| error(format!("add_i32: overflow in {} + {}", a, b)))?),
+        (BuildIn::sub_i32, [(_, I32(a)), (_, I32(b))]) => I32(a
+            .checked_sub(*b)
+            .ok_or_else(|| error(format!("sub_i32: overflow in {} - {}", a, b)))?),
+        (BuildIn::mul_i32, [(_, I32(a)), (_, I32(b))]) => I32(a
+            .checked_mul(*b)
+            .ok_or_else(|| error(format!("mul_i32: overflow in {} * {}", a, b)))?),
+        (BuildIn::div_i32, [(_, I32(a)), (_, I32(b))]) => I32(a
+            .checked_div(*b)
+            .ok_or_else(|| division_error(func_id, "/", a, b, *b == 0))?),
+        (BuildIn::mod_i32, [(_, I32(a)), (_, I32(b))]) => I32(a
+            .checked_rem(*b)
+            .ok_or_else(|| division_error(func_id, "%", a, b, *b == 0))?),
+        (BuildIn::neg_i32, [(_, I32(a))]) => I32(a.wrapping_neg()),
+        // The code generator compares and divides u8 as signed integers
+        (BuildIn::lt_u8, [(_, U8(a)), (_, U8(b))]) => Bool((*a as i8) < (*b as i8)),
+        (BuildIn::le_u8, [(_, U8(a)), (_, U8(b))]) => Bool((*a as i8) <= (*b as i8)),
+        (BuildIn::gt_u8, [(_, U8(a)), (_, U8(b))]) => Bool((*a as i8) > (*b as i8)),
+        (BuildIn::ge_u8, [(_, U8(a)), (_, U8(b))]) => Bool((*a as i8) >= (*b as i8)),
+        (BuildIn::add_u8, [(_, U8(a)), (_, U8(b))]) => U8(a.wrapping_add(*b)),
+        (BuildIn::sub_u8, [(_, U8(a)), (_, U8(b))]) => U8(a.wrapping_sub(*b)),
+        (BuildIn::mul_u8, [(_, U8(a)), (_, U8(b))]) => U8(a.wrapping_mul(*b)),
+        (BuildIn::div_u8, [(_, U8(a)), (_, U8(b))]) => U8((*a as i8)
+            .checked_div(*b as i8)
+            .ok_or_else(|| division_error(func_id, "/", a, b, *b == 0))?
+            as u8),
+        (BuildIn::mod_u8, [(_, U8(a)), (_, U8(b))]) => U8((*a as i8)
+            .checked_rem(*b as i8)
+            .ok_or_else(|| division_error(func_id, "%", a, b, *b == 0))?
+            as u8),
+        (BuildIn::neg_u8, [(_, U8(a))]) => U8(a.wrapping_neg()),
+        (BuildIn::not_bool, [(_, Bool(a))]) => Bool(!a),
+        _ => return Err(error(format!("Can not call {} with {:?}", func_id, args))),
+    };
+    Ok(res)
+}
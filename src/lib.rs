@@ -0,0 +1,195 @@
+//! The Arena compiler as a library.
+//!
+//! Each stage of the pipeline returns an [`Error`] instead of panicking on
+//! invalid programs:
+//!
+//! ```no_run
+//! use arena::sources::InMemory;
+//! use arena::{codegen::Backend, CodegenOptions};
+//!
+//! let mut sources = InMemory::new();
+//! sources.add("main.arena", "fn main() = print(\"Hello\\n\")");
+//! let (codes, main_path) = arena::load(&sources, "main.arena".as_ref())?;
+//! let asts = arena::parse(&codes)?;
+//! let (modules, main_module_id) = arena::resolve_all_imports(&sources, asts, &main_path)?;
+//! let binary = arena::type_check(&modules, &main_module_id)?;
+//! let llvm_ir = arena::codegen(binary, Backend::Arc, &CodegenOptions::default())?;
+//! # Ok::<(), arena::Error>(())
+//! ```
+
+pub mod binary;
+pub mod codegen;
+mod error;
+pub mod format;
+pub mod interpreter;
+mod manifest;
+pub mod module;
+pub mod parser;
+pub mod sources;
+pub mod type_check;
+pub mod types;
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::binary::{Binary, TypedExpr};
+use crate::codegen::garbage_collection::{Spill, ARC, TGC};
+use crate::codegen::Backend;
+use crate::interpreter::{ArcHeap, SpillHeap, TgcHeap};
+use crate::module::Module;
+use crate::sources::{Modules, SourceLoader};
+
+pub use crate::error::{Error, FunctionLocation};
+
+pub struct CodegenOptions {
+    pub profiling_frequency: u64,
+    // Has to match the GC_STATS_FORMAT_* constants in runtime/gc_stats.h
+    pub gc_stats_format: u64,
+    // Leaves out the runtime bounds and overflow checks
+    pub unchecked: bool,
+}
+
+impl Default for CodegenOptions {
+    fn default() -> Self {
+        CodegenOptions {
+            profiling_frequency: 1,
+            gc_stats_format: 0,
+            unchecked: false,
+        }
+    }
+}
+
+/// Reads the program starting at `main_path` and every file it imports.
+/// Returns the code of each file and the canonical path of the main file.
+pub fn load(
+    loader: &impl SourceLoader,
+    main_path: &Path,
+) -> Result<(HashMap<PathBuf, String>, PathBuf), Error> {
+    sources::load(loader, main_path)
+}
+
+pub fn parse(codes: &HashMap<PathBuf, String>) -> Result<HashMap<&PathBuf, Module<'_>>, Error> {
+    codes
+        .iter()
+        .map(|(path, code)| {
+            parser::parse(code.as_str())
+                .map(|ast| (path, ast))
+                .map_err(|e| sources::parse_error(path, code, e))
+        })
+        .collect()
+}
+
+/// Replaces the import paths of each module by the id of the imported module.
+/// Returns the modules by id and the id of the main module.
+pub fn resolve_all_imports<'input>(
+    loader: &impl SourceLoader,
+    asts: HashMap<&PathBuf, Module<'input>>,
+    main_path: &Path,
+) -> Result<(Modules<'input>, Rc<String>), Error> {
+    sources::resolve_all_imports(loader, asts, main_path)
+}
+
+pub fn type_check<'input>(
+    modules: &'input Modules<'input>,
+    main_module_id: &Rc<String>,
+) -> Result<Binary<'input>, Error> {
+    type_check::type_check(modules, main_module_id).map_err(type_error)
+}
+
+/// Type checks the modules without requiring a main function.
+pub fn type_check_definitions<'input>(
+    modules: &'input Modules<'input>,
+) -> Result<Binary<'input>, Error> {
+    type_check::type_check_definitions(modules).map_err(type_error)
+}
+
+fn type_error(error: type_check::TypeError) -> Error {
+    Error::Type {
+        message: error.message,
+        function: error.function,
+    }
+}
+
+/// Returns the LLVM IR of the program.
+pub fn codegen(
+    binary: Binary,
+    backend: Backend,
+    options: &CodegenOptions,
+) -> Result<String, Error> {
+    let profiling_frequency = options.profiling_frequency;
+    let gc_stats_format = options.gc_stats_format;
+    let unchecked = options.unchecked;
+    match backend {
+        Backend::Spill => {
+            codegen::codegen::<Spill>(binary, profiling_frequency, gc_stats_format, unchecked)
+        }
+        Backend::Tgc => {
+            codegen::codegen::<TGC>(binary, profiling_frequency, gc_stats_format, unchecked)
+        }
+        Backend::Arc => {
+            codegen::codegen::<ARC>(binary, profiling_frequency, gc_stats_format, unchecked)
+        }
+    }
+    .map_err(|message| Error::Codegen { message })
+}
+
+/// Runs the program with the heap model of the given backend and returns its
+/// exit code. The program gets `args` as command line arguments, starting with
+/// its name. The output of the program is written to `out`, its error output
+/// and the messages of panics to `err`.
+pub fn interpret(
+    binary: &Binary,
+    backend: Backend,
+    args: &[String],
+    out: &mut impl Write,
+    err: &mut impl Write,
+) -> Result<i32, Error> {
+    match backend {
+        Backend::Spill => interpreter::interpret(binary, SpillHeap::default(), args, out, err),
+        Backend::Tgc => interpreter::interpret(binary, TgcHeap::default(), args, out, err),
+        Backend::Arc => interpreter::interpret(binary, ArcHeap::default(), args, out, err),
+    }
+    .map_err(|e| Error::Runtime { message: e.message })
+}
+
+/// Evaluates a single expression of the binary with the heap model of the
+/// given backend and returns its value, or `None` if it has type `void`. Like
+/// in [interpret] the output is written to `out` and `err`.
+pub fn evaluate(
+    binary: &Binary,
+    expr: &TypedExpr,
+    backend: Backend,
+    out: &mut impl Write,
+    err: &mut impl Write,
+) -> Result<Option<String>, Error> {
+    match backend {
+        Backend::Spill => interpreter::evaluate(binary, SpillHeap::default(), expr, out, err),
+        Backend::Tgc => interpreter::evaluate(binary, TgcHeap::default(), expr, out, err),
+        Backend::Arc => interpreter::evaluate(binary, ArcHeap::default(), expr, out, err),
+    }
+    .map_err(|e| Error::Runtime { message: e.message })
+}
+
+/// Runs the whole pipeline and returns the LLVM IR of the program.
+pub fn compile(
+    loader: &impl SourceLoader,
+    main_path: &Path,
+    backend: Backend,
+    options: &CodegenOptions,
+) -> Result<String, Error> {
+    let (codes, main_path) = load(loader, main_path)?;
+    let asts = parse(&codes)?;
+    let (modules, main_module_id) = resolve_all_imports(loader, asts, &main_path)?;
+    let binary = type_check(&modules, &main_module_id)?;
+    codegen(binary, backend, options)
+}
+
+/// Runs the pipeline up to type checking.
+pub fn check(loader: &impl SourceLoader, main_path: &Path) -> Result<(), Error> {
+    let (codes, main_path) = load(loader, main_path)?;
+    let asts = parse(&codes)?;
+    let (modules, main_module_id) = resolve_all_imports(loader, asts, &main_path)?;
+    type_check(&modules, &main_module_id).map(|_| ())
+}
@@ -0,0 +1,874 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, DidSaveTextDocument,
+    Notification as _, PublishDiagnostics,
+};
+use lsp_types::request::{
+    Completion, DocumentSymbolRequest, GotoDefinition, HoverRequest, Request as _,
+};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionResponse, Diagnostic,
+    DocumentSymbol, DocumentSymbolResponse, GotoDefinitionResponse, Hover, HoverContents,
+    HoverProviderCapability, Location, MarkupContent, MarkupKind, OneOf, Position,
+    PublishDiagnosticsParams, Range, ServerCapabilities, SymbolKind, TextDocumentPositionParams,
+    TextDocumentSyncCapability, TextDocumentSyncKind, Url,
+};
+use serde::Serialize;
+
+use arena::binary::{BinExpr, TypedExpr};
+use arena::interpreter::type_name;
+use arena::module::{Extern, Function, IdLoc, ParamDef, Type, TypeCase};
+use arena::sources::{self, FileSystem, Modules, SourceLoader};
+use arena::type_check::{function_id, ordered_overloads};
+use arena::types::VOID_TYPE;
+use arena::Error;
+
+type LspResult<T> = Result<T, Box<dyn std::error::Error + Sync + Send>>;
+
+struct Document {
+    text: String,
+    // Last version of text that could be parsed. Requests are answered on it,
+    // so that completion keeps working while the document is being edited.
+    parsed_text: Option<String>,
+}
+
+// Reads open documents from the editor and all other files from disk
+struct Sources<'a> {
+    documents: &'a HashMap<PathBuf, Document>,
+    // Read the last parsed version of open documents
+    parsed: bool,
+    file_system: FileSystem,
+}
+
+impl SourceLoader for Sources<'_> {
+    fn canonicalize(&self, path: &Path) -> Option<PathBuf> {
+        if self.documents.contains_key(path) {
+            return Some(path.to_path_buf());
+        }
+        self.file_system.canonicalize(path)
+    }
+
+    fn read(&self, path: &Path) -> Result<String, Error> {
+        match self.documents.get(path) {
+            Some(document) if self.parsed => Ok(document
+                .parsed_text
+                .as_ref()
+                .unwrap_or(&document.text)
+                .clone()),
+            Some(document) => Ok(document.text.clone()),
+            None => self.file_system.read(path),
+        }
+    }
+
+    fn dependencies(&self) -> Vec<(String, PathBuf)> {
+        self.file_system.dependencies()
+    }
+
+    fn library_folders(&self) -> Vec<PathBuf> {
+        self.file_system.library_folders()
+    }
+}
+
+// A program with its imports resolved
+struct Program<'a> {
+    codes: &'a HashMap<PathBuf, String>,
+    modules: &'a Modules<'a>,
+    // Paths by module id
+    paths: HashMap<Rc<String>, &'a PathBuf>,
+}
+
+// An identifier in the code, qualified with a module and a type as in
+// Module::Type.Case
+struct Reference<'a> {
+    module: Option<&'a str>,
+    ty: Option<&'a str>,
+    name: &'a str,
+}
+
+enum Definition<'a> {
+    Module(&'a Rc<String>),
+    Function(&'a Rc<String>, &'a str, &'a Function<'a>),
+    Extern(&'a Rc<String>, &'a str, &'a Extern<'a>),
+    Type(&'a Rc<String>, &'a str, &'a Type<'a>),
+    TypeCase(&'a Rc<String>, &'a str, &'a TypeCase<'a>),
+}
+
+pub fn run_language_server() -> LspResult<()> {
+    let (connection, io_threads) = Connection::stdio();
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        definition_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec![":".to_string(), ".".to_string()]),
+            ..CompletionOptions::default()
+        }),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        ..ServerCapabilities::default()
+    };
+    connection.initialize(serde_json::to_value(capabilities)?)?;
+
+    let mut documents = HashMap::new();
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    break;
+                }
+                let response = handle_request(&documents, request);
+                connection.sender.send(Message::Response(response))?;
+            }
+            Message::Notification(notification) => {
+                handle_notification(&connection, &mut documents, notification)?
+            }
+            Message::Response(_) => (),
+        }
+    }
+    // The writer thread stops once the connection is dropped
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+fn handle_notification(
+    connection: &Connection,
+    documents: &mut HashMap<PathBuf, Document>,
+    notification: Notification,
+) -> LspResult<()> {
+    match notification.method.as_str() {
+        DidOpenTextDocument::METHOD => {
+            let params: lsp_types::DidOpenTextDocumentParams =
+                serde_json::from_value(notification.params)?;
+            let path = to_path(&params.text_document.uri)?;
+            update_document(documents, path, params.text_document.text);
+        }
+        DidChangeTextDocument::METHOD => {
+            let params: lsp_types::DidChangeTextDocumentParams =
+                serde_json::from_value(notification.params)?;
+            let path = to_path(&params.text_document.uri)?;
+            // Documents are synchronized in full, the last change is the whole text
+            if let Some(change) = params.content_changes.into_iter().last() {
+                update_document(documents, path, change.text);
+            }
+        }
+        DidCloseTextDocument::METHOD => {
+            let params: lsp_types::DidCloseTextDocumentParams =
+                serde_json::from_value(notification.params)?;
+            documents.remove(&to_path(&params.text_document.uri)?);
+            publish(connection, params.text_document.uri, Vec::new())?;
+        }
+        // Other documents may import the saved file
+        DidSaveTextDocument::METHOD => (),
+        _ => return Ok(()),
+    }
+    for path in documents.keys() {
+        let diagnostics = diagnostics(documents, path);
+        publish(connection, to_uri(path)?, diagnostics)?;
+    }
+    Ok(())
+}
+
+fn update_document(documents: &mut HashMap<PathBuf, Document>, path: PathBuf, text: String) {
+    let parsed_text = match arena::parser::parse(&text) {
+        Ok(_) => Some(text.clone()),
+        Err(_) => documents.remove(&path).and_then(|d| d.parsed_text),
+    };
+    documents.insert(path, Document { text, parsed_text });
+}
+
+fn publish(connection: &Connection, uri: Url, diagnostics: Vec<Diagnostic>) -> LspResult<()> {
+    let params = PublishDiagnosticsParams::new(uri, diagnostics, None);
+    let notification = Notification::new(PublishDiagnostics::METHOD.to_string(), params);
+    connection
+        .sender
+        .send(Message::Notification(notification))?;
+    Ok(())
+}
+
+// Type checks the program starting at the document. Errors without a location
+// in the document are shown on its first line.
+fn diagnostics(documents: &HashMap<PathBuf, Document>, path: &PathBuf) -> Vec<Diagnostic> {
+    let mut main_module_id = None;
+    let checked = FileSystem::new(path, &[]).and_then(|file_system| {
+        let loader = Sources {
+            documents,
+            parsed: false,
+            file_system,
+        };
+        let (codes, main_path) = arena::load(&loader, path)?;
+        let asts = arena::parse(&codes)?;
+        let (modules, module_id) = arena::resolve_all_imports(&loader, asts, &main_path)?;
+        main_module_id = Some(module_id);
+        arena::type_check_definitions(&modules).map(|_| ())
+    });
+    let error = match checked {
+        Ok(()) => return Vec::new(),
+        Err(error) => error,
+    };
+    let code = &documents[path].text;
+    let range = match &error {
+        Error::Parse {
+            path: error_path,
+            line,
+            column,
+            ..
+        } if error_path == path => {
+            let start = Position::new(*line as u32 - 1, *column as u32 - 1);
+            Range::new(start, start)
+        }
+        Error::Import {
+            path: error_path,
+            import,
+            ..
+        } if error_path == path => code
+            .match_indices("import")
+            .find_map(|(index, _)| {
+                let line_end = code[index..]
+                    .find('\n')
+                    .map_or(code.len(), |end| index + end);
+                code[index..line_end]
+                    .find(import.as_str())
+                    .map(|i| index + i)
+            })
+            .map_or(first_line(code), |offset| range(code, offset, import.len())),
+        // Type errors in a function of the document are shown at its name
+        Error::Type {
+            function: Some(function),
+            ..
+        } if main_module_id.as_ref() == Some(&function.module_id) => {
+            range(code, function.location, function.name.len())
+        }
+        _ => first_line(code),
+    };
+    let message = match error {
+        Error::Parse { message, .. } => message,
+        error => error.to_string(),
+    };
+    vec![Diagnostic::new_simple(range, message)]
+}
+
+fn first_line(code: &str) -> Range {
+    range(code, 0, code.find('\n').unwrap_or(code.len()))
+}
+
+fn handle_request(documents: &HashMap<PathBuf, Document>, request: Request) -> Response {
+    let id = request.id.clone();
+    let result = match request.method.as_str() {
+        GotoDefinition::METHOD => respond(request, |params: lsp_types::GotoDefinitionParams| {
+            with_program(
+                documents,
+                &params.text_document_position_params,
+                |program, path, reference, _| definition(program, path, reference),
+            )
+        }),
+        HoverRequest::METHOD => respond(request, |params: lsp_types::HoverParams| {
+            with_program(
+                documents,
+                &params.text_document_position_params,
+                |program, path, reference, offset| hover(program, path, reference, offset),
+            )
+        }),
+        Completion::METHOD => respond(request, |params: lsp_types::CompletionParams| {
+            with_program(
+                documents,
+                &params.text_document_position,
+                |program, path, reference, _| completion(program, path, reference),
+            )
+        }),
+        DocumentSymbolRequest::METHOD => {
+            respond(request, |params: lsp_types::DocumentSymbolParams| {
+                let position =
+                    TextDocumentPositionParams::new(params.text_document, Position::new(0, 0));
+                with_program(documents, &position, |program, path, _, _| {
+                    document_symbols(program, path)
+                })
+            })
+        }
+        method => {
+            return Response::new_err(
+                id,
+                ErrorCode::MethodNotFound as i32,
+                format!("Unsupported request {}", method),
+            )
+        }
+    };
+    match result {
+        Ok(result) => Response::new_ok(id, result),
+        Err(error) => Response::new_err(id, ErrorCode::InvalidParams as i32, error.to_string()),
+    }
+}
+
+fn respond<P: serde::de::DeserializeOwned, R: Serialize>(
+    request: Request,
+    f: impl FnOnce(P) -> LspResult<R>,
+) -> LspResult<serde_json::Value> {
+    let params = serde_json::from_value(request.params)?;
+    Ok(serde_json::to_value(f(params)?)?)
+}
+
+// Passes the program of the document, its path, the identifier at the position
+// and its byte offset to f. Returns None if the program can not be parsed.
+fn with_program<T>(
+    documents: &HashMap<PathBuf, Document>,
+    position: &TextDocumentPositionParams,
+    f: impl FnOnce(&Program, &PathBuf, &Reference, usize) -> Option<T>,
+) -> LspResult<Option<T>> {
+    let path = to_path(&position.text_document.uri)?;
+    let result = (|| {
+        let loader = Sources {
+            documents,
+            parsed: true,
+            file_system: FileSystem::new(&path, &[])?,
+        };
+        let (codes, main_path) = arena::load(&loader, &path)?;
+        let asts = arena::parse(&codes)?;
+        let (modules, _) = arena::resolve_all_imports(&loader, asts, &main_path)?;
+        let program = Program {
+            codes: &codes,
+            modules: &modules,
+            paths: codes
+                .keys()
+                .map(|path| (sources::module_id(&loader, &main_path, path), path))
+                .collect(),
+        };
+        // The position is in the current text of the document
+        let code = documents
+            .get(&main_path)
+            .map_or(&codes[&main_path], |document| &document.text);
+        let offset = offset(code, position.position);
+        Ok::<_, Error>(f(&program, &main_path, &reference_at(code, offset), offset))
+    })();
+    Ok(result.ok().flatten())
+}
+
+fn definition(
+    program: &Program,
+    path: &Path,
+    reference: &Reference,
+) -> Option<GotoDefinitionResponse> {
+    let locations = program
+        .definitions(&program.module_id(path)?, reference)
+        .into_iter()
+        .filter_map(|definition| {
+            let (module_id, location, name) = match definition {
+                Definition::Module(module_id) => (module_id, 0, ""),
+                Definition::Function(module_id, name, function) => {
+                    (module_id, function.location, name)
+                }
+                Definition::Extern(module_id, name, extern_def) => {
+                    (module_id, extern_def.location, name)
+                }
+                Definition::Type(module_id, name, ty) => (module_id, ty.location, name),
+                Definition::TypeCase(module_id, _, case) => (module_id, case.location, case.name),
+            };
+            let path = program.paths.get(module_id)?;
+            Some(Location::new(
+                to_uri(path).ok()?,
+                range(&program.codes[*path], location, name.len()),
+            ))
+        })
+        .collect::<Vec<_>>();
+    (!locations.is_empty()).then_some(GotoDefinitionResponse::Array(locations))
+}
+
+fn hover(program: &Program, path: &Path, reference: &Reference, offset: usize) -> Option<Hover> {
+    let module_id = program.module_id(path)?;
+    let definitions = program.definitions(&module_id, reference);
+    let text = if definitions.is_empty() {
+        let var_type = program.variable_type(&module_id, offset, reference.name)?;
+        format!("{}: {}", reference.name, type_name(&var_type))
+    } else {
+        definitions
+            .iter()
+            .map(|definition| match definition {
+                Definition::Module(module_id) => format!(
+                    "import \"{}\"",
+                    program
+                        .paths
+                        .get(*module_id)
+                        .map_or("", |p| p.to_str().unwrap())
+                ),
+                Definition::Function(_, name, function) => function_signature(name, function),
+                Definition::Extern(_, name, extern_def) => extern_signature(name, extern_def),
+                Definition::Type(_, name, ty) => type_signature(name, ty),
+                Definition::TypeCase(_, ty, case) => format!("{}.{}", ty, case_signature(case)),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: format!("```arena\n{}\n```", text),
+        }),
+        range: None,
+    })
+}
+
+fn completion(program: &Program, path: &Path, reference: &Reference) -> Option<CompletionResponse> {
+    let target_id = program.target_module(&program.module_id(path)?, reference)?;
+    let module = program.modules.get(target_id)?;
+    // Other modules only see the public functions, types and cases
+    let other_module = reference.module.is_some();
+    let mut items = Vec::new();
+    match reference.ty {
+        Some(ty) => {
+            for case in module.types.get(ty)?.cases.iter() {
+                if other_module && !case.public {
+                    continue;
+                }
+                items.push(CompletionItem {
+                    label: case.name.to_string(),
+                    kind: Some(CompletionItemKind::ENUM_MEMBER),
+                    detail: Some(format!("{}.{}", ty, case_signature(case))),
+                    ..CompletionItem::default()
+                });
+            }
+        }
+        None => {
+            for (name, overloads) in module.functions.iter() {
+                for function in overloads.values() {
+                    if other_module && !function.public {
+                        continue;
+                    }
+                    items.push(CompletionItem {
+                        label: name.to_string(),
+                        kind: Some(CompletionItemKind::FUNCTION),
+                        detail: Some(function_signature(name, function)),
+                        ..CompletionItem::default()
+                    });
+                }
+            }
+            for (name, extern_def) in module.externs.iter() {
+                items.push(CompletionItem {
+                    label: name.to_string(),
+                    kind: Some(CompletionItemKind::FUNCTION),
+                    detail: Some(extern_signature(name, extern_def)),
+                    ..CompletionItem::default()
+                });
+            }
+            for (name, ty) in module.types.iter() {
+                if other_module && !ty.public {
+                    continue;
+                }
+                items.push(CompletionItem {
+                    label: name.to_string(),
+                    kind: Some(CompletionItemKind::ENUM),
+                    detail: Some(type_signature(name, ty)),
+                    ..CompletionItem::default()
+                });
+            }
+            if reference.module.is_none() {
+                for name in module.imports.keys() {
+                    items.push(CompletionItem {
+                        label: name.to_string(),
+                        kind: Some(CompletionItemKind::MODULE),
+                        ..CompletionItem::default()
+                    });
+                }
+            }
+        }
+    }
+    Some(CompletionResponse::Array(items))
+}
+
+fn document_symbols(program: &Program, path: &PathBuf) -> Option<DocumentSymbolResponse> {
+    let code = &program.codes[path];
+    let module = program.modules.get(&program.module_id(path)?)?;
+    let mut symbols = Vec::new();
+    for (name, ty) in module.types.iter() {
+        let cases = ty
+            .cases
+            .iter()
+            .map(|case| {
+                symbol(
+                    code,
+                    case.name,
+                    case_signature(case),
+                    SymbolKind::ENUM_MEMBER,
+                    case.location,
+                    None,
+                )
+            })
+            .collect();
+        symbols.push(symbol(
+            code,
+            name,
+            type_signature(name, ty),
+            SymbolKind::ENUM,
+            ty.location,
+            Some(cases),
+        ));
+    }
+    for (name, overloads) in module.functions.iter() {
+        for function in overloads.values() {
+            let signature = function_signature(name, function);
+            symbols.push(symbol(
+                code,
+                name,
+                signature,
+                SymbolKind::FUNCTION,
+                function.location,
+                None,
+            ));
+        }
+    }
+    for (name, extern_def) in module.externs.iter() {
+        symbols.push(symbol(
+            code,
+            name,
+            extern_signature(name, extern_def),
+            SymbolKind::FUNCTION,
+            extern_def.location,
+            None,
+        ));
+    }
+    for (name, trait_def) in module.traits.iter() {
+        let methods = trait_def
+            .methods
+            .iter()
+            .map(|(method_name, method)| {
+                symbol(
+                    code,
+                    method_name,
+                    signature(method_name, &method.args, &method.ret_type),
+                    SymbolKind::METHOD,
+                    method.location,
+                    None,
+                )
+            })
+            .collect();
+        symbols.push(symbol(
+            code,
+            name,
+            format!("trait {}", name),
+            SymbolKind::INTERFACE,
+            trait_def.location,
+            Some(methods),
+        ));
+    }
+    for implementation in module.impls.iter() {
+        let functions = implementation
+            .functions
+            .iter()
+            .map(|(name, function)| {
+                symbol(
+                    code,
+                    name,
+                    function_signature(name, function),
+                    SymbolKind::METHOD,
+                    function.location,
+                    None,
+                )
+            })
+            .collect();
+        symbols.push(symbol(
+            code,
+            &implementation.trait_name.to_string(),
+            format!(
+                "impl {} for {}",
+                implementation.trait_name, implementation.type_name
+            ),
+            SymbolKind::CLASS,
+            implementation.location,
+            Some(functions),
+        ));
+    }
+    symbols.sort_by_key(|symbol| (symbol.range.start.line, symbol.range.start.character));
+    Some(DocumentSymbolResponse::Nested(symbols))
+}
+
+#[allow(deprecated)] // DocumentSymbol::deprecated has to be set
+fn symbol(
+    code: &str,
+    name: &str,
+    detail: String,
+    kind: SymbolKind,
+    location: usize,
+    children: Option<Vec<DocumentSymbol>>,
+) -> DocumentSymbol {
+    let range = range(code, location, name.len());
+    DocumentSymbol {
+        name: name.to_string(),
+        detail: Some(detail),
+        kind,
+        tags: None,
+        deprecated: None,
+        range,
+        selection_range: range,
+        children,
+    }
+}
+
+impl<'a> Program<'a> {
+    // Id of the module at the canonical path
+    fn module_id(&self, path: &Path) -> Option<Rc<String>> {
+        self.paths
+            .iter()
+            .find(|(_, module_path)| module_path.as_path() == path)
+            .map(|(module_id, _)| Rc::clone(module_id))
+    }
+
+    // Id of the module the reference points into
+    fn target_module(
+        &self,
+        module_id: &Rc<String>,
+        reference: &Reference,
+    ) -> Option<&'a Rc<String>> {
+        let (module_id, module) = self.modules.get_key_value(module_id)?;
+        match reference.module {
+            None => Some(module_id),
+            Some(alias) => module.imports.get(alias),
+        }
+    }
+
+    fn definitions(&self, module_id: &Rc<String>, reference: &Reference) -> Vec<Definition<'a>> {
+        let target_id = match self.target_module(module_id, reference) {
+            Some(target_id) => target_id,
+            None => return Vec::new(),
+        };
+        let module = &self.modules[target_id];
+        if let Some(ty) = reference.ty {
+            return module
+                .types
+                .get_key_value(ty)
+                .and_then(|(ty, type_def)| {
+                    let case = type_def.cases.iter().find(|c| c.name == reference.name)?;
+                    Some(Definition::TypeCase(target_id, ty, case))
+                })
+                .into_iter()
+                .collect();
+        }
+        let mut definitions = Vec::new();
+        if let Some((name, overloads)) = module.functions.get_key_value(reference.name) {
+            for function in overloads.values() {
+                definitions.push(Definition::Function(target_id, name, function));
+            }
+        }
+        if let Some((name, extern_def)) = module.externs.get_key_value(reference.name) {
+            definitions.push(Definition::Extern(target_id, name, extern_def));
+        }
+        if let Some((name, ty)) = module.types.get_key_value(reference.name) {
+            definitions.push(Definition::Type(target_id, name, ty));
+        }
+        // Calls of trait methods may go to the implementations of any module
+        if definitions.is_empty() && reference.module.is_none() {
+            for (impl_module_id, impl_module) in self.modules.iter() {
+                for implementation in impl_module.impls.iter() {
+                    if let Some((name, function)) =
+                        implementation.functions.get_key_value(reference.name)
+                    {
+                        definitions.push(Definition::Function(impl_module_id, name, function));
+                    }
+                }
+            }
+        }
+        if definitions.is_empty() && reference.module.is_none() {
+            if let Some(import_id) = module.imports.get(reference.name) {
+                definitions.push(Definition::Module(import_id));
+            }
+        }
+        definitions
+    }
+
+    // Type of the variable visible at offset, from the type checked function
+    // around offset
+    fn variable_type(
+        &self,
+        module_id: &Rc<String>,
+        offset: usize,
+        name: &str,
+    ) -> Option<Rc<String>> {
+        let module = self.modules.get(module_id)?;
+        let (function_name, index, _) = module
+            .functions
+            .iter()
+            .flat_map(|(name, overloads)| {
+                ordered_overloads(overloads)
+                    .into_iter()
+                    .enumerate()
+                    .map(move |(index, function)| (*name, index, function))
+            })
+            .filter(|(_, _, function)| function.location <= offset)
+            .max_by_key(|(_, _, function)| function.location)?;
+        let binary = arena::type_check_definitions(self.modules).ok()?;
+        let function = binary
+            .functions
+            .get(&function_id(module_id, function_name, index))?;
+        function
+            .args
+            .iter()
+            .find(|arg| arg.name == name)
+            .map(|arg| Rc::clone(&arg.param_type))
+            .or_else(|| let_type(&function.body, name))
+    }
+}
+
+// Type of the first let binding name in expr
+fn let_type(expr: &TypedExpr, name: &str) -> Option<Rc<String>> {
+    match &expr.expr {
+        BinExpr::Let(id, def, body) => {
+            if *id == name {
+                Some(Rc::clone(&def.expr_type))
+            } else {
+                let_type(def, name).or_else(|| let_type(body, name))
+            }
+        }
+        BinExpr::Const(_) | BinExpr::Var(_) => None,
+        BinExpr::Array(exprs) | BinExpr::FuncCall(_, exprs) | BinExpr::TypeCase(_, _, exprs) => {
+            exprs.iter().find_map(|e| let_type(e, name))
+        }
+        BinExpr::GetTypeCaseField(e, _, _) | BinExpr::IsCase(e, _) => let_type(e, name),
+        BinExpr::If(cond, e1, e2) => let_type(cond, name)
+            .or_else(|| let_type(e1, name))
+            .or_else(|| let_type(e2, name)),
+        BinExpr::Seq(e1, e2) => let_type(e1, name).or_else(|| let_type(e2, name)),
+    }
+}
+
+fn function_signature(name: &str, function: &Function) -> String {
+    let signature = signature(name, &function.args, &function.ret_type);
+    match function.public {
+        true => format!("pub {}", signature),
+        false => signature,
+    }
+}
+
+fn extern_signature(name: &str, extern_def: &Extern) -> String {
+    format!(
+        "extern {}",
+        signature(name, &extern_def.args, &extern_def.ret_type)
+    )
+}
+
+fn signature(name: &str, args: &[ParamDef], ret_type: &Option<IdLoc>) -> String {
+    let args = args
+        .iter()
+        .map(|arg| match &arg.param_type {
+            Some(param_type) => format!("{}: {}", arg.name, param_type),
+            None => arg.name.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    match ret_type {
+        None | Some(IdLoc::Here(VOID_TYPE)) => format!("fn {}({})", name, args),
+        Some(ret_type) => format!("fn {}({}) -> {}", name, args, ret_type),
+    }
+}
+
+fn type_signature(name: &str, ty: &Type) -> String {
+    let cases = ty
+        .cases
+        .iter()
+        .map(|case| match case.public {
+            true => format!("pub {}", case_signature(case)),
+            false => case_signature(case),
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    match ty.public {
+        true => format!("pub type {} {{ {} }}", name, cases),
+        false => format!("type {} {{ {} }}", name, cases),
+    }
+}
+
+fn case_signature(case: &TypeCase) -> String {
+    if case.fields.is_empty() {
+        return case.name.to_string();
+    }
+    let fields = case
+        .fields
+        .iter()
+        .zip(case.field_names.iter())
+        .map(|(field, name)| match name {
+            Some(name) => format!("{}: {}", name, field),
+            None => field.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("{}({})", case.name, fields)
+}
+
+fn is_id_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'_'
+}
+
+// The identifier at offset. The name is empty if there is none, like directly
+// after Module:: while completing.
+fn reference_at(code: &str, offset: usize) -> Reference<'_> {
+    let bytes = code.as_bytes();
+    let offset = offset.min(code.len());
+    let start = offset
+        - bytes[..offset]
+            .iter()
+            .rev()
+            .take_while(|c| is_id_char(**c))
+            .count();
+    let end = offset
+        + bytes[offset..]
+            .iter()
+            .take_while(|c| is_id_char(**c))
+            .count();
+    let (module, ty) = match qualifier(code, start, ".") {
+        Some((ty, ty_start)) => (qualifier(code, ty_start, "::").map(|(m, _)| m), Some(ty)),
+        None => (qualifier(code, start, "::").map(|(m, _)| m), None),
+    };
+    Reference {
+        module,
+        ty,
+        name: &code[start..end],
+    }
+}
+
+// The identifier directly before the separator ending at end, with its start
+fn qualifier<'a>(code: &'a str, end: usize, separator: &str) -> Option<(&'a str, usize)> {
+    let before = code[..end].strip_suffix(separator)?;
+    let start = before.len() - before.bytes().rev().take_while(|c| is_id_char(*c)).count();
+    (start < before.len()).then(|| (&before[start..], start))
+}
+
+// LSP positions count UTF-16 code units
+fn position(code: &str, offset: usize) -> Position {
+    let before = &code[..offset.min(code.len())];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    Position::new(
+        before.matches('\n').count() as u32,
+        before[line_start..].encode_utf16().count() as u32,
+    )
+}
+
+fn offset(code: &str, position: Position) -> usize {
+    let line_start = code
+        .split_inclusive('\n')
+        .take(position.line as usize)
+        .map(str::len)
+        .sum::<usize>();
+    let mut units = 0;
+    for (index, c) in code[line_start..].char_indices() {
+        if units >= position.character as usize || c == '\n' {
+            return line_start + index;
+        }
+        units += c.len_utf16();
+    }
+    code.len()
+}
+
+fn range(code: &str, location: usize, len: usize) -> Range {
+    Range::new(position(code, location), position(code, location + len))
+}
+
+fn to_path(uri: &Url) -> LspResult<PathBuf> {
+    let path = uri
+        .to_file_path()
+        .map_err(|_| format!("{} is not a file", uri))?;
+    Ok(fs::canonicalize(&path).unwrap_or(path))
+}
+
+fn to_uri(path: &Path) -> LspResult<Url> {
+    Ok(Url::from_file_path(path).map_err(|_| format!("{} is not absolute", path.display()))?)
+}
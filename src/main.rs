@@ -1,96 +1,253 @@
-use std::collections::HashMap;
-use std::path::PathBuf;
+use clap::Parser;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::str::FromStr;
 
-mod binary;
-mod codegen;
+mod bench;
+mod fmt;
 mod input;
-mod module;
-mod parser;
-mod type_check;
-mod types;
+mod lsp;
+mod repl;
+mod test_runner;
 
-use crate::codegen::garbage_collection::{Spill, ARC, TGC};
+use arena::binary::Binary;
+use arena::sources::FileSystem;
+use arena::CodegenOptions;
+
+use crate::input::{Cli, CompileArgs, EmitArgs, EmitKind, GcStatsFormat, InterpretArgs};
+
+const INTERPRETER_STACK_SIZE: usize = 1 << 30;
 
 fn main() {
-    let (codes, cli) = input::input();
+    let cli = Cli::parse();
+    match cli.command {
+        input::Command::Build(args) => {
+            let executable = std::env::current_dir().unwrap().join(&args.o);
+            build(&args.compile, &executable);
+        }
+        input::Command::Run(args) => {
+            let out_dir = temp_dir("run");
+            let executable = out_dir.join("out");
+            build(&args.compile, &executable);
+            let status = Command::new(&executable)
+                .args(&args.args)
+                .status()
+                .unwrap_or_else(|_| panic!("Failed to execute {}", executable.to_str().unwrap()));
+            let _ = fs::remove_dir_all(&out_dir);
+            std::process::exit(exit_code(status));
+        }
+        input::Command::Check(args) => type_checked(
+            &args.file_path,
+            &args.include.include_folders,
+            false,
+            |_| (),
+        ),
+        input::Command::Emit(args) => emit(&args),
+        input::Command::Interpret(args) => std::process::exit(interpret(&args)),
+        input::Command::Repl(args) => {
+            // Evaluated expressions are interpreted like in interpret
+            std::thread::Builder::new()
+                .stack_size(INTERPRETER_STACK_SIZE)
+                .spawn(move || repl::run_repl(&args))
+                .expect("Could not start REPL")
+                .join()
+                .expect("REPL panicked")
+        }
+        input::Command::Lsp => lsp::run_language_server().unwrap_or_else(|error| {
+            eprintln!("Language server failed: {}", error);
+            std::process::exit(1)
+        }),
+        input::Command::Fmt(args) => std::process::exit(fmt::run_formatter(&args)),
+        input::Command::Test(args) => std::process::exit(test_runner::run_tests(&args)),
+        input::Command::Bench(args) => bench::run_benchmark(&args),
+    }
+}
 
-    let executable_name = std::env::current_dir().unwrap().join(match cli.o {
-        None => PathBuf::from_str("out").unwrap(),
-        Some(exe) => exe,
-    });
-    let ll_path = executable_name.with_extension("ll");
-    let s_path = executable_name.with_extension("s");
+// Reads, parses and type checks the program and passes the result to f.
+// Imports are also searched in the include folders. Exits with code 1 if the
+// program does not type check.
+fn type_checked<T>(
+    file_path: &Path,
+    include_folders: &[PathBuf],
+    verbose: bool,
+    f: impl FnOnce(Binary) -> T,
+) -> T {
+    let loader = exit_on_error(FileSystem::new(file_path, include_folders));
+    let (codes, file_path) = exit_on_error(arena::load(&loader, file_path));
 
-    if cli.verbose {
+    if verbose {
         println!("CODE:");
         for (path, code) in &codes {
             println!("{}:\n{}\n", path.to_str().unwrap(), code);
         }
     }
 
-    let asts = codes
-        .iter()
-        .map(|(path, code)| (path, parser::parse(code.as_str())))
-        .collect::<HashMap<_, _>>();
+    let asts = exit_on_error(arena::parse(&codes));
 
-    if cli.verbose || cli.print_ast {
+    if verbose {
         println!("AST:");
         for (path, ast) in &asts {
             println!("{}:\n{:#?}\n", path.to_str().unwrap(), ast);
         }
     }
 
-    let (resolved_import_asts, main_module_id) = input::resolve_all_imports(asts, &cli.file_path);
+    let (resolved_import_asts, main_module_id) =
+        exit_on_error(arena::resolve_all_imports(&loader, asts, &file_path));
 
-    if cli.verbose {
+    if verbose {
         println!("Imports resolved AST:");
         for (prefix, ast) in &resolved_import_asts {
             println!("{}:\n{:#?}\n", prefix, ast);
         }
     }
 
-    let typed_ast = type_check::type_check(&resolved_import_asts, &main_module_id)
-        .expect("Type checking failed");
+    let typed_ast = exit_on_error(arena::type_check(&resolved_import_asts, &main_module_id));
 
-    if cli.verbose || cli.print_typed_ast {
+    if verbose {
         println!("TYPE-CHECKED AST:\n{:#?}", typed_ast);
     }
 
-    let profiling_frequency = match cli.profiling_frequency {
-        Some(i) => i,
-        None => 1,
-    };
+    f(typed_ast)
+}
 
-    if cli.spill {
-        codegen::codegen::<Spill>(
-            typed_ast,
-            ll_path.to_str().unwrap(),
-            cli.verbose || cli.print_llvm,
-            profiling_frequency,
-        )
-    } else if cli.tgc {
-        codegen::codegen::<TGC>(
-            typed_ast,
-            ll_path.to_str().unwrap(),
-            cli.verbose || cli.print_llvm,
-            profiling_frequency,
-        )
-    } else {
-        codegen::codegen::<ARC>(
-            typed_ast,
-            ll_path.to_str().unwrap(),
-            cli.verbose || cli.print_llvm,
-            profiling_frequency,
-        )
+// Prints the error and exits with code 1
+fn exit_on_error<T>(result: Result<T, arena::Error>) -> T {
+    result.unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        std::process::exit(1)
+    })
+}
+
+fn build(args: &CompileArgs, executable: &Path) {
+    let ll_path = executable.with_extension("ll");
+    let s_path = executable.with_extension("s");
+    type_checked(
+        &args.file_path,
+        &args.include.include_folders,
+        args.verbose,
+        |typed_ast| generate_llvm(args, typed_ast, &ll_path),
+    );
+    llc(&ll_path, &s_path, "asm");
+    link(args, &s_path, executable);
+    remove_file(&ll_path);
+    remove_file(&s_path);
+}
+
+fn emit(args: &EmitArgs) {
+    let compile = &args.compile;
+    match args.kind {
+        EmitKind::Ast => {
+            let loader = exit_on_error(FileSystem::new(
+                &compile.file_path,
+                &compile.include.include_folders,
+            ));
+            let (codes, _) = exit_on_error(arena::load(&loader, &compile.file_path));
+            let mut paths = codes.keys().collect::<Vec<_>>();
+            paths.sort();
+            let asts = exit_on_error(arena::parse(&codes));
+            let text = paths
+                .into_iter()
+                .map(|path| format!("{}:\n{:#?}\n", path.to_str().unwrap(), asts[path]))
+                .collect::<String>();
+            write_output(&args.o, &text);
+        }
+        EmitKind::TypedAst => {
+            let text = type_checked(
+                &compile.file_path,
+                &compile.include.include_folders,
+                compile.verbose,
+                |typed_ast| format!("{:#?}\n", typed_ast),
+            );
+            write_output(&args.o, &text);
+        }
+        EmitKind::Llvm | EmitKind::Asm | EmitKind::Obj => {
+            let out_dir = temp_dir("emit");
+            let ll_path = out_dir.join("out.ll");
+            type_checked(
+                &compile.file_path,
+                &compile.include.include_folders,
+                compile.verbose,
+                |typed_ast| generate_llvm(compile, typed_ast, &ll_path),
+            );
+            let emitted = match args.kind {
+                EmitKind::Asm => {
+                    let s_path = out_dir.join("out.s");
+                    llc(&ll_path, &s_path, "asm");
+                    s_path
+                }
+                EmitKind::Obj => {
+                    let o_path = out_dir.join("out.o");
+                    llc(&ll_path, &o_path, "obj");
+                    o_path
+                }
+                _ => ll_path,
+            };
+            match (&args.o, args.kind) {
+                (Some(o), _) => copy_file(&emitted, o),
+                (None, EmitKind::Obj) => copy_file(&emitted, Path::new("out.o")),
+                (None, _) => print!(
+                    "{}",
+                    fs::read_to_string(&emitted).expect("Could not read emitted code")
+                ),
+            }
+            let _ = fs::remove_dir_all(&out_dir);
+        }
+    }
+}
+
+// Returns the exit code of the program
+fn interpret(args: &InterpretArgs) -> i32 {
+    let backend = args.gc.backend();
+    let file_path = args.file_path.clone();
+    let include_folders = args.include.include_folders.clone();
+    // Like in compiled programs the first argument is the name of the program
+    let mut program_args = vec![file_path.to_str().unwrap().to_string()];
+    program_args.extend(args.args.iter().cloned());
+    // The interpreter recurses for every call of the interpreted program
+    std::thread::Builder::new()
+        .stack_size(INTERPRETER_STACK_SIZE)
+        .spawn(move || {
+            type_checked(&file_path, &include_folders, false, |typed_ast| {
+                let mut stdout = std::io::stdout();
+                let mut stderr = std::io::stderr();
+                exit_on_error(arena::interpret(
+                    &typed_ast,
+                    backend,
+                    &program_args,
+                    &mut stdout,
+                    &mut stderr,
+                ))
+            })
+        })
+        .expect("Could not start interpreter")
+        .join()
+        .expect("Interpreter panicked")
+}
+
+fn generate_llvm(args: &CompileArgs, typed_ast: Binary, ll_path: &Path) {
+    let options = CodegenOptions {
+        profiling_frequency: args.profiling_frequency.unwrap_or(1),
+        // Has to match the GC_STATS_FORMAT_* constants in runtime/gc_stats.h
+        gc_stats_format: match args.gc_stats {
+            None => 0,
+            Some(None) | Some(Some(GcStatsFormat::Text)) => 1,
+            Some(Some(GcStatsFormat::Json)) => 2,
+        },
+        unchecked: args.unchecked,
     };
+    let llvm_code = exit_on_error(arena::codegen(typed_ast, args.gc.backend(), &options));
+    if args.verbose {
+        println!("{}", llvm_code);
+    }
+    fs::write(ll_path, llvm_code)
+        .unwrap_or_else(|_| panic!("Could not write {}", ll_path.to_str().unwrap()));
+}
 
-    let mut llc_o_arg = "-o=".to_string();
-    llc_o_arg.push_str(s_path.to_str().unwrap());
+fn llc(ll_path: &Path, out_path: &Path, filetype: &str) {
     let llc_output = Command::new("llc-10")
         .arg("--relocation-model=pic")
-        .arg(llc_o_arg.as_str())
+        .arg(format!("--filetype={}", filetype))
+        .arg(format!("-o={}", out_path.to_str().unwrap()))
         .arg(ll_path.to_str().unwrap())
         .output()
         .expect("Failed to execute llc-10");
@@ -101,10 +258,15 @@ fn main() {
             String::from_utf8_lossy(&llc_output.stderr)
         )
     }
-    let libarena_name = if cli.stack_profiling {
+}
+
+fn link(args: &CompileArgs, s_path: &Path, executable: &Path) {
+    let libarena_name = if args.stack_profiling {
         "libarena_prof_stack.a"
-    } else if cli.heap_profiling {
+    } else if args.heap_profiling {
         "libarena_prof_heap.a"
+    } else if args.gc_stats.is_some() {
+        "libarena_gc_stats.a"
     } else {
         "libarena.a"
     };
@@ -113,12 +275,19 @@ fn main() {
         .parent()
         .expect("Could not get executable folder")
         .join(libarena_name);
+    // Libraries come after the code using them
     let gcc_output = Command::new("gcc")
         .arg("-Wextra")
         .arg(s_path.to_str().unwrap())
         .arg("-o")
-        .arg(executable_name.to_str().unwrap())
+        .arg(executable.to_str().unwrap())
         .arg(libarena_path.to_str().unwrap())
+        .args(
+            args.link.library_paths
+                .iter()
+                .map(|path| format!("-L{}", path.to_str().unwrap())),
+        )
+        .args(args.link.libraries.iter().map(|library| format!("-l{}", library)))
         .output()
         .expect("Failed to execute gcc");
     if !gcc_output.status.success() {
@@ -128,29 +297,37 @@ fn main() {
             String::from_utf8_lossy(&gcc_output.stderr)
         )
     }
+}
 
-    if !cli.keep_temporaries {
-        let rm_output_1 = Command::new("rm")
-            .arg(ll_path.to_str().unwrap())
-            .output()
-            .expect("Failed to execute rm");
-        if !rm_output_1.status.success() {
-            panic!(
-                "rm failed with error code {:?}. Output:\n{}",
-                rm_output_1.status.code(),
-                String::from_utf8_lossy(&rm_output_1.stderr)
-            )
-        }
-        let rm_output_2 = Command::new("rm")
-            .arg(s_path.to_str().unwrap())
-            .output()
-            .expect("Failed to execute rm");
-        if !rm_output_2.status.success() {
-            panic!(
-                "rm failed with error code {:?}. Output:\n{}",
-                rm_output_2.status.code(),
-                String::from_utf8_lossy(&rm_output_2.stderr)
-            )
-        }
+fn temp_dir(command: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("arena-{}-{}", command, std::process::id()));
+    fs::create_dir_all(&dir).expect("Could not create temporary folder");
+    dir
+}
+
+fn remove_file(path: &Path) {
+    fs::remove_file(path).unwrap_or_else(|_| panic!("Could not remove {}", path.to_str().unwrap()));
+}
+
+fn copy_file(from: &Path, to: &Path) {
+    fs::copy(from, to).unwrap_or_else(|_| panic!("Could not write {}", to.to_str().unwrap()));
+}
+
+fn write_output(o: &Option<PathBuf>, text: &str) {
+    match o {
+        None => print!("{}", text),
+        Some(path) => fs::write(path, text)
+            .unwrap_or_else(|_| panic!("Could not write {}", path.to_str().unwrap())),
+    }
+}
+
+// Exit code of a child process, using the shell convention of 128 + signal
+// number for processes that were terminated by a signal
+fn exit_code(status: std::process::ExitStatus) -> i32 {
+    use std::os::unix::process::ExitStatusExt;
+    match (status.code(), status.signal()) {
+        (Some(code), _) => code,
+        (None, Some(signal)) => 128 + signal,
+        (None, None) => 1,
     }
 }
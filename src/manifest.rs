@@ -0,0 +1,285 @@
+use std::path::{Path, PathBuf};
+
+use crate::error::Error;
+
+pub const MANIFEST_FILE: &str = "Arena.toml";
+
+// The Arena.toml of a project:
+//
+// [package]
+// name = "app"
+//
+// [dependencies]
+// shared = { path = "../shared" }
+//
+// Only a subset of TOML is supported: tables, inline tables, bare, quoted and
+// dotted keys, comments and values that fit on one line. Strings are the only
+// values that are read, others like numbers are skipped. The package table
+// describes the project and is not read by the compiler, and neither are keys
+// of dependencies other than path.
+pub struct Manifest {
+    pub dependencies: Vec<Dependency>,
+}
+
+pub struct Dependency {
+    pub name: String,
+    // Relative to the folder of the manifest
+    pub folder: PathBuf,
+    // Line of the definition, starting at 1
+    pub line: usize,
+}
+
+// Returns the path of the closest manifest in folder or one of its parents
+pub fn find(folder: &Path) -> Option<PathBuf> {
+    folder
+        .ancestors()
+        .map(|folder| folder.join(MANIFEST_FILE))
+        .find(|path| path.is_file())
+}
+
+// Keys with their values and the lines they are defined on, starting at 0
+#[derive(Default)]
+struct Table(Vec<(String, Value, usize)>);
+
+enum Value {
+    String(String),
+    Table(Table),
+    // Numbers, booleans and dates, which the manifest does not use
+    Other,
+}
+
+pub fn parse(path: &Path, text: &str) -> Result<Manifest, Error> {
+    let error = |line: usize, message: String| Error::Manifest {
+        path: path.to_path_buf(),
+        line: line + 1,
+        message,
+    };
+    let mut root = Table::default();
+    let mut section = Vec::new();
+    for (line, text) in text.lines().enumerate() {
+        let mut cursor = Cursor { text, position: 0 };
+        cursor.skip_whitespace();
+        if cursor.eat('[') {
+            section = cursor.keys().map_err(|message| error(line, message))?;
+            cursor.expect(']').map_err(|message| error(line, message))?;
+            root.table(&section, line)
+                .map_err(|message| error(line, message))?;
+        } else if !cursor.at_end() {
+            let keys = cursor.keys().map_err(|message| error(line, message))?;
+            cursor.expect('=').map_err(|message| error(line, message))?;
+            let value = cursor.value(line).map_err(|message| error(line, message))?;
+            let (key, tables) = keys.split_last().unwrap();
+            let full_path = [section.as_slice(), tables].concat();
+            root.table(&full_path, line)
+                .and_then(|table| table.insert(key, value, line))
+                .map_err(|message| error(line, message))?;
+        }
+        cursor.end().map_err(|message| error(line, message))?;
+    }
+
+    let mut dependencies = Vec::new();
+    for (key, value, line) in root.0 {
+        match (key.as_str(), value) {
+            ("package", Value::Table(_)) => (),
+            ("dependencies", Value::Table(table)) => {
+                for (name, value, line) in table.0 {
+                    let folder = dependency_folder(&name, value).map_err(|m| error(line, m))?;
+                    dependencies.push(Dependency {
+                        name,
+                        folder,
+                        line: line + 1,
+                    });
+                }
+            }
+            (key, _) => return Err(error(line, format!("Unknown table {}", key))),
+        }
+    }
+    Ok(Manifest { dependencies })
+}
+
+// The path of a dependency defined as name = value
+fn dependency_folder(name: &str, value: Value) -> Result<PathBuf, String> {
+    let Value::Table(table) = value else {
+        return Err(format!("Expected {} = {{ path = \"...\" }}", name));
+    };
+    match table.0.into_iter().find(|(key, _, _)| key == "path") {
+        Some((_, Value::String(folder), _)) => Ok(PathBuf::from(folder)),
+        Some(_) => Err(format!(
+            "The path of dependency {} has to be a string",
+            name
+        )),
+        None => Err(format!("Dependency {} has no path", name)),
+    }
+}
+
+impl Table {
+    // Returns the table at the keys, creating missing tables on the way
+    fn table(&mut self, keys: &[String], line: usize) -> Result<&mut Table, String> {
+        let Some((key, rest)) = keys.split_first() else {
+            return Ok(self);
+        };
+        if !self.0.iter().any(|(other, _, _)| other == key) {
+            self.0
+                .push((key.clone(), Value::Table(Table::default()), line));
+        }
+        match self.0.iter_mut().find(|(other, _, _)| other == key) {
+            Some((_, Value::Table(table), _)) => table.table(rest, line),
+            _ => Err(format!("{} is not a table", key)),
+        }
+    }
+
+    fn insert(&mut self, key: &str, value: Value, line: usize) -> Result<(), String> {
+        if self.0.iter().any(|(other, _, _)| other == key) {
+            return Err(format!("{} is defined twice", key));
+        }
+        self.0.push((key.to_string(), value, line));
+        Ok(())
+    }
+}
+
+struct Cursor<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl Cursor<'_> {
+    fn rest(&self) -> &str {
+        &self.text[self.position..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start_matches([' ', '\t']).len();
+    }
+
+    fn at_end(&self) -> bool {
+        matches!(self.peek(), None | Some('#'))
+    }
+
+    // Only whitespace and a comment may follow
+    fn end(&mut self) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.at_end() {
+            Ok(())
+        } else {
+            Err(format!("Unexpected {}", self.rest()))
+        }
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        let eaten = self.peek() == Some(c);
+        if eaten {
+            self.position += c.len_utf8();
+        }
+        eaten
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.eat(c) {
+            Ok(())
+        } else if self.at_end() {
+            Err(format!("Expected {} at the end of the line", c))
+        } else {
+            Err(format!("Expected {} instead of {}", c, self.rest()))
+        }
+    }
+
+    // A dotted key like dependencies."shared"
+    fn keys(&mut self) -> Result<Vec<String>, String> {
+        let mut keys = vec![self.key()?];
+        while self.eat('.') {
+            keys.push(self.key()?);
+        }
+        Ok(keys)
+    }
+
+    fn key(&mut self) -> Result<String, String> {
+        self.skip_whitespace();
+        if let Some('"' | '\'') = self.peek() {
+            return self.string();
+        }
+        let key = self
+            .rest()
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+            .collect::<String>();
+        if key.is_empty() {
+            return Err(format!("Expected a key instead of {}", self.rest()));
+        }
+        self.position += key.len();
+        Ok(key)
+    }
+
+    fn value(&mut self, line: usize) -> Result<Value, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('"' | '\'') => Ok(Value::String(self.string()?)),
+            Some('{') => {
+                self.position += 1;
+                let mut table = Table::default();
+                if self.eat('}') {
+                    return Ok(Value::Table(table));
+                }
+                loop {
+                    let keys = self.keys()?;
+                    self.expect('=')?;
+                    let value = self.value(line)?;
+                    let (key, tables) = keys.split_last().unwrap();
+                    table.table(tables, line)?.insert(key, value, line)?;
+                    if self.eat('}') {
+                        return Ok(Value::Table(table));
+                    }
+                    self.expect(',')?;
+                }
+            }
+            Some('[') => Err("Arrays are not supported".to_string()),
+            _ => {
+                let value = self
+                    .rest()
+                    .chars()
+                    .take_while(|c| !matches!(c, ',' | '}' | '#' | ' ' | '\t'))
+                    .collect::<String>();
+                if value.is_empty() {
+                    return Err("Expected a value".to_string());
+                }
+                self.position += value.len();
+                Ok(Value::Other)
+            }
+        }
+    }
+
+    // Parses a basic "string" with escapes or a literal 'string' without
+    fn string(&mut self) -> Result<String, String> {
+        let quote = self.peek().unwrap();
+        let mut chars = self.rest().char_indices().skip(1);
+        let mut string = String::new();
+        while let Some((index, c)) = chars.next() {
+            if c == quote {
+                self.position += index + 1;
+                return Ok(string);
+            }
+            if c != '\\' || quote == '\'' {
+                string.push(c);
+                continue;
+            }
+            match chars.next().map(|(_, c)| c) {
+                Some('\\') => string.push('\\'),
+                Some('"') => string.push('"'),
+                Some('n') => string.push('\n'),
+                Some('t') => string.push('\t'),
+                other => {
+                    return Err(format!(
+                        "Unknown escape sequence \\{}",
+                        other.unwrap_or(' ')
+                    ))
+                }
+            }
+        }
+        Err("Unterminated string".to_string())
+    }
+}
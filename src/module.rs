@@ -1,57 +1,147 @@
 use std::collections::HashMap;
+use std::fmt;
 use std::rc::Rc;
 
 #[derive(Debug)]
 pub struct Module<'input> {
+    // The file of the module as runtime errors report it, set when the
+    // imports are resolved
+    pub file: Rc<String>,
     pub imports: HashMap<&'input str, Rc<String>>,
     pub types: HashMap<&'input str, Type<'input>>,
-    pub functions: HashMap<&'input str, HashMap<Vec<IdLoc<'input>>, Function<'input>>>,
+    // Overloads by their parameter types, None for parameters without annotation
+    pub functions: HashMap<&'input str, HashMap<Vec<Option<IdLoc<'input>>>, Function<'input>>>,
+    pub traits: HashMap<&'input str, Trait<'input>>,
+    pub impls: Vec<Impl<'input>>,
+    pub externs: HashMap<&'input str, Extern<'input>>,
 }
 
 #[derive(Debug)]
 pub struct Type<'input> {
+    // Byte offset of the name in the code of the module
+    pub location: usize,
+    // Whether other modules can refer to the type by its name
+    pub public: bool,
     pub cases: Vec<TypeCase<'input>>,
+    // Build in traits whose methods are generated for the type
+    pub derives: Vec<&'input str>,
 }
 
 #[derive(Debug)]
 pub struct TypeCase<'input> {
+    // Byte offset of the name in the code of the module
+    pub location: usize,
+    // Whether other modules can construct and match the case and access its
+    // fields. Types without public cases are abstract outside their module.
+    pub public: bool,
     pub name: &'input str,
     pub fields: Vec<IdLoc<'input>>,
+    // The name of each field, None for fields that are only accessed by
+    // their position
+    pub field_names: Vec<Option<&'input str>>,
 }
 
 #[derive(Debug)]
 pub struct Function<'input> {
+    // Byte offset of the name in the code of the module
+    pub location: usize,
+    // Whether other modules can call the function, always false for the
+    // functions of implementations
+    pub public: bool,
     pub args: Vec<ParamDef<'input>>,
-    pub ret_type: IdLoc<'input>,
+    // None if the return type is inferred
+    pub ret_type: Option<IdLoc<'input>>,
     pub body: Expr<'input>,
 }
 
+// A C function that is linked into the program. The parameters always have
+// types.
+#[derive(Debug)]
+pub struct Extern<'input> {
+    // Byte offset of the name in the code of the module
+    pub location: usize,
+    pub args: Vec<ParamDef<'input>>,
+    // None if the function returns void
+    pub ret_type: Option<IdLoc<'input>>,
+}
+
+// Parameter and return types of the methods may refer to the implementing
+// type as Self
+#[derive(Debug)]
+pub struct Trait<'input> {
+    // Byte offset of the name in the code of the module
+    pub location: usize,
+    pub methods: HashMap<&'input str, TraitMethod<'input>>,
+}
+
+#[derive(Debug)]
+pub struct TraitMethod<'input> {
+    // Byte offset of the name in the code of the module
+    pub location: usize,
+    pub args: Vec<ParamDef<'input>>,
+    // None if the method returns void
+    pub ret_type: Option<IdLoc<'input>>,
+    // The default implementation, used by implementations without the method
+    pub body: Option<Expr<'input>>,
+}
+
+// The parameter and return types of the functions are the ones of the trait
+// methods, with Self replaced by the implementing type
+#[derive(Debug)]
+pub struct Impl<'input> {
+    // Byte offset of the trait name in the code of the module
+    pub location: usize,
+    pub trait_name: IdLoc<'input>,
+    pub type_name: IdLoc<'input>,
+    pub functions: HashMap<&'input str, Function<'input>>,
+}
+
 #[derive(Debug)]
 pub struct ParamDef<'input> {
     pub name: &'input str,
-    pub param_type: IdLoc<'input>,
+    // None if the type is inferred
+    pub param_type: Option<IdLoc<'input>>,
 }
 
 #[derive(Debug)]
 pub enum Expr<'input> {
+    // The elements of an array literal
+    Array(Vec<Expr<'input>>),
     Const(Const),
     FuncCall(IdLoc<'input>, Vec<Rc<Expr<'input>>>),
+    // A named field of a type with a single case
+    GetField(Rc<Expr<'input>>, &'input str),
     GetTypeCaseField(Rc<Expr<'input>>, &'input str, usize),
+    // The index of the field and the number of fields of the tuple
+    GetTupleField(Rc<Expr<'input>>, usize, usize),
     If(Box<Expr<'input>>, Rc<Expr<'input>>, Box<Expr<'input>>),
     IsTypeCase(Rc<Expr<'input>>, IdLoc<'input>, &'input str),
     Let(&'input str, Rc<Expr<'input>>, Rc<Expr<'input>>),
+    // Binds the variables of a pattern that matches every value of its type
+    LetPattern(MatchPattern<'input>, Box<Expr<'input>>, Rc<Expr<'input>>),
+    // The line of the match is reported if no arm matches
     Match(
+        usize,
         Box<Expr<'input>>,
         Vec<(MatchPattern<'input>, Rc<Expr<'input>>)>,
     ),
+    // A call of panic or assert with the line of the call and the name of the
+    // called function, which stops the program with the message
+    Panic(usize, &'input str, Rc<Expr<'input>>),
     Seq(Box<Expr<'input>>, Box<Expr<'input>>),
+    Tuple(Vec<Expr<'input>>),
+    // A case without fields may also be the field access variable.field,
+    // which is told apart by the variable being in scope
     TypeCase(IdLoc<'input>, &'input str, Vec<Expr<'input>>),
+    // A copy of a value of a single case type with some fields replaced
+    UpdateFields(Box<Expr<'input>>, Vec<(&'input str, Expr<'input>)>),
     Var(&'input str),
 }
 
 #[derive(Debug)]
 pub enum MatchPattern<'input> {
     Literal(Const),
+    Tuple(Vec<MatchPattern<'input>>),
     TypeCase(IdLoc<'input>, &'input str, Vec<MatchPattern<'input>>),
     Var(&'input str),
     Wildcard,
@@ -70,6 +160,24 @@ pub enum Const {
 pub enum IdLoc<'input> {
     Here(&'input str),
     Other(&'input str, &'input str),
+    // The field types of a tuple type, only used where a type is expected
+    Tuple(Vec<IdLoc<'input>>),
+    // The element type of an array type, only used where a type is expected
+    Array(Box<IdLoc<'input>>),
+}
+
+impl fmt::Display for IdLoc<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IdLoc::Here(id) => write!(f, "{}", id),
+            IdLoc::Other(module, id) => write!(f, "{}::{}", module, id),
+            IdLoc::Tuple(fields) => {
+                let fields = fields.iter().map(|f| f.to_string()).collect::<Vec<_>>();
+                write!(f, "({})", fields.join(", "))
+            }
+            IdLoc::Array(element) => write!(f, "[{}]", element),
+        }
+    }
 }
 
 pub fn replace_escape_characters(str_in: String) -> Rc<String> {
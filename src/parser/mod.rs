@@ -1,21 +1,89 @@
+use std::fmt;
 use std::rc::Rc;
 
-use crate::module::Module;
-use lalrpop_util::lalrpop_mod;
+use crate::module::{Expr, Module};
+use lalrpop_util::{lalrpop_mod, ParseError};
 
 lalrpop_mod!(parser, "/parser/parser.rs");
 
-pub fn parse(input: &str) -> Module {
+#[derive(Debug)]
+pub struct SyntaxError {
+    // Byte offset into the parsed code
+    pub location: usize,
+    pub message: String,
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+pub fn parse(input: &str) -> Result<Module<'_>, SyntaxError> {
     parser::ModuleParser::new()
         .parse(input)
-        .expect("Parsing failed")
+        .map_err(syntax_error)
+}
+
+// Parses a single expression, like the body of a function
+pub fn parse_expr(input: &str) -> Result<Expr<'_>, SyntaxError> {
+    parser::ExprParser::new().parse(input).map_err(syntax_error)
 }
 
-pub fn parse_imports(input: &str) -> Vec<Rc<String>> {
+fn syntax_error<T: fmt::Display>(error: ParseError<usize, T, SyntaxError>) -> SyntaxError {
+    match error {
+        ParseError::InvalidToken { location } => SyntaxError {
+            location,
+            message: "Invalid token".to_string(),
+        },
+        ParseError::UnrecognizedEOF { location, expected } => SyntaxError {
+            location,
+            message: format!("Unexpected end of file, expected {}", expected.join(", ")),
+        },
+        ParseError::UnrecognizedToken {
+            token: (location, token, _),
+            expected,
+        } => SyntaxError {
+            location,
+            message: format!(
+                "Unexpected token \"{}\", expected {}",
+                token,
+                expected.join(", ")
+            ),
+        },
+        ParseError::ExtraToken {
+            token: (location, token, _),
+        } => SyntaxError {
+            location,
+            message: format!("Unexpected token \"{}\"", token),
+        },
+        ParseError::User { error } => error,
+    }
+}
+
+pub fn parse_imports(input: &str) -> Result<Vec<Rc<String>>, SyntaxError> {
     // TODO: Parse imports without parsing whole file
-    parse(input)
+    Ok(parse(input)?
         .imports
         .into_iter()
         .map(|(_, path)| path)
-        .collect()
+        .collect())
+}
+
+// Line of the byte offset into the input, starting at 1
+pub fn line(input: &str, location: usize) -> usize {
+    input[..location.min(input.len())].matches('\n').count() + 1
+}
+
+pub fn integer_error<T>(
+    location: usize,
+    literal: &str,
+    int_type: &str,
+) -> ParseError<usize, T, SyntaxError> {
+    ParseError::User {
+        error: SyntaxError {
+            location,
+            message: format!("{} does not fit into {}", literal, int_type),
+        },
+    }
 }
@@ -0,0 +1,321 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use arena::binary::{BinExpr, Binary};
+use arena::interpreter::type_name;
+use arena::module::{Const, Expr, Function, IdLoc, Module};
+use arena::sources::{FileSystem, SourceLoader};
+use arena::types::VOID_TYPE;
+use arena::{parser, sources, Error};
+
+use crate::input::{Backend, ReplArgs};
+
+// Expressions are type checked as the body of a function with this name. It
+// can not clash with user defined functions, as identifiers can not contain $.
+const EXPRESSION_FUNCTION: &str = "$repl";
+const KEYWORDS: [&str; 8] = [
+    "fn", "type", "derive", "import", "trait", "impl", "extern", "pub",
+];
+
+const PROMPT: &str = "> ";
+const CONTINUATION_PROMPT: &str = ". ";
+const HELP: &str = "\
+fn ... / type ... / import ...  add a declaration
+<expr>                          evaluate an expression
+:type <expr>                    show the type of an expression
+:load <file>                    replace all definitions by the ones of file
+:help                           show this help
+:quit                           exit the REPL";
+
+// The definitions of a REPL session. Declarations typed into the REPL are
+// merged into the module of the loaded file, replacing earlier definitions
+// with the same name.
+struct Session {
+    // Canonical path of the loaded file. Without a loaded file this is an
+    // empty file in the working directory that does not exist on disk.
+    main_path: PathBuf,
+    file_loaded: bool,
+    // Declarations typed into the REPL, in order
+    inputs: Vec<String>,
+    // Given with -I, kept to search the imports of files loaded later
+    include_folders: Vec<PathBuf>,
+    // Reads the files of the program that are not part of the session
+    file_system: FileSystem,
+}
+
+impl Session {
+    fn new(include_folders: &[PathBuf]) -> Result<Self, Error> {
+        let current_dir = std::env::current_dir().expect("Could not get working directory");
+        let main_path = current_dir.join("<repl>");
+        Ok(Session {
+            file_system: FileSystem::new(&main_path, include_folders)?,
+            main_path,
+            file_loaded: false,
+            inputs: Vec::new(),
+            include_folders: include_folders.to_vec(),
+        })
+    }
+
+    // Declarations are read from files next to the main file, so that their
+    // imports are resolved relative to it
+    fn input_path(&self, index: usize) -> PathBuf {
+        self.main_path
+            .with_file_name(format!("<input {}>", index + 1))
+    }
+
+    fn virtual_file(&self, path: &Path) -> Option<&str> {
+        if !self.file_loaded && path == self.main_path {
+            return Some("");
+        }
+        (0..self.inputs.len())
+            .find(|index| self.input_path(*index) == path)
+            .map(|index| self.inputs[index].as_str())
+    }
+
+    // Type checks all definitions of the session, together with expr if given,
+    // and passes the result and the id of the main module to f
+    fn type_checked<T>(
+        &self,
+        expr: Option<&str>,
+        f: impl FnOnce(Binary, &Rc<String>) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let mut paths = vec![self.main_path.clone()];
+        paths.extend((0..self.inputs.len()).map(|index| self.input_path(index)));
+        let mut codes = HashMap::new();
+        for path in paths.iter() {
+            let (files, _) = arena::load(self, path)?;
+            codes.extend(files);
+        }
+        let mut asts = arena::parse(&codes)?;
+
+        let mut main_module = asts.remove(&self.main_path).unwrap();
+        for path in paths[1..].iter() {
+            merge(&mut main_module, asts.remove(path).unwrap());
+        }
+        if let Some(expr) = expr {
+            let expr = parser::parse_expr(expr)
+                .map_err(|e| sources::parse_error(Path::new("<input>"), expr, e))?;
+            let function = Function {
+                location: 0,
+                public: false,
+                args: Vec::new(),
+                ret_type: Some(IdLoc::Here(VOID_TYPE)),
+                body: Expr::Seq(Box::new(expr), Box::new(Expr::Const(Const::Void))),
+            };
+            main_module
+                .functions
+                .insert(EXPRESSION_FUNCTION, HashMap::from([(Vec::new(), function)]));
+        }
+        asts.insert(&self.main_path, main_module);
+
+        let (modules, main_module_id) = arena::resolve_all_imports(self, asts, &self.main_path)?;
+        let binary = arena::type_check_definitions(&modules).map_err(without_repl_function)?;
+        f(binary, &main_module_id)
+    }
+
+    fn declare(&mut self, declaration: &str) -> Result<(), Error> {
+        self.inputs.push(declaration.to_string());
+        let checked = self.type_checked(None, |_, _| Ok(()));
+        if checked.is_err() {
+            self.inputs.pop();
+        }
+        checked
+    }
+
+    fn load(&mut self, file_path: &Path) -> Result<(), Error> {
+        let main_path = self
+            .file_system
+            .canonicalize(file_path)
+            .ok_or_else(|| Error::Io {
+                path: file_path.to_path_buf(),
+                message: "No such file".to_string(),
+            })?;
+        let session = Session {
+            file_system: FileSystem::new(&main_path, &self.include_folders)?,
+            main_path,
+            file_loaded: true,
+            inputs: Vec::new(),
+            include_folders: self.include_folders.clone(),
+        };
+        session.type_checked(None, |_, _| Ok(()))?;
+        *self = session;
+        Ok(())
+    }
+
+    // Prints the value of expr and its type, or only its type if evaluate is false
+    fn evaluate(&self, expr: &str, backend: Backend, evaluate: bool) -> Result<(), Error> {
+        self.type_checked(Some(expr), |binary, main_module_id| {
+            let function_id =
+                arena::type_check::function_id(main_module_id, EXPRESSION_FUNCTION, 0);
+            let expr = match &binary.functions[&function_id].body.expr {
+                BinExpr::Seq(expr, _) => expr,
+                _ => unreachable!("Expression function has to be a sequence"),
+            };
+            let expr_type = type_name(expr.expr_type.as_str());
+            if !evaluate {
+                println!("{}", expr_type);
+                return Ok(());
+            }
+            let mut stdout = io::stdout();
+            let mut stderr = io::stderr();
+            let value = arena::evaluate(&binary, expr, backend, &mut stdout, &mut stderr)?;
+            if let Some(value) = value {
+                println!("{} : {}", value, expr_type);
+            }
+            Ok(())
+        })
+    }
+}
+
+impl SourceLoader for Session {
+    fn canonicalize(&self, path: &Path) -> Option<PathBuf> {
+        match self.virtual_file(path) {
+            Some(_) => Some(path.to_path_buf()),
+            None => self.file_system.canonicalize(path),
+        }
+    }
+
+    fn read(&self, path: &Path) -> Result<String, Error> {
+        match self.virtual_file(path) {
+            Some(code) => Ok(code.to_string()),
+            None => self.file_system.read(path),
+        }
+    }
+
+    fn dependencies(&self) -> Vec<(String, PathBuf)> {
+        self.file_system.dependencies()
+    }
+
+    fn library_folders(&self) -> Vec<PathBuf> {
+        self.file_system.library_folders()
+    }
+}
+
+// Adds the definitions of other to module. Functions with the same signature
+// and types, traits and externs with the same name are replaced.
+fn merge<'input>(module: &mut Module<'input>, other: Module<'input>) {
+    module.imports.extend(other.imports);
+    module.types.extend(other.types);
+    for (name, functions) in other.functions {
+        module.functions.entry(name).or_default().extend(functions);
+    }
+    module.traits.extend(other.traits);
+    module.impls.extend(other.impls);
+    module.externs.extend(other.externs);
+}
+
+// Errors in the typed expression are shown without the function it is
+// wrapped in, which the user never wrote
+fn without_repl_function(error: Error) -> Error {
+    match error {
+        Error::Type {
+            message,
+            function: Some(function),
+        } if function.name == EXPRESSION_FUNCTION => Error::Type {
+            message,
+            function: None,
+        },
+        error => error,
+    }
+}
+
+fn is_declaration(input: &str) -> bool {
+    let first_word = input
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .next()
+        .unwrap_or("");
+    KEYWORDS.contains(&first_word)
+}
+
+// An input is incomplete if it could only be parsed with more lines
+fn is_incomplete(input: &str) -> bool {
+    if input.starts_with(':') {
+        return false;
+    }
+    let end = input.trim_end().len();
+    if is_declaration(input) {
+        parser::parse(input).map_or_else(|e| e.location >= end, |_| false)
+    } else {
+        parser::parse_expr(input).map_or_else(|e| e.location >= end, |_| false)
+    }
+}
+
+// Returns false if the REPL should exit
+fn execute(session: &mut Session, input: &str, backend: Backend) -> bool {
+    let (command, argument) = input
+        .split_once(char::is_whitespace)
+        .map_or((input, ""), |(command, argument)| {
+            (command, argument.trim())
+        });
+    let result = match command {
+        ":quit" | ":q" => return false,
+        ":help" | ":h" => {
+            println!("{}", HELP);
+            Ok(())
+        }
+        ":type" | ":t" => session.evaluate(argument, backend, false),
+        ":load" | ":l" => session.load(Path::new(argument)),
+        _ if command.starts_with(':') => {
+            eprintln!("Unknown command {}, see :help", command);
+            Ok(())
+        }
+        _ if is_declaration(input) => session.declare(input),
+        _ => session.evaluate(input, backend, true),
+    };
+    if let Err(error) = result {
+        eprintln!("{}", error);
+    }
+    true
+}
+
+pub fn run_repl(args: &ReplArgs) {
+    let backend = args.gc.backend();
+    let mut session = Session::new(&args.include.include_folders).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        std::process::exit(1)
+    });
+    if let Some(file_path) = &args.file_path {
+        if let Err(error) = session.load(file_path) {
+            eprintln!("{}", error);
+        }
+    }
+
+    let stdin = io::stdin();
+    let mut input = String::new();
+    loop {
+        print!(
+            "{}",
+            if input.is_empty() {
+                PROMPT
+            } else {
+                CONTINUATION_PROMPT
+            }
+        );
+        io::stdout().flush().expect("Could not write prompt");
+        let mut line = String::new();
+        if stdin
+            .lock()
+            .read_line(&mut line)
+            .expect("Could not read input")
+            == 0
+        {
+            // End of input
+            println!();
+            break;
+        }
+        if input.is_empty() && line.trim().is_empty() {
+            continue;
+        }
+        input.push_str(&line);
+        // An empty line ends an incomplete input to show its error
+        if !line.trim().is_empty() && is_incomplete(&input) {
+            continue;
+        }
+        let complete = std::mem::take(&mut input);
+        if !execute(&mut session, complete.trim(), backend) {
+            break;
+        }
+    }
+}
@@ -0,0 +1,391 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+
+use crate::error::Error;
+use crate::manifest;
+use crate::module::Module;
+use crate::parser::parse_imports;
+
+// Modules by module id
+pub type Modules<'input> = HashMap<Rc<String>, Module<'input>>;
+
+// Where the compiler reads the code of a program from
+pub trait SourceLoader {
+    // Unique path of a file, used to identify modules
+    fn canonicalize(&self, path: &Path) -> Option<PathBuf>;
+
+    fn read(&self, path: &Path) -> Result<String, Error>;
+
+    // The named dependencies of the program with their folders. Imports
+    // starting with the name of a dependency are searched in its folder.
+    fn dependencies(&self) -> Vec<(String, PathBuf)>;
+
+    // The folders imports are searched in, in order, if they are not found
+    // next to the importing file or in a dependency
+    fn library_folders(&self) -> Vec<PathBuf>;
+}
+
+// Reads the program from disk. Imports are searched relative to the
+// importing file, in the dependencies of the program, in the include folders
+// and in ~/.arena/lib and the lib folder next to the compiler.
+pub struct FileSystem {
+    // Folders given on the command line, followed by the ones in ARENA_PATH
+    include_folders: Vec<PathBuf>,
+    // Canonical folders of the dependencies in the manifest of the program
+    dependencies: Vec<(String, PathBuf)>,
+}
+
+impl FileSystem {
+    // The loader for the program with the main module at main_path. The
+    // include folders are searched before the ones in the ARENA_PATH
+    // environment variable, which are separated like in PATH. The dependencies
+    // are read from the closest Arena.toml in the folder of the main module or
+    // one of its parents.
+    pub fn new(main_path: &Path, include_folders: &[PathBuf]) -> Result<Self, Error> {
+        let mut include_folders = include_folders.to_vec();
+        if let Some(arena_path) = std::env::var_os("ARENA_PATH") {
+            include_folders.extend(
+                std::env::split_paths(&arena_path).filter(|folder| !folder.as_os_str().is_empty()),
+            );
+        }
+        let main_path = std::env::current_dir()
+            .map(|current_dir| current_dir.join(main_path))
+            .unwrap_or_else(|_| main_path.to_path_buf());
+        let dependencies = match main_path.parent().and_then(manifest::find) {
+            Some(manifest_path) => read_dependencies(&manifest_path)?,
+            None => Vec::new(),
+        };
+        Ok(FileSystem {
+            include_folders,
+            dependencies,
+        })
+    }
+}
+
+impl SourceLoader for FileSystem {
+    fn canonicalize(&self, path: &Path) -> Option<PathBuf> {
+        fs::canonicalize(path).ok()
+    }
+
+    fn read(&self, path: &Path) -> Result<String, Error> {
+        fs::read_to_string(path).map_err(|e| Error::Io {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })
+    }
+
+    fn dependencies(&self) -> Vec<(String, PathBuf)> {
+        self.dependencies.clone()
+    }
+
+    fn library_folders(&self) -> Vec<PathBuf> {
+        let mut folders = self.include_folders.clone();
+        if let Ok(home) = std::env::var("HOME") {
+            folders.push(PathBuf::from(home).join(".arena/lib"));
+        }
+        if let Some(exe_folder) = std::env::current_exe()
+            .ok()
+            .and_then(|exe_path| exe_path.parent().map(Path::to_path_buf))
+        {
+            folders.push(exe_folder.join("lib"));
+        }
+        folders
+    }
+}
+
+// Returns the canonical folders of the dependencies in the manifest
+fn read_dependencies(manifest_path: &Path) -> Result<Vec<(String, PathBuf)>, Error> {
+    let text = fs::read_to_string(manifest_path).map_err(|e| Error::Io {
+        path: manifest_path.to_path_buf(),
+        message: e.to_string(),
+    })?;
+    let manifest_folder = manifest_path.parent().unwrap_or(manifest_path);
+    manifest::parse(manifest_path, &text)?
+        .dependencies
+        .into_iter()
+        .map(|dependency| {
+            let folder = manifest_folder.join(&dependency.folder);
+            match fs::canonicalize(&folder) {
+                Ok(folder) if folder.is_dir() => Ok((dependency.name, folder)),
+                _ => Err(Error::Manifest {
+                    path: manifest_path.to_path_buf(),
+                    line: dependency.line,
+                    message: format!(
+                        "Folder {} of dependency {} does not exist",
+                        folder.display(),
+                        dependency.name
+                    ),
+                }),
+            }
+        })
+        .collect()
+}
+
+// Reads the program from a set of files held in memory. Imports are searched
+// relative to the importing file and in the lib folder at the root.
+#[derive(Default)]
+pub struct InMemory {
+    files: HashMap<PathBuf, String>,
+}
+
+impl InMemory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, path: impl AsRef<Path>, code: impl Into<String>) -> &mut Self {
+        self.files.insert(normalize(path.as_ref()), code.into());
+        self
+    }
+}
+
+impl SourceLoader for InMemory {
+    fn canonicalize(&self, path: &Path) -> Option<PathBuf> {
+        let path = normalize(path);
+        self.files.contains_key(&path).then_some(path)
+    }
+
+    fn read(&self, path: &Path) -> Result<String, Error> {
+        self.files.get(path).cloned().ok_or_else(|| Error::Io {
+            path: path.to_path_buf(),
+            message: "No such file".to_string(),
+        })
+    }
+
+    fn dependencies(&self) -> Vec<(String, PathBuf)> {
+        Vec::new()
+    }
+
+    fn library_folders(&self) -> Vec<PathBuf> {
+        vec![PathBuf::from("/lib")]
+    }
+}
+
+// Removes . and .. from an in-memory path and makes it absolute
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::from("/");
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::Normal(name) => normalized.push(name),
+            _ => (),
+        }
+    }
+    normalized
+}
+
+// Reads the file at main_path and all files it imports transitively.
+// Returns (files, canonical path of main_path)
+pub fn load(
+    loader: &impl SourceLoader,
+    main_path: &Path,
+) -> Result<(HashMap<PathBuf, String>, PathBuf), Error> {
+    let main_path = loader.canonicalize(main_path).ok_or_else(|| Error::Io {
+        path: main_path.to_path_buf(),
+        message: "No such file".to_string(),
+    })?;
+    let mut files = HashMap::new();
+    load_imports(loader, &mut vec![main_path.clone()], &mut files)?;
+    Ok((files, main_path))
+}
+
+// Reads the last module of the import chain and follows its imports depth
+// first. The chain holds the modules from the main module on that import each
+// other, so an import of a module in the chain closes a cycle.
+fn load_imports(
+    loader: &impl SourceLoader,
+    chain: &mut Vec<PathBuf>,
+    files: &mut HashMap<PathBuf, String>,
+) -> Result<(), Error> {
+    let path = chain.last().expect("Import chain is empty").clone();
+    let code = loader.read(&path)?;
+    let mut imports = parse_imports(code.as_str()).map_err(|e| parse_error(&path, &code, e))?;
+    // The imports of a module are unordered, they are followed in the order
+    // of their paths to report the same cycle every time
+    imports.sort();
+    files.insert(path, code);
+    for import in imports {
+        let imported = resolve_import(loader, chain, &import)?;
+        if chain.contains(&imported) {
+            let mut cycle = chain.clone();
+            cycle.push(imported);
+            return Err(Error::ImportCycle { chain: cycle });
+        }
+        if !files.contains_key(&imported) {
+            chain.push(imported);
+            load_imports(loader, chain, files)?;
+            chain.pop();
+        }
+    }
+    Ok(())
+}
+
+pub fn parse_error(path: &Path, code: &str, error: crate::parser::SyntaxError) -> Error {
+    let before = &code[..error.location.min(code.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+    Error::Parse {
+        path: path.to_path_buf(),
+        line,
+        column,
+        message: error.message,
+    }
+}
+
+// Returns the canonical path of the file imported as import by the last module
+// of the import chain. It is searched next to the importing file, in the
+// folder of the dependency the import starts with and then in the library
+// folders.
+fn resolve_import(
+    loader: &impl SourceLoader,
+    chain: &[PathBuf],
+    import: &str,
+) -> Result<PathBuf, Error> {
+    let (current_file, imported_by) = chain.split_last().expect("Import chain is empty");
+    let import_path = PathBuf::from(import).with_extension("arena");
+    let mut searched = current_file
+        .parent()
+        .into_iter()
+        .map(|folder| folder.join(&import_path))
+        .collect::<Vec<_>>();
+    let mut components = import_path.components();
+    if let (Some(Component::Normal(name)), Some(_)) = (components.next(), components.clone().next())
+    {
+        searched.extend(
+            loader
+                .dependencies()
+                .into_iter()
+                .filter(|(dependency, _)| name == dependency.as_str())
+                .map(|(_, folder)| folder.join(components.as_path())),
+        );
+    }
+    searched.extend(
+        loader
+            .library_folders()
+            .into_iter()
+            .map(|folder| folder.join(&import_path)),
+    );
+    searched
+        .iter()
+        .find_map(|path| loader.canonicalize(path))
+        .ok_or_else(|| Error::Import {
+            path: current_file.to_path_buf(),
+            import: import.to_string(),
+            imported_by: imported_by.to_vec(),
+            searched,
+        })
+}
+
+pub fn resolve_all_imports<'input>(
+    loader: &impl SourceLoader,
+    asts: HashMap<&PathBuf, Module<'input>>,
+    main_module_path: &Path,
+) -> Result<(Modules<'input>, Rc<String>), Error> {
+    // returns (modules, main_module_id)
+
+    let mut modules = HashMap::new();
+    for (path, module) in asts {
+        let mut imports = HashMap::new();
+        for (name, imp_path) in module.imports {
+            let imp = resolve_import(loader, &[path.to_path_buf()], &imp_path)?;
+            imports.insert(name, module_id(loader, main_module_path, &imp));
+        }
+        modules.insert(
+            module_id(loader, main_module_path, path),
+            Module {
+                file: Rc::new(display_path(path)),
+                imports,
+                types: module.types,
+                functions: module.functions,
+                traits: module.traits,
+                impls: module.impls,
+                externs: module.externs,
+            },
+        );
+    }
+    Ok((
+        modules,
+        module_id(loader, main_module_path, main_module_path),
+    ))
+}
+
+// The path relative to the current folder if the file is inside of it
+pub(crate) fn display_path(path: &Path) -> String {
+    std::env::current_dir()
+        .ok()
+        .and_then(|current_dir| path.strip_prefix(current_dir).ok())
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+// Id of the module at the canonical path, prefixed to the ids of its types and
+// functions. It is derived from the path relative to the folder of the main
+// module, or else to the dependency or library folder containing the module,
+// so that the ids and with them the generated code do not depend on where the
+// program and its dependencies are located. Library modules also include the
+// canonical library folder, as two folders can contain modules with the same
+// path, but not its position, which changes with -I and ARENA_PATH.
+pub fn module_id(loader: &impl SourceLoader, main_path: &Path, path: &Path) -> Rc<String> {
+    let main_folder = main_path.parent().unwrap_or(main_path);
+    let relative = match path.strip_prefix(main_folder) {
+        Ok(relative) => relative_components(relative),
+        Err(_) => loader
+            .dependencies()
+            .iter()
+            .find_map(|(name, folder)| {
+                let folder = loader.canonicalize(folder).unwrap_or(folder.to_path_buf());
+                let relative = path.strip_prefix(folder).ok()?;
+                Some(format!("<{}>/{}", name, relative_components(relative)))
+            })
+            .or_else(|| {
+                loader.library_folders().iter().find_map(|folder| {
+                    let folder = loader.canonicalize(folder).unwrap_or(folder.to_path_buf());
+                    let relative = path.strip_prefix(&folder).ok()?;
+                    Some(format!(
+                        "<{}>/{}",
+                        folder.display(),
+                        relative_components(relative)
+                    ))
+                })
+            })
+            .unwrap_or_else(|| relative_components(&relative_path(main_folder, path))),
+    };
+    Rc::new(format!("${:x}$", stable_hash(&relative)))
+}
+
+// The components of a relative path separated by /, independent of the
+// platform
+fn relative_components(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+// The path relative to folder, going up with .. for paths outside of it
+fn relative_path(folder: &Path, path: &Path) -> PathBuf {
+    let common = folder
+        .components()
+        .zip(path.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut relative = PathBuf::new();
+    for _ in folder.components().skip(common) {
+        relative.push("..");
+    }
+    relative.extend(path.components().skip(common));
+    relative
+}
+
+// FNV-1a, which unlike DefaultHasher is guaranteed to stay the same between
+// Rust versions
+fn stable_hash(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
@@ -0,0 +1,406 @@
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::input::{selected_backends, Backend, TestArgs};
+
+const RESULT_HEADER: &str = "// Result:";
+const COMPILE_ERROR_HEADER: &str = "// Compile error:";
+const EXIT_HEADER: &str = "// Exit:";
+const COMPILE_ONLY_HEADER: &str = "// Compile only";
+const ARGS_HEADER: &str = "// Args:";
+const ENV_HEADER: &str = "// Env:";
+// Environment variable with a folder the test can write files into. Every
+// test case gets its own, as the cases run in parallel.
+const TEMP_DIR_VARIABLE: &str = "ARENA_TEST_TEMP_DIR";
+const RESULT_PREFIX: &str = "// ";
+
+struct Expectation {
+    // The message the program fails to compile with, instead of running
+    compile_error: Option<String>,
+    stdout: Vec<String>,
+    exit_code: i32,
+    // Not run in the interpreter, e.g. because it calls extern functions or
+    // takes minutes to interpret
+    compile_only: bool,
+    // Command line arguments and environment variables of the program
+    args: Vec<String>,
+    env: Vec<(String, String)>,
+}
+
+struct TestCase {
+    path: PathBuf,
+    backend: Backend,
+    // Run in the interpreter instead of compiling
+    interpret: bool,
+    expectation: Arc<Expectation>,
+}
+
+enum Outcome {
+    Passed,
+    Failed(String),
+}
+
+// Returns the exit code of the test command
+pub fn run_tests(args: &TestArgs) -> i32 {
+    let roots = if args.paths.is_empty() {
+        vec![PathBuf::from("tests")]
+    } else {
+        args.paths.clone()
+    };
+    let mut test_files = Vec::new();
+    for root in roots.iter() {
+        collect_test_files(root, &mut test_files);
+    }
+    test_files.sort();
+
+    let backends = selected_backends(&args.gc);
+
+    let mut skipped = Vec::new();
+    let mut cases = Vec::new();
+    for path in test_files {
+        let code = fs::read_to_string(&path)
+            .unwrap_or_else(|_| panic!("Could not read test {}", path.to_str().unwrap()));
+        match parse_expectation(&code) {
+            None => skipped.push(path),
+            Some(expectation) => {
+                let expectation = Arc::new(expectation);
+                let modes = if args.interpret && !expectation.compile_only {
+                    vec![false, true]
+                } else {
+                    vec![false]
+                };
+                for interpret in modes {
+                    for backend in backends.iter() {
+                        cases.push(TestCase {
+                            path: path.clone(),
+                            backend: *backend,
+                            interpret,
+                            expectation: Arc::clone(&expectation),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    let out_dir = std::env::temp_dir().join(format!("arena-test-{}", std::process::id()));
+    fs::create_dir_all(&out_dir).expect("Could not create temporary test folder");
+
+    let jobs = args.jobs.unwrap_or_else(|| {
+        thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+    });
+    let timeout = Duration::from_secs(args.timeout);
+    let case_count = cases.len();
+    // Reversed so that popping from the back runs the tests in order
+    let queue = Arc::new(Mutex::new(
+        cases.into_iter().enumerate().rev().collect::<Vec<_>>(),
+    ));
+    let results = Arc::new(Mutex::new(Vec::new()));
+    let workers = (0..jobs.max(1))
+        .map(|_| {
+            let queue = Arc::clone(&queue);
+            let results = Arc::clone(&results);
+            let out_dir = out_dir.clone();
+            thread::spawn(move || loop {
+                let next = queue.lock().unwrap().pop();
+                match next {
+                    None => break,
+                    Some((index, case)) => {
+                        let executable = out_dir.join(format!("test_{}", index));
+                        let outcome = run_test_case(&case, &executable, timeout);
+                        report_outcome(&case, &outcome);
+                        results.lock().unwrap().push(outcome);
+                    }
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for worker in workers {
+        worker.join().expect("Test worker panicked");
+    }
+    let _ = fs::remove_dir_all(&out_dir);
+
+    for path in skipped.iter() {
+        println!(
+            "SKIPPED {} (no {} or {} header)",
+            path.to_str().unwrap(),
+            RESULT_HEADER,
+            COMPILE_ERROR_HEADER
+        );
+    }
+    let results = results.lock().unwrap();
+    let passed = results
+        .iter()
+        .filter(|outcome| matches!(outcome, Outcome::Passed))
+        .count();
+    let failed = case_count - passed;
+    println!();
+    println!("Results:");
+    println!("Passed: {}", passed);
+    println!("Failed: {}", failed);
+    println!("Skipped: {}", skipped.len());
+    if failed == 0 {
+        0
+    } else {
+        1
+    }
+}
+
+fn collect_test_files(path: &Path, test_files: &mut Vec<PathBuf>) {
+    if path.is_dir() {
+        let entries = fs::read_dir(path)
+            .unwrap_or_else(|_| panic!("Could not read folder {}", path.to_str().unwrap()));
+        for entry in entries {
+            collect_test_files(
+                &entry.expect("Could not read folder entry").path(),
+                test_files,
+            );
+        }
+    } else if path.extension().map(|ext| ext == "arena").unwrap_or(false) {
+        test_files.push(path.to_path_buf());
+    }
+}
+
+// A test starts with a comment block of the form:
+// // Result:
+// // <expected stdout line>
+// // ...
+// // Exit: <expected exit code>   (optional, defaults to 0)
+// // Compile only                 (optional, not run with --interpret)
+// // Args: <arguments>            (optional, separated by whitespace)
+// // Env: <NAME>=<value>          (optional, once per variable)
+// The optional lines follow the expected output in any order. A test of a
+// program that does not compile instead starts with the line:
+// // Compile error: <part of the expected error message>
+fn parse_expectation(code: &str) -> Option<Expectation> {
+    let mut lines = code.lines().peekable();
+    let first_line = lines.next()?;
+    if let Some(message) = first_line.strip_prefix(COMPILE_ERROR_HEADER) {
+        return Some(Expectation {
+            compile_error: Some(message.trim().to_string()),
+            stdout: Vec::new(),
+            exit_code: 0,
+            compile_only: false,
+            args: Vec::new(),
+            env: Vec::new(),
+        });
+    }
+    if first_line != RESULT_HEADER {
+        return None;
+    }
+    let is_option = |line: &str| {
+        line.starts_with(EXIT_HEADER)
+            || line == COMPILE_ONLY_HEADER
+            || line.starts_with(ARGS_HEADER)
+            || line.starts_with(ENV_HEADER)
+    };
+    let mut stdout = Vec::new();
+    while let Some(expected_line) = lines
+        .next_if(|line| line.starts_with(RESULT_PREFIX) && !is_option(line))
+        .and_then(|line| line.strip_prefix(RESULT_PREFIX))
+    {
+        stdout.push(expected_line.to_string());
+    }
+    let mut expectation = Expectation {
+        compile_error: None,
+        stdout,
+        exit_code: 0,
+        compile_only: false,
+        args: Vec::new(),
+        env: Vec::new(),
+    };
+    for line in lines.take_while(|line| is_option(line)) {
+        if let Some(code) = line.strip_prefix(EXIT_HEADER) {
+            expectation.exit_code = code
+                .trim()
+                .parse()
+                .unwrap_or_else(|_| panic!("Invalid exit code annotation: {}", line));
+        } else if let Some(args) = line.strip_prefix(ARGS_HEADER) {
+            expectation
+                .args
+                .extend(args.split_whitespace().map(str::to_string));
+        } else if let Some(variable) = line.strip_prefix(ENV_HEADER) {
+            let (name, value) = variable
+                .trim()
+                .split_once('=')
+                .unwrap_or_else(|| panic!("Invalid environment annotation: {}", line));
+            expectation.env.push((name.to_string(), value.to_string()));
+        } else {
+            expectation.compile_only = true;
+        }
+    }
+    Some(expectation)
+}
+
+// The timeout applies to compiling and running the test separately
+fn run_test_case(case: &TestCase, executable: &Path, timeout: Duration) -> Outcome {
+    let temp_dir = executable.with_extension("tmp");
+    fs::create_dir_all(&temp_dir).expect("Could not create temporary test folder");
+    let timed_out = |step: &str| {
+        Outcome::Failed(format!(
+            "{} timed out after {} seconds",
+            step,
+            timeout.as_secs()
+        ))
+    };
+    let compiler = std::env::current_exe().expect("Could not get executable path");
+    let run_output = if case.interpret {
+        let mut command = Command::new(compiler);
+        command
+            .arg("interpret")
+            .arg(case.backend.flag())
+            .arg(&case.path)
+            .arg("--")
+            .args(&case.expectation.args)
+            .envs(case.expectation.env.iter().cloned())
+            .env(TEMP_DIR_VARIABLE, &temp_dir);
+        let run_output = output_with_timeout(&mut command, timeout);
+        let _ = fs::remove_dir_all(&temp_dir);
+        match (run_output, &case.expectation.compile_error) {
+            (Some(output), Some(message)) => return compile_error_outcome(&output, message),
+            (Some(output), None) => output,
+            (None, _) => return timed_out("Interpretation"),
+        }
+    } else {
+        let mut command = Command::new(compiler);
+        command
+            .arg("build")
+            .arg(case.backend.flag())
+            .arg(&case.path)
+            .arg("-o")
+            .arg(executable);
+        let compile_output = match output_with_timeout(&mut command, timeout) {
+            Some(output) => output,
+            None => return timed_out("Compilation"),
+        };
+        if let Some(message) = &case.expectation.compile_error {
+            return compile_error_outcome(&compile_output, message);
+        }
+        if !compile_output.status.success() {
+            return Outcome::Failed(format!(
+                "Compilation failed:\n{}",
+                String::from_utf8_lossy(&compile_output.stderr)
+            ));
+        }
+
+        let mut command = Command::new(executable);
+        command
+            .args(&case.expectation.args)
+            .envs(case.expectation.env.iter().cloned())
+            .env(TEMP_DIR_VARIABLE, &temp_dir);
+        let run_output = output_with_timeout(&mut command, timeout);
+        let _ = fs::remove_file(executable);
+        let _ = fs::remove_dir_all(&temp_dir);
+        match run_output {
+            Some(output) => output,
+            None => return timed_out("Run"),
+        }
+    };
+    let stdout = String::from_utf8_lossy(&run_output.stdout);
+    let stdout_lines = stdout.lines().map(|l| l.to_string()).collect::<Vec<_>>();
+
+    let mut errors = Vec::new();
+    if stdout_lines != case.expectation.stdout {
+        errors.push(format!(
+            "Expected stdout:\n{:?}\nGot:\n{:?}",
+            case.expectation.stdout, stdout_lines
+        ));
+    }
+    match run_output.status.code() {
+        Some(code) if code == case.expectation.exit_code => (),
+        code => errors.push(format!(
+            "Expected exit code {}, got {}",
+            case.expectation.exit_code,
+            code.map(|c| c.to_string())
+                .unwrap_or_else(|| "none (terminated by signal)".to_string())
+        )),
+    }
+    if errors.is_empty() {
+        Outcome::Passed
+    } else {
+        let stderr = String::from_utf8_lossy(&run_output.stderr);
+        if !stderr.is_empty() {
+            errors.push(format!("StdErr:\n{}", stderr));
+        }
+        Outcome::Failed(errors.join("\n"))
+    }
+}
+
+// Whether the compiler or interpreter failed with the expected message
+fn compile_error_outcome(output: &Output, message: &str) -> Outcome {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if output.status.success() {
+        Outcome::Failed(format!("Expected compilation to fail with:\n{}", message))
+    } else if !stderr.contains(message) {
+        Outcome::Failed(format!(
+            "Expected compilation to fail with:\n{}\nGot:\n{}",
+            message, stderr
+        ))
+    } else {
+        Outcome::Passed
+    }
+}
+
+// Runs the command like Command::output, but kills it and returns None if it
+// does not finish within the timeout
+fn output_with_timeout(command: &mut Command, timeout: Duration) -> Option<Output> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap_or_else(|_| panic!("Failed to execute {:?}", command.get_program()));
+    // The pipes are read while waiting, so that the child does not block on a
+    // full pipe
+    let read = |mut pipe: Box<dyn Read + Send>| {
+        thread::spawn(move || {
+            let mut bytes = Vec::new();
+            let _ = pipe.read_to_end(&mut bytes);
+            bytes
+        })
+    };
+    let stdout = read(Box::new(child.stdout.take().unwrap()));
+    let stderr = read(Box::new(child.stderr.take().unwrap()));
+    let start = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait().expect("Failed to wait for test") {
+            return Some(Output {
+                status,
+                stdout: stdout.join().unwrap(),
+                stderr: stderr.join().unwrap(),
+            });
+        }
+        if start.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            return None;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+fn report_outcome(case: &TestCase, outcome: &Outcome) {
+    let mode = if case.interpret { "interpret " } else { "" };
+    match outcome {
+        Outcome::Passed => println!(
+            "PASSED {} [{}{}]",
+            case.path.to_str().unwrap(),
+            mode,
+            case.backend.name()
+        ),
+        Outcome::Failed(reason) => println!(
+            "FAILED {} [{}{}]\n{}\n",
+            case.path.to_str().unwrap(),
+            mode,
+            case.backend.name(),
+            reason
+        ),
+    }
+}
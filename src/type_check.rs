@@ -1,15 +1,153 @@
+mod derive;
+mod inference;
+mod traits;
+mod visibility;
+
 use std::collections::HashMap;
 use std::rc::Rc;
 
+use self::inference::{EmptyArrays, Signature};
 use crate::binary::*;
-use crate::codegen::build_in::get_build_in_signature;
+use crate::codegen::build_in::{get_build_in_signature, is_reserved_symbol, BuildIn};
+use crate::error::FunctionLocation;
 use crate::module::*;
+use crate::parser;
 use crate::types::*;
 
+// Name of the variable holding the matched object in desugared match arms
+const MATCH_OBJ: &str = "$match_obj$";
+const UPDATE_OBJ: &str = "$update_obj$";
+// Name of the function that builds the array of command line arguments for
+// main. It can not clash with user defined functions, as identifiers can not
+// contain $.
+const MAIN_ARGS: &str = "$main_args";
+
+// An error of the type check, with the function whose body it was found in
+#[derive(Clone)]
+pub struct TypeError {
+    pub message: String,
+    pub function: Option<FunctionLocation>,
+}
+
+impl From<String> for TypeError {
+    fn from(message: String) -> Self {
+        TypeError {
+            message,
+            function: None,
+        }
+    }
+}
+
+impl TypeError {
+    fn in_function(
+        message: String,
+        module_id: &Rc<String>,
+        module: &Module,
+        name: &str,
+        location: usize,
+    ) -> Self {
+        TypeError {
+            message,
+            function: Some(function_location(module_id, module, name, location)),
+        }
+    }
+}
+
+impl From<&str> for TypeError {
+    fn from(message: &str) -> Self {
+        TypeError::from(message.to_string())
+    }
+}
+
+// The location of the function name in the module, for errors in the function
+fn function_location(
+    module_id: &Rc<String>,
+    module: &Module,
+    name: &str,
+    location: usize,
+) -> FunctionLocation {
+    FunctionLocation {
+        module_id: Rc::clone(module_id),
+        file: Rc::clone(&module.file),
+        name: name.to_string(),
+        location,
+    }
+}
+
+// The function being checked, which panics report with their line
+struct Caller<'a> {
+    file: &'a str,
+    name: &'a str,
+}
+
+// What the body of a function is checked in: its module and the ids and
+// types of all functions and types
+struct TypeCheckContext<'a, 'input> {
+    module_id: &'a Rc<String>,
+    imports: &'a HashMap<&'a str, Rc<String>>,
+    function_ids:
+        &'a HashMap<&'a Rc<String>, HashMap<&'input str, HashMap<Vec<Rc<String>>, Rc<String>>>>,
+    trait_methods: &'a HashMap<&'input str, HashMap<Vec<Rc<String>>, Rc<String>>>,
+    function_ret_types: &'a HashMap<Rc<String>, Rc<String>>,
+    type_ids: &'a HashMap<&'a Rc<String>, HashMap<&'input str, Rc<String>>>,
+    type_defs: &'a HashMap<Rc<String>, BinType<'input>>,
+    empty_arrays: &'a EmptyArrays<'input>,
+    caller: Caller<'a>,
+}
+
 pub fn type_check<'input>(
     modules: &'input HashMap<Rc<String>, Module<'input>>,
     main_module_id: &Rc<String>,
-) -> Result<Binary<'input>, String> {
+) -> Result<Binary<'input>, TypeError> {
+    let binary = type_check_definitions(modules)?;
+
+    let main_module = modules
+        .get(main_module_id)
+        .ok_or("Could not find main module")?;
+    let main_functions = main_module
+        .functions
+        .get("main")
+        .ok_or("Could not find function named main")?;
+    if main_functions.len() > 1 {
+        return Err("Function main can not be overloaded".into());
+    }
+    let main_function = function_id(main_module_id, "main", 0);
+    let main_location = main_functions.values().next().unwrap().location;
+    let inserted_main_functions =
+        type_check_main(binary.functions, &main_function).map_err(|message| {
+            TypeError::in_function(message, main_module_id, main_module, "main", main_location)
+        })?;
+
+    Ok(Binary {
+        functions: inserted_main_functions,
+        types: binary.types,
+        externs: binary.externs,
+    })
+}
+
+// Id of the index-th overload of the function name in the module
+pub fn function_id(module_id: &Rc<String>, name: &str, index: usize) -> Rc<String> {
+    Rc::new(format!("{}{}${}", module_id, name, index))
+}
+
+// The overloads of a function in the order of their definitions, which gives
+// them the index in their function id
+pub fn ordered_overloads<'a, 'input>(
+    functions: &'a HashMap<Vec<Option<IdLoc<'input>>>, Function<'input>>,
+) -> Vec<&'a Function<'input>> {
+    let mut overloads = functions.values().collect::<Vec<_>>();
+    overloads.sort_by_key(|function| function.location);
+    overloads
+}
+
+// Type checks all types and functions of the modules without requiring a main
+// function. The returned binary can not be compiled on its own.
+pub fn type_check_definitions<'input>(
+    modules: &'input HashMap<Rc<String>, Module<'input>>,
+) -> Result<Binary<'input>, TypeError> {
+    // CHECK THAT OTHER MODULES ONLY REFER TO PUBLIC DEFINITIONS:
+    visibility::check_visibility(modules)?;
+
     // GENERATE UNIQUE TYPE NAMES:
     // type_names[module_id][type_name]
     let mut type_names: HashMap<&Rc<String>, HashMap<&str, Rc<String>>> = HashMap::new();
@@ -35,6 +173,8 @@ pub fn type_check<'input>(
                         .map(|case| {
                             Ok(BinTypeCase {
                                 name: case.name,
+                                field_names: case.field_names.clone(),
+                                public: case.public,
                                 fields: case
                                     .fields
                                     .iter()
@@ -55,6 +195,46 @@ pub fn type_check<'input>(
         }
     }
 
+    // RESOLVE TRAIT IMPLEMENTATIONS:
+    // trait_methods[method_name][parameter_types]
+    let build_in_traits =
+        parser::parse(traits::BUILD_IN_TRAITS).expect("Could not parse build in traits");
+    let methods = traits::collect_methods(modules, &build_in_traits, &type_names, &all_types)?;
+    let mut trait_methods: HashMap<&str, HashMap<Vec<Rc<String>>, Rc<String>>> = HashMap::new();
+    for (method_id, method) in methods.iter() {
+        if trait_methods
+            .entry(method.name)
+            .or_default()
+            .insert(method.signature.params.clone(), Rc::clone(method_id))
+            .is_some()
+        {
+            return Err(TypeError::in_function(
+                format!(
+                    "Method {} is implemented twice for parameter types {:?}",
+                    method.name, method.signature.params
+                ),
+                method.module_id,
+                &modules[method.module_id],
+                method.name,
+                method.location,
+            ));
+        }
+    }
+
+    // COLLECT EXTERN FUNCTIONS:
+    // externs[symbol]
+    let externs = collect_externs(modules)?;
+
+    // INFER MISSING PARAMETER AND RETURN TYPES
+    let (signatures, empty_arrays) = inference::infer_signatures(
+        modules,
+        &type_names,
+        &all_types,
+        &methods,
+        &trait_methods,
+        &externs,
+    )?;
+
     // GENERATE UNIQUE FUNCTION NAMES:
     // function_names[module_id][function_name][parameter_types]
     // function_ret_types[function_id]
@@ -67,121 +247,446 @@ pub fn type_check<'input>(
         let mut module_function = HashMap::new();
         for (name, functions) in module.functions.iter() {
             let mut polymorph_functions = HashMap::new();
-            for (index, (signature, f)) in functions.iter().enumerate() {
-                let function_id = Rc::new(format!("{}{}${}", unique_name, name, index));
-                polymorph_functions.insert(
-                    signature
-                        .iter()
-                        .map(|ty| get_unique_type_id(&type_names, &module.imports, unique_name, ty))
-                        .collect::<Result<Vec<_>, String>>()?,
-                    Rc::clone(&function_id),
-                );
-                function_ret_types.insert(
-                    function_id,
-                    get_unique_type_id(&type_names, &module.imports, unique_name, &f.ret_type)?,
-                );
+            for (index, function) in ordered_overloads(functions).into_iter().enumerate() {
+                let function_id = function_id(unique_name, name, index);
+                let signature = &signatures[&function_id];
+                if polymorph_functions
+                    .insert(signature.params.clone(), Rc::clone(&function_id))
+                    .is_some()
+                {
+                    return Err(TypeError::in_function(
+                        format!(
+                            "Function {} is defined twice with parameter types {:?}",
+                            name, signature.params
+                        ),
+                        unique_name,
+                        module,
+                        name,
+                        function.location,
+                    ));
+                }
+                function_ret_types.insert(function_id, Rc::clone(&signature.ret_type));
             }
             module_function.insert(*name, polymorph_functions);
         }
+        // Externs are called by their symbol
+        for (name, extern_ast) in module.externs.iter() {
+            let symbol = Rc::new(name.to_string());
+            let extern_def = &externs[&symbol];
+            if module_function
+                .entry(*name)
+                .or_default()
+                .insert(extern_def.args.clone(), Rc::clone(&symbol))
+                .is_some()
+            {
+                return Err(TypeError::in_function(
+                    format!(
+                        "Function {} is defined twice with parameter types {:?}",
+                        name, extern_def.args
+                    ),
+                    unique_name,
+                    module,
+                    name,
+                    extern_ast.location,
+                ));
+            }
+            function_ret_types.insert(symbol, Rc::clone(&extern_def.ret_type));
+        }
         function_names.insert(unique_name, module_function);
     }
+    for (method_id, method) in methods.iter() {
+        function_ret_types.insert(Rc::clone(method_id), Rc::clone(&method.signature.ret_type));
+    }
 
     let mut checked_functions = HashMap::new();
 
     for (unique_name, module) in modules.iter() {
         for (name, functions) in module.functions.iter() {
-            for (signature, function) in functions.iter() {
-                let sig: Vec<Rc<String>> = signature
-                    .iter()
-                    .map(|ty| get_unique_type_id(&type_names, &module.imports, unique_name, ty))
-                    .collect::<Result<Vec<_>, String>>()?;
-                checked_functions.insert(
-                    Rc::clone(&function_names[unique_name][name][&sig]),
-                    type_check_function(
-                        unique_name,
-                        &module.imports,
-                        &function_names,
-                        &function_ret_types,
-                        &type_names,
-                        &all_types,
-                        function,
-                    )?, // TODO: Improve Error handling by concatenating them
-                );
+            for (index, function) in ordered_overloads(functions).into_iter().enumerate() {
+                let function_id = function_id(unique_name, name, index);
+                let checked_function = type_check_function(
+                    &TypeCheckContext {
+                        module_id: unique_name,
+                        imports: &module.imports,
+                        function_ids: &function_names,
+                        trait_methods: &trait_methods,
+                        function_ret_types: &function_ret_types,
+                        type_ids: &type_names,
+                        type_defs: &all_types,
+                        empty_arrays: &empty_arrays,
+                        caller: Caller {
+                            file: &module.file,
+                            name,
+                        },
+                    },
+                    &function.args,
+                    &function.body,
+                    &signatures[&function_id],
+                )
+                .map_err(|message| {
+                    TypeError::in_function(message, unique_name, module, name, function.location)
+                })?; // TODO: Improve Error handling by concatenating them
+                checked_functions.insert(function_id, checked_function);
             }
         }
     }
+    for (method_id, method) in methods.iter() {
+        let checked_method = match method.body {
+            Some(body) => type_check_function(
+                &TypeCheckContext {
+                    module_id: method.module_id,
+                    imports: &modules[method.module_id].imports,
+                    function_ids: &function_names,
+                    trait_methods: &trait_methods,
+                    function_ret_types: &function_ret_types,
+                    type_ids: &type_names,
+                    type_defs: &all_types,
+                    empty_arrays: &empty_arrays,
+                    caller: Caller {
+                        file: &modules[method.module_id].file,
+                        name: method.name,
+                    },
+                },
+                method.args,
+                body,
+                &method.signature,
+            )
+            .map_err(|message| {
+                TypeError::in_function(
+                    message,
+                    method.module_id,
+                    &modules[method.module_id],
+                    method.name,
+                    method.location,
+                )
+            })?,
+            None => derive::derive_method(method, &all_types, &trait_methods, &function_ret_types)?,
+        };
+        checked_functions.insert(Rc::clone(method_id), checked_method);
+    }
 
-    let main_module = function_names
-        .get(main_module_id)
-        .ok_or("Could not find main module")?;
-    let main_function_name = main_module
-        .get("main")
-        .ok_or("Could not find function named main")?;
-    let main_function = main_function_name
-        .get(&vec![])
-        .ok_or("Could not find main function with no arguments")?;
-    let inserted_main_functions = type_check_main(checked_functions, main_function)?;
+    // ADD THE TUPLE AND ARRAY TYPES OF ALL FIELDS, FUNCTIONS AND EXPRESSIONS
+    let field_types = all_types
+        .values()
+        .flat_map(|t| t.cases.iter().flat_map(|case| case.fields.iter()))
+        .cloned()
+        .collect::<Vec<_>>();
+    for field_type in field_types.iter() {
+        add_structural_type(&mut all_types, field_type);
+    }
+    for function in checked_functions.values() {
+        for arg in function.args.iter() {
+            add_structural_type(&mut all_types, &arg.param_type);
+        }
+        add_structural_type(&mut all_types, &function.ret_type);
+        add_expr_structural_types(&mut all_types, &function.body);
+    }
 
     Ok(Binary {
-        functions: inserted_main_functions,
+        functions: checked_functions,
         types: all_types,
+        externs,
     })
 }
 
+// Externs only pass primitive values, which C functions take without knowing
+// the garbage collector. Modules may declare the same extern if they agree on
+// its signature.
+fn collect_externs<'input>(
+    modules: &'input HashMap<Rc<String>, Module<'input>>,
+) -> Result<HashMap<Rc<String>, BinExtern>, String> {
+    let mut externs: HashMap<Rc<String>, BinExtern> = HashMap::new();
+    for module in modules.values() {
+        for (name, extern_def) in module.externs.iter() {
+            if is_reserved_symbol(name) {
+                return Err(format!(
+                    "Extern function {} has the name of a build in function",
+                    name
+                ));
+            }
+            let extern_type = |type_ref: &IdLoc| match type_ref {
+                IdLoc::Here(type_name)
+                    if [I32_TYPE, U8_TYPE, BOOL_TYPE, STR_TYPE].contains(type_name) =>
+                {
+                    Ok(Rc::new(type_name.to_string()))
+                }
+                _ => Err(format!(
+                    "Extern function {} can not pass values of type {}",
+                    name, type_ref
+                )),
+            };
+            let args = extern_def
+                .args
+                .iter()
+                .map(|arg| extern_type(arg.param_type.as_ref().unwrap()))
+                .collect::<Result<Vec<_>, String>>()?;
+            let ret_type = match &extern_def.ret_type {
+                Some(ret_type) => extern_type(ret_type)?,
+                None => Rc::new(VOID_TYPE.to_string()),
+            };
+            let symbol = Rc::new(name.to_string());
+            match externs.get(&symbol) {
+                Some(other) if other.args != args || other.ret_type != ret_type => {
+                    return Err(format!(
+                        "Extern function {} is declared with different signatures",
+                        name
+                    ))
+                }
+                _ => {
+                    externs.insert(symbol, BinExtern { args, ret_type });
+                }
+            }
+        }
+    }
+    Ok(externs)
+}
+
+// Tuple and array types are defined by their use, as a type with a single
+// case. The case of an array has the element type as its only field.
+fn add_structural_type(types: &mut HashMap<Rc<String>, BinType>, type_id: &Rc<String>) {
+    if types.contains_key(type_id) {
+        return;
+    }
+    if let Some(element) = array_element(type_id) {
+        add_structural_type(types, &element);
+        types.insert(
+            Rc::clone(type_id),
+            BinType {
+                cases: vec![BinTypeCase {
+                    name: ARRAY_CASE,
+                    field_names: vec![None],
+                    fields: vec![element],
+                    public: true,
+                }],
+            },
+        );
+    } else if let Some(fields) = tuple_fields(type_id) {
+        for field in fields.iter() {
+            add_structural_type(types, field);
+        }
+        types.insert(
+            Rc::clone(type_id),
+            BinType {
+                cases: vec![BinTypeCase {
+                    name: TUPLE_CASE,
+                    field_names: vec![None; fields.len()],
+                    fields,
+                    public: true,
+                }],
+            },
+        );
+    }
+}
+
+fn add_expr_structural_types(types: &mut HashMap<Rc<String>, BinType>, expr: &TypedExpr) {
+    add_structural_type(types, &expr.expr_type);
+    match &expr.expr {
+        BinExpr::Const(_) | BinExpr::Var(_) => (),
+        BinExpr::Array(exprs) | BinExpr::FuncCall(_, exprs) | BinExpr::TypeCase(_, _, exprs) => {
+            for e in exprs.iter() {
+                add_expr_structural_types(types, e);
+            }
+        }
+        BinExpr::GetTypeCaseField(e, _, _) | BinExpr::IsCase(e, _) => {
+            add_expr_structural_types(types, e)
+        }
+        BinExpr::If(cond, e1, e2) => {
+            add_expr_structural_types(types, cond);
+            add_expr_structural_types(types, e1);
+            add_expr_structural_types(types, e2);
+        }
+        BinExpr::Let(_, e1, e2) | BinExpr::Seq(e1, e2) => {
+            add_expr_structural_types(types, e1);
+            add_expr_structural_types(types, e2);
+        }
+    }
+}
+
 fn type_check_main<'input>(
     mut functions: HashMap<Rc<String>, BinFunction<'input>>,
     main_function: &Rc<String>,
 ) -> Result<HashMap<Rc<String>, BinFunction<'input>>, String> {
-    // Check if main function returns void:
+    // Check the signature of the main function:
     let main_func = functions
         .get(main_function)
         .ok_or("Could not find main function".to_string())?;
-    if main_func.ret_type.as_str() != VOID_TYPE {
-        return Err("Main function has to return void".to_string());
+    let args_type = array_type_id(&Rc::new(STRING_TYPE.to_string()));
+    let takes_args = match &main_func.args[..] {
+        [] => false,
+        [arg] if arg.param_type == args_type => true,
+        _ => {
+            return Err(
+                "Main function has to take no parameters or the arguments as [string]"
+                    .to_string(),
+            )
+        }
+    };
+    let ret_type = Rc::clone(&main_func.ret_type);
+    if ![VOID_TYPE, I32_TYPE].contains(&ret_type.as_str()) {
+        return Err("Main function has to return void or i32".to_string());
     }
 
     let int_type = Rc::new(I32_TYPE.to_string());
-    // Insert LLVM main function that calls user defined main function
+    let void_type = Rc::new(VOID_TYPE.to_string());
+    let typed = |expr, expr_type: &Rc<String>| TypedExpr {
+        expr,
+        expr_type: Rc::clone(expr_type),
+    };
+    let main_args = if takes_args {
+        functions.insert(Rc::new(MAIN_ARGS.to_string()), main_args_function(&args_type));
+        // The first argument is the name of the program
+        vec![typed(
+            BinExpr::FuncCall(
+                Rc::new(MAIN_ARGS.to_string()),
+                vec![
+                    typed(BinExpr::Array(Vec::new()), &args_type),
+                    typed(BinExpr::Const(Const::I32(1)), &int_type),
+                ],
+            ),
+            &args_type,
+        )]
+    } else {
+        Vec::new()
+    };
+    let main_call = typed(
+        BinExpr::FuncCall(Rc::clone(main_function), main_args),
+        &ret_type,
+    );
+    // The exit code is the result of main or 0
+    let exit_code = if ret_type.as_str() == I32_TYPE {
+        main_call
+    } else {
+        typed(
+            BinExpr::Seq(
+                Box::new(main_call),
+                Box::new(typed(BinExpr::Const(Const::I32(0)), &int_type)),
+            ),
+            &int_type,
+        )
+    };
+    // Insert LLVM main function that saves the command line arguments for
+    // the runtime and calls user defined main function
     let main_calling_func = BinFunction {
-        args: Vec::new(),
+        args: vec![
+            BinParamDef {
+                name: "argc",
+                param_type: Rc::clone(&int_type),
+            },
+            BinParamDef {
+                name: "argv",
+                param_type: Rc::new(VOID_PTR_TYPE.to_string()),
+            },
+        ],
         ret_type: Rc::clone(&int_type),
-        body: TypedExpr {
-            expr: BinExpr::Seq(
-                Box::new(TypedExpr {
-                    expr: BinExpr::FuncCall(Rc::clone(main_function), Vec::new()),
-                    expr_type: Rc::new(VOID_TYPE.to_string()),
-                }),
-                Box::new(TypedExpr {
-                    expr: BinExpr::Const(Const::I32(0)),
-                    expr_type: Rc::clone(&int_type),
-                }),
+        body: typed(
+            BinExpr::Seq(
+                Box::new(typed(
+                    BinExpr::FuncCall(
+                        Rc::new(BuildIn::init_args.as_str().to_string()),
+                        vec![
+                            typed(BinExpr::Var("argc"), &int_type),
+                            typed(BinExpr::Var("argv"), &Rc::new(VOID_PTR_TYPE.to_string())),
+                        ],
+                    ),
+                    &void_type,
+                )),
+                Box::new(exit_code),
             ),
-            expr_type: int_type,
-        },
+            &int_type,
+        ),
     };
     functions.insert(Rc::new("main".to_string()), main_calling_func);
     Ok(functions)
 }
 
+// Appends the command line arguments from index i on to args:
+// fn $main_args(args: [string], i: i32) -> [string] =
+//     if i == arg_count() then args else $main_args(push(args, string(arg_at(i))), i + 1)
+fn main_args_function<'input>(args_type: &Rc<String>) -> BinFunction<'input> {
+    let int_type = Rc::new(I32_TYPE.to_string());
+    let str_type = Rc::new(STR_TYPE.to_string());
+    let typed = |expr, expr_type: &Rc<String>| TypedExpr {
+        expr,
+        expr_type: Rc::clone(expr_type),
+    };
+    let build_in = |name, args: Vec<TypedExpr<'input>>| {
+        let arg_types = args.iter().map(|arg| Rc::clone(&arg.expr_type)).collect();
+        let (func_id, ret_type) = get_build_in_signature(name, &arg_types)
+            .unwrap_or_else(|| panic!("Could not find build in function {}", name));
+        typed(BinExpr::FuncCall(func_id, args), &ret_type)
+    };
+    let linked = |build_in: BuildIn, args, ret_type| {
+        typed(
+            BinExpr::FuncCall(Rc::new(build_in.as_str().to_string()), args),
+            ret_type,
+        )
+    };
+    let arg = linked(
+        BuildIn::arg_at,
+        vec![typed(BinExpr::Var("i"), &int_type)],
+        &str_type,
+    );
+    let pushed = build_in(
+        "push",
+        vec![
+            typed(BinExpr::Var("args"), args_type),
+            build_in("string", vec![arg]),
+        ],
+    );
+    let next = build_in(
+        "add",
+        vec![
+            typed(BinExpr::Var("i"), &int_type),
+            typed(BinExpr::Const(Const::I32(1)), &int_type),
+        ],
+    );
+    let done = build_in(
+        "eq",
+        vec![
+            typed(BinExpr::Var("i"), &int_type),
+            linked(BuildIn::arg_count, Vec::new(), &int_type),
+        ],
+    );
+    BinFunction {
+        args: vec![
+            BinParamDef {
+                name: "args",
+                param_type: Rc::clone(args_type),
+            },
+            BinParamDef {
+                name: "i",
+                param_type: Rc::clone(&int_type),
+            },
+        ],
+        ret_type: Rc::clone(args_type),
+        body: typed(
+            BinExpr::If(
+                Box::new(done),
+                Box::new(typed(BinExpr::Var("args"), args_type)),
+                Box::new(typed(
+                    BinExpr::FuncCall(Rc::new(MAIN_ARGS.to_string()), vec![pushed, next]),
+                    args_type,
+                )),
+            ),
+            args_type,
+        ),
+    }
+}
+
 fn type_check_function<'input>(
-    module_id: &Rc<String>,
-    imports: &HashMap<&str, Rc<String>>,
-    function_ids: &HashMap<&Rc<String>, HashMap<&'input str, HashMap<Vec<Rc<String>>, Rc<String>>>>,
-    function_ret_types: &HashMap<Rc<String>, Rc<String>>,
-    type_ids: &HashMap<&Rc<String>, HashMap<&'input str, Rc<String>>>,
-    type_defs: &HashMap<Rc<String>, BinType>,
-    function: &Function<'input>,
+    tc: &TypeCheckContext<'_, 'input>,
+    params: &[ParamDef<'input>],
+    body: &Expr<'input>,
+    signature: &Signature,
 ) -> Result<BinFunction<'input>, String> {
-    let args = function
-        .args
+    let args = params
         .iter()
-        .map(|arg| {
-            Ok(BinParamDef {
-                name: arg.name,
-                param_type: get_unique_type_id(type_ids, imports, module_id, &arg.param_type)?,
-            })
+        .zip(signature.params.iter())
+        .map(|(arg, param_type)| BinParamDef {
+            name: arg.name,
+            param_type: Rc::clone(param_type),
         })
-        .collect::<Result<Vec<_>, String>>()?;
+        .collect::<Vec<_>>();
     let mut vars: HashMap<&'input str, Rc<String>> = HashMap::new();
     for arg in args.iter() {
         if arg.param_type.as_str() == VOID_TYPE {
@@ -190,17 +695,8 @@ fn type_check_function<'input>(
         vars.insert(arg.name, Rc::clone(&arg.param_type));
     }
 
-    let typed_body = type_check_expr(
-        module_id,
-        imports,
-        function_ids,
-        function_ret_types,
-        type_ids,
-        type_defs,
-        &mut vars,
-        &function.body,
-    )?;
-    let ret_type = get_unique_type_id(type_ids, imports, module_id, &function.ret_type)?;
+    let typed_body = type_check_expr(tc, &mut vars, body)?;
+    let ret_type = Rc::clone(&signature.ret_type);
     let checked_body = expect_type(ret_type.as_str(), typed_body)?;
     Ok(BinFunction {
         args,
@@ -210,16 +706,42 @@ fn type_check_function<'input>(
 }
 
 fn type_check_expr<'input>(
-    module_id: &Rc<String>,
-    imports: &HashMap<&str, Rc<String>>,
-    function_ids: &HashMap<&Rc<String>, HashMap<&'input str, HashMap<Vec<Rc<String>>, Rc<String>>>>,
-    function_ret_types: &HashMap<Rc<String>, Rc<String>>,
-    type_ids: &HashMap<&Rc<String>, HashMap<&'input str, Rc<String>>>,
-    type_defs: &HashMap<Rc<String>, BinType>,
+    tc: &TypeCheckContext<'_, 'input>,
     vars: &mut HashMap<&'input str, Rc<String>>,
     expr: &Expr<'input>,
 ) -> Result<TypedExpr<'input>, String> {
     Ok(match expr {
+        Expr::Array(elements) => {
+            let checked_elements = elements
+                .iter()
+                .map(|element| type_check_expr(tc, vars, element))
+                .collect::<Result<Vec<_>, String>>()?;
+            // The elements of an empty array are only known from inference
+            let element_type = match checked_elements
+                .iter()
+                .find(|element| element.expr_type.as_str() != EXIT_TYPE)
+                .or(checked_elements.first())
+            {
+                Some(element) => Rc::clone(&element.expr_type),
+                None => match tc.empty_arrays.get(&(expr as *const Expr)) {
+                    Some(type_id) => array_element(type_id).unwrap(),
+                    None => {
+                        return Err(
+                            "Could not infer the element type of an empty array".to_string()
+                        )
+                    }
+                },
+            };
+            check_array_element(&element_type)?;
+            let checked_elements = checked_elements
+                .into_iter()
+                .map(|element| expect_type(&element_type, element))
+                .collect::<Result<Vec<_>, String>>()?;
+            TypedExpr {
+                expr: BinExpr::Array(checked_elements),
+                expr_type: array_type_id(&element_type),
+            }
+        }
         Expr::Const(Const::U8(i)) => TypedExpr {
             expr: BinExpr::Const(Const::U8(*i)),
             expr_type: Rc::new(U8_TYPE.to_string()),
@@ -240,29 +762,14 @@ fn type_check_expr<'input>(
             expr: BinExpr::Const(Const::Void),
             expr_type: Rc::new(VOID_TYPE.to_string()),
         },
-        Expr::FuncCall(id_loc, args) => type_check_func_call(
-            module_id,
-            imports,
-            function_ids,
-            function_ret_types,
-            type_ids,
-            type_defs,
-            vars,
-            id_loc,
-            args,
-        )?,
+        Expr::FuncCall(id_loc, args) => type_check_func_call(tc, vars, id_loc, args)?,
+        Expr::GetField(obj, name) => {
+            let typed_obj = type_check_expr(tc, vars, obj)?;
+            get_field(tc.module_id, tc.imports, tc.type_defs, typed_obj, name)?
+        }
         Expr::GetTypeCaseField(obj, case, field_index) => {
-            let typed_obj = type_check_expr(
-                module_id,
-                imports,
-                function_ids,
-                function_ret_types,
-                type_ids,
-                type_defs,
-                vars,
-                obj,
-            )?;
-            let case_def = type_defs[&typed_obj.expr_type]
+            let typed_obj = type_check_expr(tc, vars, obj)?;
+            let case_def = tc.type_defs[&typed_obj.expr_type]
                 .cases
                 .iter()
                 .find(|f| f.name == *case)
@@ -282,38 +789,27 @@ fn type_check_expr<'input>(
                 ))?),
             }
         }
+        Expr::GetTupleField(obj, field_index, len) => {
+            let typed_obj = type_check_expr(tc, vars, obj)?;
+            let field_type = match tuple_fields(&typed_obj.expr_type) {
+                Some(fields) if fields.len() == *len => Rc::clone(&fields[*field_index]),
+                _ => {
+                    return Err(format!(
+                        "Can not match type {} to a tuple with {} fields",
+                        typed_obj.expr_type, len
+                    ))
+                }
+            };
+            TypedExpr {
+                expr: BinExpr::GetTypeCaseField(Box::new(typed_obj), TUPLE_CASE, *field_index),
+                expr_type: field_type,
+            }
+        }
         Expr::If(cond, then_expr, else_expr) => {
-            let typed_cond = type_check_expr(
-                module_id,
-                imports,
-                function_ids,
-                function_ret_types,
-                type_ids,
-                type_defs,
-                vars,
-                cond,
-            )?;
+            let typed_cond = type_check_expr(tc, vars, cond)?;
             let checked_cond = expect_type(BOOL_TYPE, typed_cond)?;
-            let typed_then = type_check_expr(
-                module_id,
-                imports,
-                function_ids,
-                function_ret_types,
-                type_ids,
-                type_defs,
-                vars,
-                then_expr,
-            )?;
-            let typed_else = type_check_expr(
-                module_id,
-                imports,
-                function_ids,
-                function_ret_types,
-                type_ids,
-                type_defs,
-                vars,
-                else_expr,
-            )?;
+            let typed_then = type_check_expr(tc, vars, then_expr)?;
+            let typed_else = type_check_expr(tc, vars, else_expr)?;
             let checked_else = expect_type(typed_then.expr_type.as_str(), typed_else)?;
             let ret_type = if typed_then.expr_type.as_str() == EXIT_TYPE {
                 Rc::clone(&checked_else.expr_type)
@@ -330,29 +826,23 @@ fn type_check_expr<'input>(
             }
         }
         Expr::IsTypeCase(ty_case, ty, case) => {
-            let typed_ty_case = type_check_expr(
-                module_id,
-                imports,
-                function_ids,
-                function_ret_types,
-                type_ids,
-                type_defs,
-                vars,
-                ty_case,
-            )?;
+            let typed_ty_case = type_check_expr(tc, vars, ty_case)?;
             let type_id = match ty {
-                IdLoc::Here(typ_name) => type_ids[module_id]
+                IdLoc::Here(typ_name) => tc.type_ids[tc.module_id]
                     .get(typ_name)
                     .ok_or(format!("Type {} not found", typ_name))?,
-                IdLoc::Other(mod_name, typ_name) => match imports.get(mod_name) {
-                    Some(other_mod) => type_ids[other_mod]
+                IdLoc::Other(mod_name, typ_name) => match tc.imports.get(mod_name) {
+                    Some(other_mod) => tc.type_ids[other_mod]
                         .get(typ_name)
                         .ok_or(format!("Type {} not found", typ_name))?,
                     None => Err(format!("Unresolved import: {}", mod_name))?,
                 },
+                IdLoc::Tuple(_) | IdLoc::Array(_) => {
+                    return Err(format!("Type {} has no case {}", ty, case))
+                }
             };
             if typed_ty_case.expr_type.as_str() == type_id.as_str() {
-                if type_defs[type_id]
+                if tc.type_defs[type_id]
                     .cases
                     .iter()
                     .find(|c| c.name == *case)
@@ -373,69 +863,126 @@ fn type_check_expr<'input>(
                 ));
             }
         }
-        Expr::Let(name, definition, body) => type_check_let(
-            module_id,
-            imports,
-            function_ids,
-            function_ret_types,
-            type_ids,
-            type_defs,
-            vars,
-            name,
-            definition.as_ref(),
-            body.as_ref(),
-        )?,
-        Expr::Match(obj, match_arms) => type_check_match(
-            module_id,
-            imports,
-            function_ids,
-            function_ret_types,
-            type_ids,
-            type_defs,
-            vars,
-            obj,
-            match_arms,
-        )?,
+        Expr::Let(name, definition, body) => {
+            type_check_let(tc, vars, name, definition.as_ref(), body.as_ref())?
+        }
+        Expr::LetPattern(pattern, definition, body) => {
+            check_pattern_vars(pattern, &mut Vec::new())?;
+            let typed_def = type_check_expr(tc, vars, definition)?;
+            if !is_irrefutable(
+                tc.module_id,
+                tc.imports,
+                tc.type_ids,
+                tc.type_defs,
+                &typed_def.expr_type,
+                pattern,
+            )? {
+                return Err(format!(
+                    "The pattern of let does not match every value of type {}, \
+                    please add an else branch",
+                    written_type(tc.module_id, tc.imports, &typed_def.expr_type)
+                ));
+            }
+            // The pattern always matches, so its condition is left out
+            let (_, body) = build_pattern_lets(pattern, body);
+            let old_type = vars.insert(MATCH_OBJ, Rc::clone(&typed_def.expr_type));
+            let typed_body = type_check_expr(tc, vars, &body)?;
+            match old_type {
+                None => vars.remove(MATCH_OBJ),
+                Some(t) => vars.insert(MATCH_OBJ, t),
+            };
+            let ret_type = Rc::clone(&typed_body.expr_type);
+            TypedExpr {
+                expr: BinExpr::Let(MATCH_OBJ, Box::new(typed_def), Box::new(typed_body)),
+                expr_type: ret_type,
+            }
+        }
+        Expr::Match(line, obj, match_arms) => type_check_match(tc, vars, *line, obj, match_arms)?,
+        Expr::Panic(line, function, message) => {
+            let typed_message = type_check_expr(tc, vars, message)?;
+            let build_in = match typed_message.expr_type.as_str() {
+                STR_TYPE => BuildIn::panic_str,
+                STRING_TYPE => BuildIn::panic_string,
+                message_type => {
+                    return Err(format!(
+                        "Function {} expects a message of type str or string, but found {}",
+                        function, message_type
+                    ))
+                }
+            };
+            let kind = if *function == "assert" {
+                "Assertion failed"
+            } else {
+                "Panic"
+            };
+            let location = format!(
+                "{}:{}: {} in function {}: ",
+                tc.caller.file, line, kind, tc.caller.name
+            );
+            TypedExpr {
+                expr: BinExpr::FuncCall(
+                    Rc::new(build_in.as_str().to_string()),
+                    vec![
+                        TypedExpr {
+                            expr: BinExpr::Const(Const::Str(Rc::new(location))),
+                            expr_type: Rc::new(STR_TYPE.to_string()),
+                        },
+                        typed_message,
+                    ],
+                ),
+                expr_type: Rc::new(EXIT_TYPE.to_string()),
+            }
+        }
         Expr::Seq(e1, e2) => {
-            let checked_e1 = type_check_expr(
-                module_id,
-                imports,
-                function_ids,
-                function_ret_types,
-                type_ids,
-                type_defs,
-                vars,
-                e1.as_ref(),
-            )?;
-            let checked_e2 = type_check_expr(
-                module_id,
-                imports,
-                function_ids,
-                function_ret_types,
-                type_ids,
-                type_defs,
-                vars,
-                e2.as_ref(),
-            )?;
+            let checked_e1 = type_check_expr(tc, vars, e1.as_ref())?;
+            let checked_e2 = type_check_expr(tc, vars, e2.as_ref())?;
             let ret_type = Rc::clone(&checked_e2.expr_type);
             TypedExpr {
                 expr: BinExpr::Seq(Box::new(checked_e1), Box::new(checked_e2)),
                 expr_type: ret_type,
             }
         }
+        Expr::Tuple(fields) => {
+            let checked_fields = fields
+                .iter()
+                .map(|field| type_check_expr(tc, vars, field))
+                .collect::<Result<Vec<_>, String>>()?;
+            let field_types = checked_fields
+                .iter()
+                .map(|field| Rc::clone(&field.expr_type))
+                .collect::<Vec<_>>();
+            check_tuple_fields(&field_types)?;
+            let type_id = tuple_type_id(&field_types);
+            TypedExpr {
+                expr: BinExpr::TypeCase(Rc::clone(&type_id), TUPLE_CASE, checked_fields),
+                expr_type: type_id,
+            }
+        }
+        Expr::TypeCase(IdLoc::Here(var), name, args)
+            if args.is_empty() && vars.contains_key(var) =>
+        {
+            let typed_obj = TypedExpr {
+                expr: BinExpr::Var(var),
+                expr_type: Rc::clone(&vars[var]),
+            };
+            get_field(tc.module_id, tc.imports, tc.type_defs, typed_obj, name)?
+        }
         Expr::TypeCase(typ, case, args) => {
             let type_id = match typ {
-                IdLoc::Here(typ_name) => type_ids[module_id]
+                IdLoc::Here(typ_name) => tc.type_ids[tc.module_id]
                     .get(typ_name)
                     .ok_or(format!("Type {} not found", typ_name))?,
-                IdLoc::Other(mod_name, typ_name) => match imports.get(mod_name) {
-                    Some(other_mod) => type_ids[other_mod]
+                IdLoc::Other(mod_name, typ_name) => match tc.imports.get(mod_name) {
+                    Some(other_mod) => tc.type_ids[other_mod]
                         .get(typ_name)
                         .ok_or(format!("Type {} not found", typ_name))?,
                     None => Err(format!("Unresolved import: {}", mod_name))?,
                 },
+                IdLoc::Tuple(_) | IdLoc::Array(_) => {
+                    return Err(format!("Type {} has no case {}", typ, case))
+                }
             };
-            let type_def = &type_defs[type_id];
+            let type_def = &tc.type_defs[type_id];
             let case_def = type_def
                 .cases
                 .iter()
@@ -454,19 +1001,7 @@ fn type_check_expr<'input>(
                 .iter()
                 .zip(case_def.fields.iter())
                 .map(|(arg, arg_def)| {
-                    expect_type(
-                        arg_def.as_str(),
-                        type_check_expr(
-                            module_id,
-                            imports,
-                            function_ids,
-                            function_ret_types,
-                            type_ids,
-                            type_defs,
-                            vars,
-                            arg,
-                        )?,
-                    )
+                    expect_type(arg_def.as_str(), type_check_expr(tc, vars, arg)?)
                 })
                 .collect::<Result<Vec<_>, String>>()?; // TODO: Improve error handling by joining all Error Strings
             let ret_type = Rc::clone(&type_id);
@@ -475,6 +1010,57 @@ fn type_check_expr<'input>(
                 expr_type: ret_type,
             }
         }
+        Expr::UpdateFields(obj, updates) => {
+            let typed_obj = type_check_expr(tc, vars, obj)?;
+            let type_id = Rc::clone(&typed_obj.expr_type);
+            let mut fields = Vec::new();
+            for (name, _) in updates.iter() {
+                let (_, index, _) =
+                    named_field(tc.module_id, tc.imports, tc.type_defs, &type_id, name)?;
+                check_field_access(tc.module_id, tc.imports, tc.type_defs, &type_id, name)?;
+                if fields.contains(&index) {
+                    return Err(format!("Field {} is updated twice", name));
+                }
+                fields.push(index);
+            }
+            // The object is bound to a variable, as its other fields are copied
+            let case_def = &tc.type_defs[&type_id].cases[0];
+            let checked_fields = case_def
+                .fields
+                .iter()
+                .enumerate()
+                .map(
+                    |(index, field_type)| match fields.iter().position(|f| *f == index) {
+                        Some(update) => expect_type(
+                            field_type.as_str(),
+                            type_check_expr(tc, vars, &updates[update].1)?,
+                        ),
+                        None => Ok(TypedExpr {
+                            expr: BinExpr::GetTypeCaseField(
+                                Box::new(TypedExpr {
+                                    expr: BinExpr::Var(UPDATE_OBJ),
+                                    expr_type: Rc::clone(&type_id),
+                                }),
+                                case_def.name,
+                                index,
+                            ),
+                            expr_type: Rc::clone(field_type),
+                        }),
+                    },
+                )
+                .collect::<Result<Vec<_>, String>>()?;
+            TypedExpr {
+                expr: BinExpr::Let(
+                    UPDATE_OBJ,
+                    Box::new(typed_obj),
+                    Box::new(TypedExpr {
+                        expr: BinExpr::TypeCase(Rc::clone(&type_id), case_def.name, checked_fields),
+                        expr_type: Rc::clone(&type_id),
+                    }),
+                ),
+                expr_type: type_id,
+            }
+        }
         Expr::Var(name) => match vars.get(name) {
             Some(var_type) => TypedExpr {
                 expr: BinExpr::Var(name),
@@ -488,30 +1074,14 @@ fn type_check_expr<'input>(
 }
 
 fn type_check_func_call<'input>(
-    module_id: &Rc<String>,
-    imports: &HashMap<&str, Rc<String>>,
-    function_ids: &HashMap<&Rc<String>, HashMap<&'input str, HashMap<Vec<Rc<String>>, Rc<String>>>>,
-    function_ret_types: &HashMap<Rc<String>, Rc<String>>,
-    type_ids: &HashMap<&Rc<String>, HashMap<&'input str, Rc<String>>>,
-    type_defs: &HashMap<Rc<String>, BinType>,
+    tc: &TypeCheckContext<'_, 'input>,
     vars: &mut HashMap<&'input str, Rc<String>>,
     id_loc: &IdLoc<'input>,
     args: &Vec<Rc<Expr<'input>>>,
 ) -> Result<TypedExpr<'input>, String> {
     let type_checked_args = args
         .iter()
-        .map(|arg| {
-            type_check_expr(
-                module_id,
-                imports,
-                function_ids,
-                function_ret_types,
-                type_ids,
-                type_defs,
-                vars,
-                arg.as_ref(),
-            )
-        })
+        .map(|arg| type_check_expr(tc, vars, arg.as_ref()))
         .collect::<Result<Vec<_>, String>>()?; // TODO: Improve error handling by joining all Error Strings
 
     let arg_types = type_checked_args
@@ -523,12 +1093,12 @@ fn type_check_func_call<'input>(
     match id_loc {
         IdLoc::Here(name) => {
             // Find function in own module
-            match function_ids[module_id].get(name) {
+            match tc.function_ids[tc.module_id].get(name) {
                 Some(functions) => {
                     // There are some functions with this name. Check for matching signature
                     match functions.get(&arg_types) {
                         Some(function_id) => {
-                            let ret_type = Rc::clone(&function_ret_types[function_id]);
+                            let ret_type = Rc::clone(&tc.function_ret_types[function_id]);
                             return Ok(TypedExpr {
                                 expr: BinExpr::FuncCall(Rc::clone(&function_id), type_checked_args),
                                 expr_type: ret_type,
@@ -541,11 +1111,26 @@ fn type_check_func_call<'input>(
             }
 
             // If not found search build_in function
-            match get_build_in_signature(&name, &arg_types) {
-                Some((func_call_name, ret_type)) => Ok(TypedExpr {
+            if let Some((func_call_name, ret_type)) = get_build_in_signature(&name, &arg_types) {
+                return Ok(TypedExpr {
                     expr: BinExpr::FuncCall(func_call_name, type_checked_args),
                     expr_type: ret_type,
-                }),
+                });
+            }
+
+            // Otherwise search the trait implementations of all modules
+            match tc
+                .trait_methods
+                .get(name)
+                .and_then(|implementations| implementations.get(&arg_types))
+            {
+                Some(method_id) => {
+                    let ret_type = Rc::clone(&tc.function_ret_types[method_id]);
+                    Ok(TypedExpr {
+                        expr: BinExpr::FuncCall(Rc::clone(method_id), type_checked_args),
+                        expr_type: ret_type,
+                    })
+                }
                 None => Err(format!(
                     "Could not find function {} with signature {:?}",
                     &name, &arg_types
@@ -553,13 +1138,13 @@ fn type_check_func_call<'input>(
             }
         }
         IdLoc::Other(module, name) => {
-            match imports.get(module) {
-                Some(other_module_id) => match function_ids[other_module_id].get(name) {
+            match tc.imports.get(module) {
+                Some(other_module_id) => match tc.function_ids[other_module_id].get(name) {
                     Some(functions) => {
                         // There are some functions with this name. Check for matching signature
                         match functions.get(&arg_types) {
                             Some(function_id) => {
-                                let ret_type = Rc::clone(&function_ret_types[function_id]);
+                                let ret_type = Rc::clone(&tc.function_ret_types[function_id]);
                                 Ok(TypedExpr {
                                     expr: BinExpr::FuncCall(
                                         Rc::clone(function_id),
@@ -582,45 +1167,23 @@ fn type_check_func_call<'input>(
                 None => Err(format!("Unresolved import: {}", module)),
             }
         }
+        IdLoc::Tuple(_) | IdLoc::Array(_) => unreachable!("Functions are called by name"),
     }
 }
 
 fn type_check_let<'input>(
-    module_id: &Rc<String>,
-    imports: &HashMap<&str, Rc<String>>,
-    function_ids: &HashMap<&Rc<String>, HashMap<&'input str, HashMap<Vec<Rc<String>>, Rc<String>>>>,
-    function_ret_types: &HashMap<Rc<String>, Rc<String>>,
-    type_ids: &HashMap<&Rc<String>, HashMap<&'input str, Rc<String>>>,
-    type_defs: &HashMap<Rc<String>, BinType>,
+    tc: &TypeCheckContext<'_, 'input>,
     vars: &mut HashMap<&'input str, Rc<String>>,
     name: &'input str,
     definition: &Expr<'input>,
     body: &Expr<'input>,
 ) -> Result<TypedExpr<'input>, String> {
-    let typed_def = type_check_expr(
-        module_id,
-        imports,
-        function_ids,
-        function_ret_types,
-        type_ids,
-        type_defs,
-        vars,
-        definition,
-    )?;
+    let typed_def = type_check_expr(tc, vars, definition)?;
     if typed_def.expr_type.as_str() == VOID_TYPE {
         return Err("Variables of type void are not allowed".to_string());
     }
     let old_type = vars.insert(name, Rc::clone(&typed_def.expr_type));
-    let typed_body = type_check_expr(
-        module_id,
-        imports,
-        function_ids,
-        function_ret_types,
-        type_ids,
-        type_defs,
-        vars,
-        body,
-    )?;
+    let typed_body = type_check_expr(tc, vars, body)?;
     let ret_type = Rc::clone(&typed_body.expr_type);
     match old_type {
         None => vars.remove(&name),
@@ -633,59 +1196,136 @@ fn type_check_let<'input>(
 }
 
 fn type_check_match<'input>(
-    module_id: &Rc<String>,
-    imports: &HashMap<&str, Rc<String>>,
-    function_ids: &HashMap<&Rc<String>, HashMap<&'input str, HashMap<Vec<Rc<String>>, Rc<String>>>>,
-    function_ret_types: &HashMap<Rc<String>, Rc<String>>,
-    type_ids: &HashMap<&Rc<String>, HashMap<&'input str, Rc<String>>>,
-    type_defs: &HashMap<Rc<String>, BinType>,
+    tc: &TypeCheckContext<'_, 'input>,
     vars: &mut HashMap<&'input str, Rc<String>>,
+    line: usize,
     obj: &Box<Expr<'input>>,
     match_arms: &Vec<(MatchPattern<'input>, Rc<Expr<'input>>)>,
 ) -> Result<TypedExpr<'input>, String> {
+    for (pattern, _) in match_arms.iter() {
+        check_pattern_vars(pattern, &mut Vec::new())?;
+    }
     type_check_let(
-        module_id,
-        imports,
-        function_ids,
-        function_ret_types,
-        type_ids,
-        type_defs,
+        tc,
         vars,
-        "$match_obj$",
+        MATCH_OBJ,
         obj.as_ref(),
-        &build_ifs_from_arms(match_arms),
+        &build_ifs_from_arms(line, match_arms),
     )
 }
 
+// Values that no arm matches panic with the line of the match
 fn build_ifs_from_arms<'input>(
+    line: usize,
     match_arms: &Vec<(MatchPattern<'input>, Rc<Expr<'input>>)>,
 ) -> Expr<'input> {
-    let failed_expr = Expr::Seq(
-        Box::new(Expr::FuncCall(
-            IdLoc::Here("print"),
-            vec![Rc::new(Expr::Const(Const::Str(Rc::new(
-                "Not exhaustive match\n".to_string(),
-            ))))],
-        )),
-        Box::new(Expr::FuncCall(
-            IdLoc::Here("exit"),
-            vec![Rc::new(Expr::Const(Const::I32(1)))],
-        )),
+    let failed_expr = Expr::Panic(
+        line,
+        "match",
+        Rc::new(Expr::Const(Const::Str(Rc::new(
+            "Not exhaustive match".to_string(),
+        )))),
     );
     let ifs = match_arms.iter().fold(failed_expr, |acc, arm| {
-        let (condition, variable_definitions) =
-            get_condition_vars_pattern(Rc::new(Expr::Var("$match_obj$")), &arm.0);
-        let then_expr = variable_definitions
-            .into_iter()
-            .rev()
-            .fold(Rc::clone(&arm.1), |body, var_def| {
-                Rc::new(Expr::Let(var_def.0, var_def.1, Rc::clone(&body)))
-            });
+        let (condition, then_expr) = build_pattern_lets(&arm.0, &arm.1);
         Expr::If(Box::new(condition), then_expr, Box::new(acc))
     });
     ifs
 }
 
+// Every variable of a pattern needs its own name, as one would shadow the other
+fn check_pattern_vars<'input>(
+    pattern: &MatchPattern<'input>,
+    vars: &mut Vec<&'input str>,
+) -> Result<(), String> {
+    match pattern {
+        MatchPattern::Var(name) if vars.contains(name) => Err(format!(
+            "Variable {} is bound more than once in the same pattern",
+            name
+        )),
+        MatchPattern::Var(name) => {
+            vars.push(name);
+            Ok(())
+        }
+        MatchPattern::Tuple(fields) | MatchPattern::TypeCase(_, _, fields) => fields
+            .iter()
+            .try_for_each(|field| check_pattern_vars(field, vars)),
+        MatchPattern::Literal(_) | MatchPattern::Wildcard => Ok(()),
+    }
+}
+
+// The condition of the pattern on the match object, and the body inside the
+// definitions of the variables of the pattern
+fn build_pattern_lets<'input>(
+    pattern: &MatchPattern<'input>,
+    body: &Rc<Expr<'input>>,
+) -> (Expr<'input>, Rc<Expr<'input>>) {
+    let (condition, variable_definitions) =
+        get_condition_vars_pattern(Rc::new(Expr::Var(MATCH_OBJ)), pattern);
+    let body = variable_definitions
+        .into_iter()
+        .rev()
+        .fold(Rc::clone(body), |body, var_def| {
+            Rc::new(Expr::Let(var_def.0, var_def.1, Rc::clone(&body)))
+        });
+    (condition, body)
+}
+
+// Whether the pattern matches every value of type_id. Patterns that can not
+// match any value of the type are an error.
+fn is_irrefutable(
+    module_id: &Rc<String>,
+    imports: &HashMap<&str, Rc<String>>,
+    type_ids: &HashMap<&Rc<String>, HashMap<&str, Rc<String>>>,
+    type_defs: &HashMap<Rc<String>, BinType>,
+    type_id: &Rc<String>,
+    pattern: &MatchPattern,
+) -> Result<bool, String> {
+    let (field_types, fields, single_case) = match pattern {
+        MatchPattern::Literal(_) => return Ok(false),
+        MatchPattern::Var(_) | MatchPattern::Wildcard => return Ok(true),
+        MatchPattern::Tuple(fields) => match tuple_fields(type_id) {
+            Some(field_types) if field_types.len() == fields.len() => (field_types, fields, true),
+            _ => {
+                return Err(format!(
+                    "Can not match type {} to a tuple with {} fields",
+                    type_id,
+                    fields.len()
+                ))
+            }
+        },
+        MatchPattern::TypeCase(ty, case, fields) => {
+            let pattern_type = get_unique_type_id(type_ids, imports, module_id, ty)?;
+            if pattern_type != *type_id {
+                return Err(format!(
+                    "Can not match type {} to type {}",
+                    pattern_type, type_id
+                ));
+            }
+            let cases = &type_defs[type_id].cases;
+            let case_def = cases
+                .iter()
+                .find(|c| c.name == *case)
+                .ok_or(format!("{} is not a case of {}", case, type_id))?;
+            if case_def.fields.len() != fields.len() {
+                return Err(format!(
+                    "{}.{} expects {} fields, got {}",
+                    type_id,
+                    case,
+                    case_def.fields.len(),
+                    fields.len()
+                ));
+            }
+            (case_def.fields.clone(), fields, cases.len() == 1)
+        }
+    };
+    let mut irrefutable = single_case;
+    for (field_type, field) in field_types.iter().zip(fields.iter()) {
+        irrefutable &= is_irrefutable(module_id, imports, type_ids, type_defs, field_type, field)?;
+    }
+    Ok(irrefutable)
+}
+
 fn get_condition_vars_pattern<'input>(
     obj: Rc<Expr<'input>>,
     pattern: &MatchPattern<'input>,
@@ -719,11 +1359,134 @@ fn get_condition_vars_pattern<'input>(
                 )
             },
         ),
+        MatchPattern::Tuple(fields) => fields.iter().enumerate().fold(
+            (Expr::Const(Const::Bool(true)), Vec::new()),
+            |(acc_expr, mut acc_vars), (i, field)| {
+                let (cond, mut vars) = get_condition_vars_pattern(
+                    Rc::new(Expr::GetTupleField(Rc::clone(&obj), i, fields.len())),
+                    field,
+                );
+                acc_vars.append(&mut vars);
+                (
+                    Expr::If(
+                        Box::new(acc_expr),
+                        Rc::new(cond),
+                        Box::new(Expr::Const(Const::Bool(false))),
+                    ),
+                    acc_vars,
+                )
+            },
+        ),
         MatchPattern::Var(name) => (Expr::Const(Const::Bool(true)), vec![(*name, obj)]),
         MatchPattern::Wildcard => (Expr::Const(Const::Bool(true)), Vec::new()),
     }
 }
 
+// The case, index and type of a named field. Only fields of types with a
+// single case can be accessed by their name.
+pub fn named_field<'input>(
+    module_id: &Rc<String>,
+    imports: &HashMap<&str, Rc<String>>,
+    type_defs: &HashMap<Rc<String>, BinType<'input>>,
+    type_id: &Rc<String>,
+    name: &str,
+) -> Result<(&'input str, usize, Rc<String>), String> {
+    let no_field = || {
+        format!(
+            "Type {} has no field {}",
+            written_type(module_id, imports, type_id),
+            name
+        )
+    };
+    let case = match type_defs
+        .get(type_id)
+        .map(|type_def| type_def.cases.as_slice())
+    {
+        Some([case]) => case,
+        Some(_) => {
+            return Err(format!(
+                "Type {} has more than one case, its field {} can only be matched",
+                written_type(module_id, imports, type_id),
+                name
+            ))
+        }
+        None => return Err(no_field()),
+    };
+    let index = case
+        .field_names
+        .iter()
+        .position(|field_name| *field_name == Some(name))
+        .ok_or_else(no_field)?;
+    Ok((case.name, index, Rc::clone(&case.fields[index])))
+}
+
+fn get_field<'input>(
+    module_id: &Rc<String>,
+    imports: &HashMap<&str, Rc<String>>,
+    type_defs: &HashMap<Rc<String>, BinType<'input>>,
+    typed_obj: TypedExpr<'input>,
+    name: &str,
+) -> Result<TypedExpr<'input>, String> {
+    let (case, index, field_type) =
+        named_field(module_id, imports, type_defs, &typed_obj.expr_type, name)?;
+    check_field_access(module_id, imports, type_defs, &typed_obj.expr_type, name)?;
+    Ok(TypedExpr {
+        expr: BinExpr::GetTypeCaseField(Box::new(typed_obj), case, index),
+        expr_type: field_type,
+    })
+}
+
+// Named fields of types of other modules can only be accessed if their case is
+// public, so that abstract types keep their representation to themselves
+fn check_field_access(
+    module_id: &Rc<String>,
+    imports: &HashMap<&str, Rc<String>>,
+    type_defs: &HashMap<Rc<String>, BinType>,
+    type_id: &Rc<String>,
+    name: &str,
+) -> Result<(), String> {
+    if type_id.starts_with(module_id.as_str())
+        || type_defs[type_id].cases.iter().all(|case| case.public)
+    {
+        Ok(())
+    } else {
+        Err(format!(
+            "Field {} of type {} is private",
+            name,
+            written_type(module_id, imports, type_id)
+        ))
+    }
+}
+
+// A type id as it is written in the module, like Geometry::Pos for a type of
+// the imported module Geometry, for error messages
+pub fn written_type(
+    module_id: &Rc<String>,
+    imports: &HashMap<&str, Rc<String>>,
+    type_id: &str,
+) -> String {
+    if let Some(element) = array_element(type_id) {
+        return format!("[{}]", written_type(module_id, imports, &element));
+    }
+    if let Some(fields) = tuple_fields(type_id) {
+        let fields = fields
+            .iter()
+            .map(|field| written_type(module_id, imports, field))
+            .collect::<Vec<_>>();
+        return format!("({})", fields.join(", "));
+    }
+    let name = crate::interpreter::type_name(type_id);
+    if type_id.starts_with(module_id.as_str()) {
+        return name;
+    }
+    imports
+        .iter()
+        .filter(|(_, other_id)| type_id.starts_with(other_id.as_str()))
+        .map(|(module, _)| format!("{}::{}", module, name))
+        .min()
+        .unwrap_or(name)
+}
+
 fn get_unique_type_id(
     type_ids: &HashMap<&Rc<String>, HashMap<&str, Rc<String>>>,
     imports: &HashMap<&str, Rc<String>>,
@@ -734,7 +1497,10 @@ fn get_unique_type_id(
         IdLoc::Here(type_name) => Ok(type_ids[module_id]
             .get(type_name)
             .map(|tn| Rc::clone(tn))
-            .unwrap_or(Rc::new(type_name.to_string()))),
+            .unwrap_or_else(|| match *type_name {
+                "string" => Rc::new(STRING_TYPE.to_string()),
+                _ => Rc::new(type_name.to_string()),
+            })),
         IdLoc::Other(module_name, type_name) => {
             let module_id = imports
                 .get(module_name)
@@ -744,6 +1510,37 @@ fn get_unique_type_id(
                 .ok_or(format!("Could not find type {}", type_name))?;
             Ok(Rc::clone(type_id))
         }
+        IdLoc::Tuple(fields) => {
+            let fields = fields
+                .iter()
+                .map(|field| get_unique_type_id(type_ids, imports, module_id, field))
+                .collect::<Result<Vec<_>, String>>()?;
+            check_tuple_fields(&fields)?;
+            Ok(tuple_type_id(&fields))
+        }
+        IdLoc::Array(element) => {
+            let element = get_unique_type_id(type_ids, imports, module_id, element)?;
+            check_array_element(&element)?;
+            Ok(array_type_id(&element))
+        }
+    }
+}
+
+fn check_tuple_fields(fields: &[Rc<String>]) -> Result<(), String> {
+    match fields
+        .iter()
+        .find(|field| [VOID_TYPE, EXIT_TYPE].contains(&field.as_str()))
+    {
+        Some(field) => Err(format!("Tuple fields of type {} are not allowed", field)),
+        None => Ok(()),
+    }
+}
+
+fn check_array_element(element: &str) -> Result<(), String> {
+    if [VOID_TYPE, EXIT_TYPE].contains(&element) {
+        Err(format!("Array elements of type {} are not allowed", element))
+    } else {
+        Ok(())
     }
 }
 
@@ -0,0 +1,256 @@
+// Generates the methods of derived traits from the cases of the type. Eq
+// compares the cases and then their fields, Ord orders values by the order of
+// their cases and then by their fields from left to right, and Show prints
+// values like I32.Cons(1, I32.Nil).
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::traits::Method;
+use crate::binary::*;
+use crate::codegen::build_in::get_build_in_signature;
+use crate::interpreter::type_name;
+use crate::module::Const;
+use crate::types::*;
+
+struct Deriver<'a, 'input> {
+    trait_name: &'a str,
+    type_id: &'a Rc<String>,
+    type_defs: &'a HashMap<Rc<String>, BinType<'input>>,
+    trait_methods: &'a HashMap<&'input str, HashMap<Vec<Rc<String>>, Rc<String>>>,
+    function_ret_types: &'a HashMap<Rc<String>, Rc<String>>,
+}
+
+pub fn derive_method<'input>(
+    method: &Method<'_, 'input>,
+    type_defs: &HashMap<Rc<String>, BinType<'input>>,
+    trait_methods: &HashMap<&'input str, HashMap<Vec<Rc<String>>, Rc<String>>>,
+    function_ret_types: &HashMap<Rc<String>, Rc<String>>,
+) -> Result<BinFunction<'input>, String> {
+    let args = method
+        .args
+        .iter()
+        .zip(method.signature.params.iter())
+        .map(|(arg, param_type)| BinParamDef {
+            name: arg.name,
+            param_type: Rc::clone(param_type),
+        })
+        .collect::<Vec<_>>();
+    let trait_name = match method.name {
+        "eq" => "Eq",
+        "lt" => "Ord",
+        "print" => "Show",
+        name => unreachable!("Method {} can not be derived", name),
+    };
+    let deriver = Deriver {
+        trait_name,
+        type_id: &method.signature.params[0],
+        type_defs,
+        trait_methods,
+        function_ret_types,
+    };
+    let body = match trait_name {
+        "Eq" => deriver.eq(args[0].name, args[1].name)?,
+        "Ord" => deriver.lt(args[0].name, args[1].name)?,
+        _ => deriver.print(args[0].name)?,
+    };
+    Ok(BinFunction {
+        args,
+        ret_type: Rc::clone(&method.signature.ret_type),
+        body,
+    })
+}
+
+impl<'a, 'input> Deriver<'a, 'input> {
+    fn cases(&self) -> &'a Vec<BinTypeCase<'input>> {
+        &self.type_defs[self.type_id].cases
+    }
+
+    fn var(&self, name: &'input str) -> TypedExpr<'input> {
+        TypedExpr {
+            expr: BinExpr::Var(name),
+            expr_type: Rc::clone(self.type_id),
+        }
+    }
+
+    fn is_case(&self, name: &'input str, case: &'input str) -> TypedExpr<'input> {
+        TypedExpr {
+            expr: BinExpr::IsCase(Box::new(self.var(name)), case),
+            expr_type: Rc::new(BOOL_TYPE.to_string()),
+        }
+    }
+
+    fn field(
+        &self,
+        name: &'input str,
+        case: &BinTypeCase<'input>,
+        index: usize,
+    ) -> TypedExpr<'input> {
+        TypedExpr {
+            expr: BinExpr::GetTypeCaseField(Box::new(self.var(name)), case.name, index),
+            expr_type: Rc::clone(&case.fields[index]),
+        }
+    }
+
+    // Calls the build in function or the trait method name
+    fn call(&self, name: &str, args: Vec<TypedExpr<'input>>) -> Result<TypedExpr<'input>, String> {
+        let arg_types = args
+            .iter()
+            .map(|arg| Rc::clone(&arg.expr_type))
+            .collect::<Vec<_>>();
+        let (function_id, ret_type) = match get_build_in_signature(name, &arg_types) {
+            Some(build_in) => build_in,
+            None => {
+                let method_id = self
+                    .trait_methods
+                    .get(name)
+                    .and_then(|implementations| implementations.get(&arg_types))
+                    .ok_or(format!(
+                        "Can not derive {} for {}, because {} does not implement {}",
+                        self.trait_name,
+                        type_name(self.type_id),
+                        type_name(&arg_types[0]),
+                        self.trait_name
+                    ))?;
+                let ret_type = Rc::clone(&self.function_ret_types[method_id]);
+                (Rc::clone(method_id), ret_type)
+            }
+        };
+        Ok(TypedExpr {
+            expr: BinExpr::FuncCall(function_id, args),
+            expr_type: ret_type,
+        })
+    }
+
+    fn eq(&self, a: &'input str, b: &'input str) -> Result<TypedExpr<'input>, String> {
+        let mut cases = boolean(false);
+        for case in self.cases().iter().rev() {
+            let mut fields = boolean(true);
+            for index in (0..case.fields.len()).rev() {
+                let field_eq = self.call(
+                    "eq",
+                    vec![self.field(a, case, index), self.field(b, case, index)],
+                )?;
+                fields = if_else(field_eq, fields, boolean(false));
+            }
+            let same_case = if_else(self.is_case(b, case.name), fields, boolean(false));
+            cases = if_else(self.is_case(a, case.name), same_case, cases);
+        }
+        Ok(cases)
+    }
+
+    fn lt(&self, a: &'input str, b: &'input str) -> Result<TypedExpr<'input>, String> {
+        let cases = self.cases();
+        let mut result = boolean(false);
+        for (case_index, case) in cases.iter().enumerate().rev() {
+            // Compares the fields from left to right
+            let mut fields = boolean(false);
+            for index in (0..case.fields.len()).rev() {
+                let field_lt = self.call(
+                    "lt",
+                    vec![self.field(a, case, index), self.field(b, case, index)],
+                )?;
+                let field_gt = self.call(
+                    "lt",
+                    vec![self.field(b, case, index), self.field(a, case, index)],
+                )?;
+                fields = if_else(
+                    field_lt,
+                    boolean(true),
+                    if_else(field_gt, boolean(false), fields),
+                );
+            }
+            // Otherwise a is smaller if b has a later case
+            let mut later_case = boolean(false);
+            for later in cases[case_index + 1..].iter().rev() {
+                later_case = if_else(self.is_case(b, later.name), boolean(true), later_case);
+            }
+            let compared = if_else(self.is_case(b, case.name), fields, later_case);
+            result = if_else(self.is_case(a, case.name), compared, result);
+        }
+        Ok(result)
+    }
+
+    fn print(&self, value: &'input str) -> Result<TypedExpr<'input>, String> {
+        let mut cases = constant(Const::Void, VOID_TYPE);
+        for case in self.cases().iter().rev() {
+            let mut prints = vec![print_str(&format!(
+                "{}.{}",
+                type_name(self.type_id),
+                case.name
+            ))];
+            for index in 0..case.fields.len() {
+                prints.push(print_str(if index == 0 { "(" } else { ", " }));
+                prints.push(self.print_field(self.field(value, case, index))?);
+            }
+            if !case.fields.is_empty() {
+                prints.push(print_str(")"));
+            }
+            cases = if_else(self.is_case(value, case.name), sequence(prints), cases);
+        }
+        Ok(cases)
+    }
+
+    fn print_field(&self, field: TypedExpr<'input>) -> Result<TypedExpr<'input>, String> {
+        match field.expr_type.as_str() {
+            BOOL_TYPE => Ok(if_else(field, print_str("true"), print_str("false"))),
+            STR_TYPE | STRING_TYPE => Ok(sequence(vec![
+                print_str("\""),
+                self.call("print", vec![field])?,
+                print_str("\""),
+            ])),
+            _ => self.call("print", vec![field]),
+        }
+    }
+}
+
+fn constant<'input>(value: Const, type_id: &str) -> TypedExpr<'input> {
+    TypedExpr {
+        expr: BinExpr::Const(value),
+        expr_type: Rc::new(type_id.to_string()),
+    }
+}
+
+fn boolean<'input>(value: bool) -> TypedExpr<'input> {
+    constant(Const::Bool(value), BOOL_TYPE)
+}
+
+fn if_else<'input>(
+    condition: TypedExpr<'input>,
+    then_expr: TypedExpr<'input>,
+    else_expr: TypedExpr<'input>,
+) -> TypedExpr<'input> {
+    let expr_type = Rc::clone(&then_expr.expr_type);
+    TypedExpr {
+        expr: BinExpr::If(
+            Box::new(condition),
+            Box::new(then_expr),
+            Box::new(else_expr),
+        ),
+        expr_type,
+    }
+}
+
+fn print_str<'input>(text: &str) -> TypedExpr<'input> {
+    let (function_id, ret_type) =
+        get_build_in_signature("print", &vec![Rc::new(STR_TYPE.to_string())]).unwrap();
+    TypedExpr {
+        expr: BinExpr::FuncCall(
+            function_id,
+            vec![constant(Const::Str(Rc::new(text.to_string())), STR_TYPE)],
+        ),
+        expr_type: ret_type,
+    }
+}
+
+// Evaluates the void expressions one after another
+fn sequence(exprs: Vec<TypedExpr>) -> TypedExpr {
+    exprs
+        .into_iter()
+        .rev()
+        .reduce(|rest, expr| TypedExpr {
+            expr: BinExpr::Seq(Box::new(expr), Box::new(rest)),
+            expr_type: Rc::new(VOID_TYPE.to_string()),
+        })
+        .unwrap()
+}
@@ -0,0 +1,950 @@
+// Infers the parameter and return types that are not annotated. Every
+// function has exactly one type, found by unifying the types of its body and
+// of all its calls in the whole program. Calls of overloaded functions are
+// resolved once the types of their arguments select a single overload.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::traits::Method;
+use super::{
+    build_ifs_from_arms, build_pattern_lets, function_id, function_location, get_unique_type_id,
+    named_field, ordered_overloads, TypeError, MATCH_OBJ,
+};
+use crate::binary::{BinExtern, BinType};
+use crate::codegen::build_in::get_build_in_signature;
+use crate::error::FunctionLocation;
+use crate::module::*;
+use crate::types::*;
+
+pub const PRIMITIVE_TYPES: [&str; 5] = [U8_TYPE, I32_TYPE, BOOL_TYPE, STR_TYPE, STRING_TYPE];
+// Build in functions take at most this many arguments
+const MAX_BUILD_IN_ARGS: usize = 3;
+
+pub struct Signature {
+    pub params: Vec<Rc<String>>,
+    pub ret_type: Rc<String>,
+}
+
+#[derive(Clone)]
+enum Ty {
+    Var(usize),
+    Known(Rc<String>),
+    // A tuple whose field types may not be known yet
+    Tuple(Vec<Ty>),
+    // An array whose element type may not be known yet
+    Array(Box<Ty>),
+}
+
+struct FunctionType {
+    params: Vec<Ty>,
+    ret_type: Ty,
+}
+
+struct Candidate {
+    params: Vec<Ty>,
+    ret_type: Ty,
+}
+
+// A call of an overloaded function, resolved once only one candidate fits
+struct Call {
+    // The function the call is in
+    function: Option<FunctionLocation>,
+    name: String,
+    candidates: Vec<Candidate>,
+    args: Vec<Ty>,
+    ret_type: Ty,
+}
+
+// A field access, resolved once the type of the object is known
+struct FieldAccess<'input> {
+    // The module of the access, for its imports in errors, and the function
+    // it is in
+    module_id: Rc<String>,
+    function: Option<FunctionLocation>,
+    obj: Ty,
+    name: &'input str,
+    field: Ty,
+}
+
+struct Inference<'a, 'input> {
+    modules: &'input HashMap<Rc<String>, Module<'input>>,
+    type_ids: &'a HashMap<&'a Rc<String>, HashMap<&'input str, Rc<String>>>,
+    type_defs: &'a HashMap<Rc<String>, BinType<'input>>,
+    trait_methods: &'a HashMap<&'input str, HashMap<Vec<Rc<String>>, Rc<String>>>,
+    externs: &'a HashMap<Rc<String>, BinExtern>,
+    // The type each type variable is bound to, and the function it was bound
+    // in, which conflicting calls of an inferred function are explained with
+    bindings: Vec<Option<Ty>>,
+    bound_in: Vec<Option<FunctionLocation>>,
+    functions: HashMap<Rc<String>, FunctionType>,
+    calls: Vec<Call>,
+    field_accesses: Vec<FieldAccess<'input>>,
+    // The element types of the empty array literals, which can only be known
+    // from their use, with the function they are in
+    empty_arrays: Vec<(*const Expr<'input>, Ty, Option<FunctionLocation>)>,
+    // The function whose body is inferred, which conflicts are reported in
+    function: Option<FunctionLocation>,
+    // The first conflict found, reported if it keeps types from being inferred
+    conflict: Option<TypeError>,
+}
+
+// The array type id of every empty array literal, by the address of the literal
+pub type EmptyArrays<'input> = HashMap<*const Expr<'input>, Rc<String>>;
+
+// Returns the signature of every function by function id and the type of
+// every empty array literal
+pub fn infer_signatures<'input>(
+    modules: &'input HashMap<Rc<String>, Module<'input>>,
+    type_ids: &HashMap<&Rc<String>, HashMap<&'input str, Rc<String>>>,
+    type_defs: &HashMap<Rc<String>, BinType<'input>>,
+    methods: &HashMap<Rc<String>, Method<'_, 'input>>,
+    trait_methods: &HashMap<&'input str, HashMap<Vec<Rc<String>>, Rc<String>>>,
+    externs: &HashMap<Rc<String>, BinExtern>,
+) -> Result<(HashMap<Rc<String>, Signature>, EmptyArrays<'input>), TypeError> {
+    let mut inference = Inference {
+        modules,
+        type_ids,
+        type_defs,
+        trait_methods,
+        externs,
+        bindings: Vec::new(),
+        bound_in: Vec::new(),
+        functions: HashMap::new(),
+        calls: Vec::new(),
+        field_accesses: Vec::new(),
+        empty_arrays: Vec::new(),
+        function: None,
+        conflict: None,
+    };
+    for (module_id, module) in modules.iter() {
+        for (name, overloads) in module.functions.iter() {
+            for (index, function) in ordered_overloads(overloads).into_iter().enumerate() {
+                let in_function = |message| TypeError {
+                    message,
+                    function: Some(function_location(
+                        module_id,
+                        module,
+                        name,
+                        function.location,
+                    )),
+                };
+                let params = function
+                    .args
+                    .iter()
+                    .map(|arg| inference.annotated_type(module_id, &arg.param_type))
+                    .collect::<Result<Vec<_>, String>>()
+                    .map_err(in_function)?;
+                let ret_type = inference
+                    .annotated_type(module_id, &function.ret_type)
+                    .map_err(in_function)?;
+                inference.functions.insert(
+                    function_id(module_id, name, index),
+                    FunctionType { params, ret_type },
+                );
+            }
+        }
+    }
+    // The types of trait methods are given by the trait
+    for (method_id, method) in methods.iter() {
+        let params = method
+            .signature
+            .params
+            .iter()
+            .map(|param| Ty::Known(Rc::clone(param)))
+            .collect();
+        let ret_type = Ty::Known(Rc::clone(&method.signature.ret_type));
+        inference
+            .functions
+            .insert(Rc::clone(method_id), FunctionType { params, ret_type });
+    }
+
+    // The bodies are inferred in the order of the modules and of the
+    // functions in their files, so that the first use of a function, which
+    // its types are inferred from, is the same every time
+    let mut bodies = Vec::new();
+    for (module_id, module) in modules.iter() {
+        for (name, overloads) in module.functions.iter() {
+            for (index, function) in ordered_overloads(overloads).into_iter().enumerate() {
+                bodies.push((module_id, module, name, index, function));
+            }
+        }
+    }
+    bodies.sort_by_key(|(module_id, _, _, _, function)| (*module_id, function.location));
+    // Body types by function id
+    let mut body_types = HashMap::new();
+    for (module_id, module, name, index, function) in bodies {
+        let function_id = function_id(module_id, name, index);
+        let function_type = &inference.functions[&function_id];
+        let ret_type = function_type.ret_type.clone();
+        let mut vars: HashMap<&str, Ty> = function
+            .args
+            .iter()
+            .map(|arg| arg.name)
+            .zip(function_type.params.iter().cloned())
+            .collect();
+        inference.function = Some(function_location(
+            module_id,
+            module,
+            name,
+            function.location,
+        ));
+        let body_type = inference.infer_expr(module_id, &mut vars, &function.body);
+        inference.unify(&ret_type, &body_type);
+        body_types.insert(function_id, body_type);
+    }
+    // Trait methods only constrain the functions they call
+    for method in methods.values() {
+        let body = match method.body {
+            Some(body) => body,
+            None => continue,
+        };
+        let mut vars: HashMap<&str, Ty> = method
+            .args
+            .iter()
+            .map(|arg| arg.name)
+            .zip(
+                method
+                    .signature
+                    .params
+                    .iter()
+                    .map(|param| Ty::Known(Rc::clone(param))),
+            )
+            .collect();
+        inference.function = Some(function_location(
+            method.module_id,
+            &modules[method.module_id],
+            method.name,
+            method.location,
+        ));
+        let body_type = inference.infer_expr(method.module_id, &mut vars, body);
+        inference.unify(
+            &Ty::Known(Rc::clone(&method.signature.ret_type)),
+            &body_type,
+        );
+    }
+    inference.resolve_calls()?;
+
+    let mut signatures = HashMap::new();
+    for (module_id, module) in modules.iter() {
+        for (name, overloads) in module.functions.iter() {
+            for (index, function) in ordered_overloads(overloads).into_iter().enumerate() {
+                let function_id = function_id(module_id, name, index);
+                let function_type = &inference.functions[&function_id];
+                let in_function = |message| TypeError {
+                    message,
+                    function: Some(function_location(
+                        module_id,
+                        module,
+                        name,
+                        function.location,
+                    )),
+                };
+                let mut params = Vec::new();
+                for (arg, param_type) in function.args.iter().zip(function_type.params.iter()) {
+                    params.push(inference.known(param_type).ok_or_else(|| {
+                        inference.conflict.clone().unwrap_or_else(|| {
+                            in_function(format!(
+                                "Could not infer the type of parameter {} of function {}, \
+                                please add a type annotation",
+                                arg.name, name
+                            ))
+                        })
+                    })?);
+                }
+                let ret_type = match inference.known(&function_type.ret_type) {
+                    Some(ret_type) => ret_type,
+                    // Functions that never return, like ones calling exit
+                    None if inference.is_exit(&body_types[&function_id]) => {
+                        Rc::new(VOID_TYPE.to_string())
+                    }
+                    None => {
+                        return Err(inference.conflict.clone().unwrap_or_else(|| {
+                            in_function(format!(
+                                "Could not infer the return type of function {}, \
+                                please add a type annotation",
+                                name
+                            ))
+                        }))
+                    }
+                };
+                signatures.insert(function_id, Signature { params, ret_type });
+            }
+        }
+    }
+    let mut empty_arrays = HashMap::new();
+    for (expr, element, function) in inference.empty_arrays.iter() {
+        let element = inference.known(element).ok_or_else(|| {
+            inference.conflict.clone().unwrap_or_else(|| TypeError {
+                message: "Could not infer the element type of an empty array, \
+                    please add a type annotation"
+                    .to_string(),
+                function: function.clone(),
+            })
+        })?;
+        empty_arrays.insert(*expr, array_type_id(&element));
+    }
+    Ok((signatures, empty_arrays))
+}
+
+impl<'a, 'input> Inference<'a, 'input> {
+    // Keeps the first conflict, found in the function being inferred
+    fn conflict(&mut self, message: String) {
+        if self.conflict.is_none() {
+            self.conflict = Some(TypeError {
+                message,
+                function: self.function.clone(),
+            });
+        }
+    }
+
+    fn fresh_var(&mut self) -> Ty {
+        self.bindings.push(None);
+        self.bound_in.push(None);
+        Ty::Var(self.bindings.len() - 1)
+    }
+
+    fn known_type(type_id: &str) -> Ty {
+        Ty::Known(Rc::new(type_id.to_string()))
+    }
+
+    fn annotated_type(
+        &mut self,
+        module_id: &Rc<String>,
+        annotation: &Option<IdLoc>,
+    ) -> Result<Ty, String> {
+        Ok(match annotation {
+            Some(ty) => Ty::Known(get_unique_type_id(
+                self.type_ids,
+                &self.modules[module_id].imports,
+                module_id,
+                ty,
+            )?),
+            None => self.fresh_var(),
+        })
+    }
+
+    fn resolve(&self, ty: &Ty) -> Ty {
+        match ty {
+            Ty::Var(var) => match &self.bindings[*var] {
+                Some(bound) => self.resolve(bound),
+                None => ty.clone(),
+            },
+            Ty::Known(_) | Ty::Tuple(_) | Ty::Array(_) => ty.clone(),
+        }
+    }
+
+    // The function a type variable was bound to a type other than a variable
+    // in, None if it is unbound or no variable
+    fn bound_in(&self, ty: &Ty) -> Option<&FunctionLocation> {
+        match ty {
+            Ty::Var(var) => match &self.bindings[*var] {
+                Some(bound @ Ty::Var(_)) => self.bound_in(bound),
+                Some(_) => self.bound_in[*var].as_ref(),
+                None => None,
+            },
+            Ty::Known(_) | Ty::Tuple(_) | Ty::Array(_) => None,
+        }
+    }
+
+    fn known(&self, ty: &Ty) -> Option<Rc<String>> {
+        match self.resolve(ty) {
+            Ty::Known(type_id) => Some(type_id),
+            Ty::Var(_) => None,
+            Ty::Tuple(fields) => fields
+                .iter()
+                .map(|field| self.known(field))
+                .collect::<Option<Vec<_>>>()
+                .map(|fields| tuple_type_id(&fields)),
+            Ty::Array(element) => self.known(&element).map(|element| array_type_id(&element)),
+        }
+    }
+
+    // The field types of a tuple with len fields, None if ty is no such tuple
+    fn tuple_fields(&self, ty: &Ty, len: usize) -> Option<Vec<Ty>> {
+        let fields = match self.resolve(ty) {
+            Ty::Tuple(fields) => fields,
+            Ty::Known(type_id) => tuple_fields(&type_id)?.into_iter().map(Ty::Known).collect(),
+            Ty::Var(_) | Ty::Array(_) => return None,
+        };
+        (fields.len() == len).then_some(fields)
+    }
+
+    // The element type of an array, None if ty is no array
+    fn array_element(&self, ty: &Ty) -> Option<Ty> {
+        match self.resolve(ty) {
+            Ty::Array(element) => Some(*element),
+            Ty::Known(type_id) => array_element(&type_id).map(Ty::Known),
+            Ty::Var(_) | Ty::Tuple(_) => None,
+        }
+    }
+
+    fn is_exit(&self, ty: &Ty) -> bool {
+        self.known(ty)
+            .is_some_and(|type_id| type_id.as_str() == EXIT_TYPE)
+    }
+
+    // Whether the types can be unified, without binding any variable
+    fn fits(&self, ty1: &Ty, ty2: &Ty) -> bool {
+        match (self.resolve(ty1), self.resolve(ty2)) {
+            (Ty::Known(type1), Ty::Known(type2)) => {
+                type1 == type2 || type1.as_str() == EXIT_TYPE || type2.as_str() == EXIT_TYPE
+            }
+            (Ty::Known(type_id), _) | (_, Ty::Known(type_id)) if type_id.as_str() == EXIT_TYPE => {
+                true
+            }
+            (Ty::Tuple(fields), other) | (other, Ty::Tuple(fields)) => {
+                match self.tuple_fields(&other, fields.len()) {
+                    Some(other_fields) => fields
+                        .iter()
+                        .zip(other_fields.iter())
+                        .all(|(field, other_field)| self.fits(field, other_field)),
+                    None => matches!(other, Ty::Var(_)),
+                }
+            }
+            (Ty::Array(element), other) | (other, Ty::Array(element)) => {
+                match self.array_element(&other) {
+                    Some(other_element) => self.fits(&element, &other_element),
+                    None => matches!(other, Ty::Var(_)),
+                }
+            }
+            _ => true,
+        }
+    }
+
+    // The exit type fits every type, so it does not bind variables
+    fn unify(&mut self, ty1: &Ty, ty2: &Ty) {
+        match (self.resolve(ty1), self.resolve(ty2)) {
+            (Ty::Var(var1), Ty::Var(var2)) if var1 == var2 => {}
+            (Ty::Known(type_id), _) | (_, Ty::Known(type_id)) if type_id.as_str() == EXIT_TYPE => {}
+            (Ty::Var(var), ty) | (ty, Ty::Var(var)) => {
+                if !self.occurs(var, &ty) {
+                    self.bindings[var] = Some(ty);
+                    self.bound_in[var] = self.function.clone();
+                } else {
+                    self.conflict("Found a type that contains itself".to_string());
+                }
+            }
+            (Ty::Tuple(fields), other) | (other, Ty::Tuple(fields)) => {
+                match self.tuple_fields(&other, fields.len()) {
+                    Some(other_fields) => {
+                        for (field, other_field) in fields.iter().zip(other_fields.iter()) {
+                            self.unify(field, other_field);
+                        }
+                    }
+                    None => self.conflict(format!(
+                        "Expected {}, but found {}",
+                        self.describe(&Ty::Tuple(fields)),
+                        self.describe(&other)
+                    )),
+                }
+            }
+            (Ty::Array(element), other) | (other, Ty::Array(element)) => {
+                match self.array_element(&other) {
+                    Some(other_element) => self.unify(&element, &other_element),
+                    None => self.conflict(format!(
+                        "Expected {}, but found {}",
+                        self.describe(&Ty::Array(element)),
+                        self.describe(&other)
+                    )),
+                }
+            }
+            (Ty::Known(type1), Ty::Known(type2)) => {
+                if type1 != type2 {
+                    self.conflict(format!("Expected {}, but found {}", type1, type2));
+                }
+            }
+        }
+    }
+
+    // Whether binding var to ty would make a type that contains itself
+    fn occurs(&self, var: usize, ty: &Ty) -> bool {
+        match self.resolve(ty) {
+            Ty::Var(other) => other == var,
+            Ty::Known(_) => false,
+            Ty::Tuple(fields) => fields.iter().any(|field| self.occurs(var, field)),
+            Ty::Array(element) => self.occurs(var, &element),
+        }
+    }
+
+    fn describe(&self, ty: &Ty) -> String {
+        match (self.known(ty), self.resolve(ty)) {
+            (Some(type_id), _) => type_id.to_string(),
+            (None, Ty::Tuple(fields)) => {
+                let fields = fields
+                    .iter()
+                    .map(|field| self.describe(field))
+                    .collect::<Vec<_>>();
+                format!("({})", fields.join(", "))
+            }
+            (None, Ty::Array(element)) => format!("[{}]", self.describe(&element)),
+            (None, _) => "_".to_string(),
+        }
+    }
+
+    fn describe_call(&self, name: &str, types: &[Ty]) -> String {
+        let types = types
+            .iter()
+            .map(|ty| self.describe(ty))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("{}({})", name, types)
+    }
+
+    fn candidates(
+        &mut self,
+        module_id: &Rc<String>,
+        id_loc: &IdLoc,
+        arity: usize,
+    ) -> Vec<Candidate> {
+        let (target_id, name) = match id_loc {
+            IdLoc::Here(name) => (module_id, *name),
+            IdLoc::Other(module, name) => match self.modules[module_id].imports.get(module) {
+                Some(target_id) => (target_id, *name),
+                None => return Vec::new(),
+            },
+            IdLoc::Tuple(_) | IdLoc::Array(_) => return Vec::new(),
+        };
+        let mut candidates = Vec::new();
+        if let Some(overloads) = self.modules[target_id].functions.get(name) {
+            for (index, function) in ordered_overloads(overloads).into_iter().enumerate() {
+                let function_type = &self.functions[&function_id(target_id, name, index)];
+                if function.args.len() == arity {
+                    candidates.push(Candidate {
+                        params: function_type.params.clone(),
+                        ret_type: function_type.ret_type.clone(),
+                    });
+                }
+            }
+        }
+        if self.modules[target_id].externs.contains_key(name) {
+            let extern_def = &self.externs[&Rc::new(name.to_string())];
+            if extern_def.args.len() == arity {
+                candidates.push(Candidate {
+                    params: extern_def
+                        .args
+                        .iter()
+                        .map(|arg| Ty::Known(Rc::clone(arg)))
+                        .collect(),
+                    ret_type: Ty::Known(Rc::clone(&extern_def.ret_type)),
+                });
+            }
+        }
+        if let IdLoc::Here(name) = id_loc {
+            for (params, method_id) in self.trait_methods.get(name).into_iter().flatten() {
+                // Functions of the module take precedence over trait methods
+                let defined = candidates.iter().any(|candidate| {
+                    candidate
+                        .params
+                        .iter()
+                        .zip(params.iter())
+                        .all(|(param, ty)| self.known(param).as_ref() == Some(ty))
+                });
+                if params.len() == arity && !defined {
+                    let function_type = &self.functions[method_id];
+                    candidates.push(Candidate {
+                        params: function_type.params.clone(),
+                        ret_type: function_type.ret_type.clone(),
+                    });
+                }
+            }
+        }
+        if matches!(id_loc, IdLoc::Here(_)) && arity <= MAX_BUILD_IN_ARGS {
+            for params in primitive_signatures(arity) {
+                let param_types = params.iter().map(|ty| Rc::new(ty.to_string())).collect();
+                // Functions of the module take precedence over build in functions
+                let defined = candidates.iter().any(|candidate| {
+                    candidate
+                        .params
+                        .iter()
+                        .zip(params.iter())
+                        .all(|(param, ty)| self.known(param).is_some_and(|p| p.as_str() == *ty))
+                });
+                if let Some((_, ret_type)) = get_build_in_signature(name, &param_types) {
+                    if !defined {
+                        candidates.push(Candidate {
+                            params: params.iter().map(|ty| Self::known_type(ty)).collect(),
+                            ret_type: Ty::Known(ret_type),
+                        });
+                    }
+                }
+            }
+            if let Some(candidate) = self.array_build_in(name, arity) {
+                candidates.push(candidate);
+            }
+        }
+        candidates
+    }
+
+    // The build in array functions work on arrays of any element type
+    fn array_build_in(&mut self, name: &str, arity: usize) -> Option<Candidate> {
+        if !matches!(
+            (name, arity),
+            ("len", 1) | ("get", 2) | ("set", 3) | ("push", 2)
+        ) {
+            return None;
+        }
+        let element = self.fresh_var();
+        let array = Ty::Array(Box::new(element.clone()));
+        let index = Self::known_type(I32_TYPE);
+        let (params, ret_type) = match (name, arity) {
+            ("len", 1) => (vec![array], index),
+            ("get", 2) => (vec![array, index], element),
+            ("set", 3) => (vec![array.clone(), index, element], array),
+            _ => (vec![array.clone(), element], array),
+        };
+        Some(Candidate { params, ret_type })
+    }
+
+    // Unifies the call with its only fitting candidate. Returns false if
+    // more than one candidate fits.
+    fn resolve_call(&mut self, call: &mut Call) -> bool {
+        // A candidate whose inferred parameter types do not fit, with the
+        // function they were inferred in
+        let inferred = call.candidates.iter().find_map(|candidate| {
+            let bound_in = candidate
+                .params
+                .iter()
+                .zip(call.args.iter())
+                .filter(|(param, arg)| !self.fits(param, arg))
+                .find_map(|(param, _)| self.bound_in(param))?;
+            Some((
+                self.describe_call(&call.name, &candidate.params),
+                bound_in.name.clone(),
+            ))
+        });
+        call.candidates.retain(|candidate| {
+            candidate
+                .params
+                .iter()
+                .zip(call.args.iter())
+                .all(|(param, arg)| self.fits(param, arg))
+        });
+        match call.candidates.len() {
+            0 => {
+                let message = match inferred {
+                    Some((signature, bound_in)) => format!(
+                        "Function {} was inferred as {} at its first use in function {}, \
+                        which conflicts with the call {}",
+                        call.name,
+                        signature,
+                        bound_in,
+                        self.describe_call(&call.name, &call.args)
+                    ),
+                    None => format!(
+                        "Could not find function {}",
+                        self.describe_call(&call.name, &call.args)
+                    ),
+                };
+                self.conflict(message);
+                true
+            }
+            1 => {
+                let candidate = &call.candidates[0];
+                for (param, arg) in candidate.params.iter().zip(call.args.iter()) {
+                    self.unify(param, arg);
+                }
+                let ret_type = candidate.ret_type.clone();
+                self.unify(&call.ret_type, &ret_type);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn resolve_field_access(&mut self, access: &FieldAccess) -> bool {
+        let type_id = match self.known(&access.obj) {
+            Some(type_id) => type_id,
+            None => return false,
+        };
+        let imports = &self.modules[&access.module_id].imports;
+        match named_field(
+            &access.module_id,
+            imports,
+            self.type_defs,
+            &type_id,
+            access.name,
+        ) {
+            Ok((_, _, field)) => self.unify(&Ty::Known(field), &access.field),
+            Err(error) => self.conflict(error),
+        }
+        true
+    }
+
+    // Resolves deferred calls and field accesses until none can be resolved
+    // anymore
+    fn resolve_calls(&mut self) -> Result<(), TypeError> {
+        loop {
+            let mut calls = std::mem::take(&mut self.calls);
+            let mut field_accesses = std::mem::take(&mut self.field_accesses);
+            let count = calls.len() + field_accesses.len();
+            // Conflicts are found in the function of the call or access
+            calls.retain_mut(|call| {
+                self.function = call.function.clone();
+                !self.resolve_call(call)
+            });
+            field_accesses.retain(|access| {
+                self.function = access.function.clone();
+                !self.resolve_field_access(access)
+            });
+            let resolved = calls.len() + field_accesses.len() < count;
+            self.calls = calls;
+            self.field_accesses = field_accesses;
+            if !resolved {
+                break;
+            }
+        }
+        match self.calls.first() {
+            None => Ok(()),
+            Some(call) => {
+                let mut candidates = call
+                    .candidates
+                    .iter()
+                    .map(|candidate| self.describe_call(&call.name, &candidate.params))
+                    .collect::<Vec<_>>();
+                candidates.sort();
+                // A conflict may keep the call from being resolved
+                if let Some(conflict) = &self.conflict {
+                    return Err(conflict.clone());
+                }
+                Err(TypeError {
+                    message: format!(
+                        "Ambiguous call {}, it could call {}. Please add type annotations",
+                        self.describe_call(&call.name, &call.args),
+                        candidates.join(" or ")
+                    ),
+                    function: call.function.clone(),
+                })
+            }
+        }
+    }
+
+    fn type_id(&self, module_id: &Rc<String>, ty: &IdLoc) -> Option<Rc<String>> {
+        get_unique_type_id(
+            self.type_ids,
+            &self.modules[module_id].imports,
+            module_id,
+            ty,
+        )
+        .ok()
+    }
+
+    fn infer_expr(
+        &mut self,
+        module_id: &Rc<String>,
+        vars: &mut HashMap<&'input str, Ty>,
+        expr: &Expr<'input>,
+    ) -> Ty {
+        match expr {
+            Expr::Array(elements) => {
+                let element = self.fresh_var();
+                for e in elements.iter() {
+                    let element_type = self.infer_expr(module_id, vars, e);
+                    self.unify(&element, &element_type);
+                }
+                if elements.is_empty() {
+                    let function = self.function.clone();
+                    self.empty_arrays.push((expr, element.clone(), function));
+                }
+                Ty::Array(Box::new(element))
+            }
+            Expr::Const(Const::U8(_)) => Self::known_type(U8_TYPE),
+            Expr::Const(Const::I32(_)) => Self::known_type(I32_TYPE),
+            Expr::Const(Const::Bool(_)) => Self::known_type(BOOL_TYPE),
+            Expr::Const(Const::Str(_)) => Self::known_type(STR_TYPE),
+            Expr::Const(Const::Void) => Self::known_type(VOID_TYPE),
+            Expr::FuncCall(id_loc, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.infer_expr(module_id, vars, arg))
+                    .collect::<Vec<_>>();
+                let ret_type = self.fresh_var();
+                let mut call = Call {
+                    function: self.function.clone(),
+                    name: id_loc.to_string(),
+                    candidates: self.candidates(module_id, id_loc, args.len()),
+                    args,
+                    ret_type: ret_type.clone(),
+                };
+                if !self.resolve_call(&mut call) {
+                    self.calls.push(call);
+                    return ret_type;
+                }
+                // Keeps the exit type, which does not bind ret_type
+                match call.candidates.first() {
+                    Some(candidate) => candidate.ret_type.clone(),
+                    None => ret_type,
+                }
+            }
+            Expr::GetField(obj, name) => {
+                let obj_type = self.infer_expr(module_id, vars, obj);
+                self.infer_field(module_id, &obj_type, name)
+            }
+            Expr::GetTypeCaseField(obj, case, index) => {
+                let obj_type = self.infer_expr(module_id, vars, obj);
+                // Patterns check the case of the object first, so its type is known
+                let field = self.known(&obj_type).and_then(|type_id| {
+                    let case_def = self
+                        .type_defs
+                        .get(&type_id)?
+                        .cases
+                        .iter()
+                        .find(|c| c.name == *case)?;
+                    case_def.fields.get(*index).cloned()
+                });
+                match field {
+                    Some(field) => Ty::Known(field),
+                    None => self.fresh_var(),
+                }
+            }
+            Expr::GetTupleField(obj, index, len) => {
+                let obj_type = self.infer_expr(module_id, vars, obj);
+                let fields = (0..*len).map(|_| self.fresh_var()).collect::<Vec<_>>();
+                let field = fields[*index].clone();
+                self.unify(&Ty::Tuple(fields), &obj_type);
+                field
+            }
+            Expr::If(cond, then_expr, else_expr) => {
+                let cond_type = self.infer_expr(module_id, vars, cond);
+                self.unify(&Self::known_type(BOOL_TYPE), &cond_type);
+                let then_type = self.infer_expr(module_id, vars, then_expr);
+                let else_type = self.infer_expr(module_id, vars, else_expr);
+                self.unify(&then_type, &else_type);
+                if self.is_exit(&then_type) {
+                    else_type
+                } else {
+                    then_type
+                }
+            }
+            Expr::IsTypeCase(obj, ty, _) => {
+                let obj_type = self.infer_expr(module_id, vars, obj);
+                if let Some(type_id) = self.type_id(module_id, ty) {
+                    self.unify(&Ty::Known(type_id), &obj_type);
+                }
+                Self::known_type(BOOL_TYPE)
+            }
+            Expr::Let(name, definition, body) => {
+                let def_type = self.infer_expr(module_id, vars, definition);
+                self.infer_let(module_id, vars, name, def_type, body)
+            }
+            Expr::LetPattern(pattern, definition, body) => {
+                let def_type = self.infer_expr(module_id, vars, definition);
+                // The condition constrains the type of the object by the pattern
+                let (condition, body) = build_pattern_lets(pattern, body);
+                self.infer_let(module_id, vars, MATCH_OBJ, def_type.clone(), &condition);
+                self.infer_let(module_id, vars, MATCH_OBJ, def_type, &body)
+            }
+            Expr::Match(line, obj, arms) => {
+                let obj_type = self.infer_expr(module_id, vars, obj);
+                self.infer_let(
+                    module_id,
+                    vars,
+                    MATCH_OBJ,
+                    obj_type,
+                    &build_ifs_from_arms(*line, arms),
+                )
+            }
+            Expr::Panic(_, _, message) => {
+                self.infer_expr(module_id, vars, message);
+                Self::known_type(EXIT_TYPE)
+            }
+            Expr::Seq(e1, e2) => {
+                self.infer_expr(module_id, vars, e1);
+                self.infer_expr(module_id, vars, e2)
+            }
+            Expr::Tuple(fields) => Ty::Tuple(
+                fields
+                    .iter()
+                    .map(|field| self.infer_expr(module_id, vars, field))
+                    .collect(),
+            ),
+            Expr::TypeCase(IdLoc::Here(var), name, args)
+                if args.is_empty() && vars.contains_key(var) =>
+            {
+                let obj_type = vars[var].clone();
+                self.infer_field(module_id, &obj_type, name)
+            }
+            Expr::TypeCase(ty, case, args) => {
+                let arg_types = args
+                    .iter()
+                    .map(|arg| self.infer_expr(module_id, vars, arg))
+                    .collect::<Vec<_>>();
+                let type_id = match self.type_id(module_id, ty) {
+                    Some(type_id) => type_id,
+                    None => return self.fresh_var(),
+                };
+                let fields = self
+                    .type_defs
+                    .get(&type_id)
+                    .and_then(|type_def| type_def.cases.iter().find(|c| c.name == *case))
+                    .map(|case_def| case_def.fields.clone())
+                    .unwrap_or_default();
+                for (field, arg_type) in fields.iter().zip(arg_types.iter()) {
+                    self.unify(&Ty::Known(Rc::clone(field)), arg_type);
+                }
+                Ty::Known(type_id)
+            }
+            Expr::UpdateFields(obj, updates) => {
+                let obj_type = self.infer_expr(module_id, vars, obj);
+                for (name, value) in updates.iter() {
+                    let value_type = self.infer_expr(module_id, vars, value);
+                    let field_type = self.infer_field(module_id, &obj_type, name);
+                    self.unify(&field_type, &value_type);
+                }
+                obj_type
+            }
+            Expr::Var(name) => match vars.get(name) {
+                Some(ty) => ty.clone(),
+                None => self.fresh_var(),
+            },
+        }
+    }
+
+    fn infer_field(&mut self, module_id: &Rc<String>, obj_type: &Ty, name: &'input str) -> Ty {
+        let access = FieldAccess {
+            module_id: Rc::clone(module_id),
+            function: self.function.clone(),
+            obj: obj_type.clone(),
+            name,
+            field: self.fresh_var(),
+        };
+        let field = access.field.clone();
+        if !self.resolve_field_access(&access) {
+            self.field_accesses.push(access);
+        }
+        field
+    }
+
+    fn infer_let(
+        &mut self,
+        module_id: &Rc<String>,
+        vars: &mut HashMap<&'input str, Ty>,
+        name: &'input str,
+        def_type: Ty,
+        body: &Expr<'input>,
+    ) -> Ty {
+        let old_type = vars.insert(name, def_type);
+        let body_type = self.infer_expr(module_id, vars, body);
+        match old_type {
+            None => vars.remove(name),
+            Some(ty) => vars.insert(name, ty),
+        };
+        body_type
+    }
+}
+
+// All combinations of primitive types for the given number of parameters
+fn primitive_signatures(arity: usize) -> Vec<Vec<&'static str>> {
+    (0..arity).fold(vec![Vec::new()], |signatures, _| {
+        signatures
+            .iter()
+            .flat_map(|signature| {
+                PRIMITIVE_TYPES.iter().map(move |ty| {
+                    let mut signature = signature.clone();
+                    signature.push(*ty);
+                    signature
+                })
+            })
+            .collect()
+    })
+}
@@ -0,0 +1,269 @@
+// Resolves the implementations of traits. Every method of an implementation
+// becomes a function with the types of the trait method, where Self is
+// replaced by the implementing type. Calls of trait methods are resolved
+// statically by the types of their arguments, in every module.
+
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use super::get_unique_type_id;
+use super::inference::{Signature, PRIMITIVE_TYPES};
+use crate::binary::BinType;
+use crate::module::*;
+use crate::types::*;
+
+// The traits of the operators, used if a module does not define a trait with
+// the same name. Their default methods are type checked in the module of the
+// implementation.
+pub const BUILD_IN_TRAITS: &str = "
+trait Eq {
+    fn eq(a: Self, b: Self) -> bool
+    fn neq(a: Self, b: Self) -> bool = !eq(a, b)
+}
+
+trait Ord {
+    fn lt(a: Self, b: Self) -> bool
+    fn le(a: Self, b: Self) -> bool = !lt(b, a)
+    fn gt(a: Self, b: Self) -> bool = lt(b, a)
+    fn ge(a: Self, b: Self) -> bool = !lt(a, b)
+}
+
+trait Show {
+    fn print(value: Self)
+}
+";
+
+const SELF_TYPE: &str = "Self";
+
+// A function implementing a trait method for a type
+pub struct Method<'a, 'input> {
+    // The module the body is type checked in
+    pub module_id: &'a Rc<String>,
+    pub name: &'input str,
+    // Byte offset of the name of the method in the code of the module, or of
+    // the type for derived methods
+    pub location: usize,
+    pub args: &'a Vec<ParamDef<'input>>,
+    // None for derived methods, which are generated from the type
+    pub body: Option<&'a Expr<'input>>,
+    pub signature: Signature,
+}
+
+// Id of the method name of the implementation of the trait for the type
+pub fn method_id(trait_id: &str, type_id: &str, name: &str) -> Rc<String> {
+    Rc::new(format!("{}${}${}", trait_id, type_id, name))
+}
+
+// Returns the methods of all implementations by function id
+pub fn collect_methods<'a, 'input>(
+    modules: &'a HashMap<Rc<String>, Module<'input>>,
+    build_in: &'a Module<'input>,
+    type_ids: &HashMap<&Rc<String>, HashMap<&'input str, Rc<String>>>,
+    type_defs: &HashMap<Rc<String>, BinType<'input>>,
+) -> Result<HashMap<Rc<String>, Method<'a, 'input>>, String> {
+    let mut methods = HashMap::new();
+    let mut implemented = HashSet::new();
+    for (module_id, module) in modules.iter() {
+        for implementation in module.impls.iter() {
+            // The types and default methods of the trait refer to the module
+            // of the trait
+            let (trait_id, trait_module_id, trait_def) = match &implementation.trait_name {
+                IdLoc::Here(name) => match module.traits.get(name) {
+                    Some(trait_def) => (format!("{}{}", module_id, name), module_id, trait_def),
+                    None => match build_in.traits.get(name) {
+                        Some(trait_def) => (name.to_string(), module_id, trait_def),
+                        None => return Err(format!("Trait {} not found", name)),
+                    },
+                },
+                IdLoc::Other(module_name, name) => {
+                    let other_id = module
+                        .imports
+                        .get(module_name)
+                        .ok_or(format!("Unresolved import: {}", module_name))?;
+                    let (other_id, other_module) = modules.get_key_value(other_id).unwrap();
+                    match other_module.traits.get(name) {
+                        Some(trait_def) => (format!("{}{}", other_id, name), other_id, trait_def),
+                        None => {
+                            return Err(format!("Trait {} not found", implementation.trait_name))
+                        }
+                    }
+                }
+                IdLoc::Tuple(_) | IdLoc::Array(_) => {
+                    return Err(format!("Trait {} not found", implementation.trait_name))
+                }
+            };
+            let type_id = get_unique_type_id(
+                type_ids,
+                &module.imports,
+                module_id,
+                &implementation.type_name,
+            )?;
+            if !type_defs.contains_key(&type_id) && !PRIMITIVE_TYPES.contains(&type_id.as_str()) {
+                return Err(format!("Type {} not found", implementation.type_name));
+            }
+            if !implemented.insert((trait_id.clone(), Rc::clone(&type_id))) {
+                return Err(format!(
+                    "Trait {} is implemented twice for {}",
+                    implementation.trait_name, implementation.type_name
+                ));
+            }
+            if let Some(name) = implementation
+                .functions
+                .keys()
+                .find(|name| !trait_def.methods.contains_key(*name))
+            {
+                return Err(format!(
+                    "{} is not a method of trait {}",
+                    name, implementation.trait_name
+                ));
+            }
+
+            // Resolves a type of the trait in the module of the trait
+            let trait_type = |ty: &IdLoc| match ty {
+                IdLoc::Here(SELF_TYPE) => Ok(Rc::clone(&type_id)),
+                ty => get_unique_type_id(
+                    type_ids,
+                    &modules[trait_module_id].imports,
+                    trait_module_id,
+                    ty,
+                ),
+            };
+            for (name, trait_method) in trait_def.methods.iter() {
+                let signature =
+                    method_signature(&implementation.trait_name, name, trait_method, trait_type)?;
+                let method = match (implementation.functions.get(name), &trait_method.body) {
+                    (Some(function), _) => {
+                        check_implementation(
+                            type_ids, module_id, module, &type_id, name, function, &signature,
+                        )?;
+                        Method {
+                            module_id,
+                            name,
+                            location: function.location,
+                            args: &function.args,
+                            body: Some(&function.body),
+                            signature,
+                        }
+                    }
+                    (None, Some(body)) => Method {
+                        module_id: trait_module_id,
+                        name,
+                        location: trait_method.location,
+                        args: &trait_method.args,
+                        body: Some(body),
+                        signature,
+                    },
+                    (None, None) => {
+                        return Err(format!(
+                            "Implementation of trait {} for {} is missing method {}",
+                            implementation.trait_name, implementation.type_name, name
+                        ))
+                    }
+                };
+                methods.insert(method_id(&trait_id, &type_id, name), method);
+            }
+        }
+
+        for (type_name, type_def) in module.types.iter() {
+            let type_id = &type_ids[module_id][type_name];
+            for derived in type_def.derives.iter() {
+                let trait_def = build_in.traits.get(derived).ok_or(format!(
+                    "Can not derive {}, only Eq, Ord and Show can be derived",
+                    derived
+                ))?;
+                if !implemented.insert((derived.to_string(), Rc::clone(type_id))) {
+                    return Err(format!(
+                        "Trait {} is implemented twice for {}",
+                        derived, type_name
+                    ));
+                }
+                let trait_type = |ty: &IdLoc| match ty {
+                    IdLoc::Here(SELF_TYPE) => Ok(Rc::clone(type_id)),
+                    ty => get_unique_type_id(type_ids, &module.imports, module_id, ty),
+                };
+                // Only the methods without default are generated
+                for (name, trait_method) in trait_def.methods.iter() {
+                    let signature =
+                        method_signature(&IdLoc::Here(derived), name, trait_method, trait_type)?;
+                    let method = Method {
+                        module_id,
+                        name,
+                        location: type_def.location,
+                        args: &trait_method.args,
+                        body: trait_method.body.as_ref(),
+                        signature,
+                    };
+                    methods.insert(method_id(derived, type_id, name), method);
+                }
+            }
+        }
+    }
+    Ok(methods)
+}
+
+// The types of the method, resolved by trait_type
+fn method_signature(
+    trait_name: &IdLoc,
+    name: &str,
+    trait_method: &TraitMethod,
+    trait_type: impl Fn(&IdLoc) -> Result<Rc<String>, String>,
+) -> Result<Signature, String> {
+    let params = trait_method
+        .args
+        .iter()
+        .map(|arg| match &arg.param_type {
+            Some(param_type) => trait_type(param_type),
+            None => Err(format!(
+                "Parameter {} of method {} of trait {} needs a type annotation",
+                arg.name, name, trait_name
+            )),
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let ret_type = match &trait_method.ret_type {
+        Some(ret_type) => trait_type(ret_type)?,
+        None => Rc::new(VOID_TYPE.to_string()),
+    };
+    Ok(Signature { params, ret_type })
+}
+
+// Checks that the annotations of the implementation match the trait
+fn check_implementation<'input>(
+    type_ids: &HashMap<&Rc<String>, HashMap<&'input str, Rc<String>>>,
+    module_id: &Rc<String>,
+    module: &Module<'input>,
+    type_id: &Rc<String>,
+    name: &str,
+    function: &Function<'input>,
+    signature: &Signature,
+) -> Result<(), String> {
+    if function.args.len() != signature.params.len() {
+        return Err(format!(
+            "Method {} for {} expects {} parameters, got {}",
+            name,
+            type_id,
+            signature.params.len(),
+            function.args.len()
+        ));
+    }
+    let annotations = function
+        .args
+        .iter()
+        .map(|arg| &arg.param_type)
+        .zip(signature.params.iter())
+        .chain([(&function.ret_type, &signature.ret_type)]);
+    for (annotation, expected) in annotations {
+        if let Some(ty) = annotation {
+            let ty = match ty {
+                IdLoc::Here(SELF_TYPE) => Rc::clone(type_id),
+                ty => get_unique_type_id(type_ids, &module.imports, module_id, ty)?,
+            };
+            if ty != *expected {
+                return Err(format!(
+                    "Method {} for {} expects type {}, but found {}",
+                    name, type_id, expected, ty
+                ));
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,218 @@
+// Other modules can only refer to the public functions, types and cases of a
+// module. A type whose cases are all private is abstract outside its module,
+// as other modules can neither construct nor match its values. The named
+// fields of private cases are checked with the type of the accessed value
+// while type checking.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::{ordered_overloads, TypeError};
+use crate::module::*;
+
+// The references of one module to the modules it imports
+struct Visibility<'a, 'input> {
+    modules: &'a HashMap<Rc<String>, Module<'input>>,
+    imports: &'a HashMap<&'input str, Rc<String>>,
+}
+
+pub fn check_visibility(modules: &HashMap<Rc<String>, Module>) -> Result<(), TypeError> {
+    for (module_id, module) in modules.iter() {
+        // Errors are reported in the function they are found in
+        let in_function = |name: &str, location: usize, message: String| {
+            TypeError::in_function(message, module_id, module, name, location)
+        };
+        check_overloads(module_id, module)?;
+        let visibility = Visibility {
+            modules,
+            imports: &module.imports,
+        };
+        for type_def in module.types.values() {
+            for case in type_def.cases.iter() {
+                for field in case.fields.iter() {
+                    visibility.type_ref(field)?;
+                }
+            }
+        }
+        for (name, overloads) in module.functions.iter() {
+            for function in overloads.values() {
+                visibility
+                    .function(&function.args, &function.ret_type, Some(&function.body))
+                    .map_err(|message| in_function(name, function.location, message))?;
+            }
+        }
+        for trait_def in module.traits.values() {
+            for (name, method) in trait_def.methods.iter() {
+                visibility
+                    .function(&method.args, &method.ret_type, method.body.as_ref())
+                    .map_err(|message| in_function(name, method.location, message))?;
+            }
+        }
+        for implementation in module.impls.iter() {
+            visibility.type_ref(&implementation.type_name)?;
+            for (name, function) in implementation.functions.iter() {
+                visibility
+                    .function(&function.args, &function.ret_type, Some(&function.body))
+                    .map_err(|message| in_function(name, function.location, message))?;
+            }
+        }
+        for (name, extern_def) in module.externs.iter() {
+            visibility
+                .function(&extern_def.args, &extern_def.ret_type, None)
+                .map_err(|message| in_function(name, extern_def.location, message))?;
+        }
+    }
+    Ok(())
+}
+
+// The overloads of a function are either all public or all private, so that
+// a call is visible independent of the overload it resolves to. The error is
+// reported at the first overload.
+fn check_overloads(module_id: &Rc<String>, module: &Module) -> Result<(), TypeError> {
+    for (name, overloads) in module.functions.iter() {
+        let public = overloads
+            .values()
+            .filter(|function| function.public)
+            .count();
+        if public > 0 && public < overloads.len() {
+            let first = ordered_overloads(overloads)[0];
+            return Err(TypeError::in_function(
+                format!(
+                    "Function {} is public in some of its overloads, but not in all",
+                    name
+                ),
+                module_id,
+                module,
+                name,
+                first.location,
+            ));
+        }
+    }
+    Ok(())
+}
+
+impl<'a, 'input> Visibility<'a, 'input> {
+    // None for unresolved imports, which the type check reports
+    fn imported(&self, module: &str) -> Option<&'a Module<'input>> {
+        self.imports
+            .get(module)
+            .and_then(|module_id| self.modules.get(module_id))
+    }
+
+    fn type_ref(&self, ty: &IdLoc) -> Result<(), String> {
+        match ty {
+            IdLoc::Here(_) => Ok(()),
+            IdLoc::Other(module, name) => {
+                match self.imported(module).and_then(|m| m.types.get(name)) {
+                    Some(type_def) if !type_def.public => Err(format!("Type {} is private", ty)),
+                    _ => Ok(()),
+                }
+            }
+            IdLoc::Tuple(fields) => fields.iter().try_for_each(|field| self.type_ref(field)),
+            IdLoc::Array(element) => self.type_ref(element),
+        }
+    }
+
+    fn case(&self, ty: &IdLoc, case: &str) -> Result<(), String> {
+        self.type_ref(ty)?;
+        if let IdLoc::Other(module, name) = ty {
+            let case_def = self
+                .imported(module)
+                .and_then(|m| m.types.get(name))
+                .and_then(|type_def| type_def.cases.iter().find(|c| c.name == case));
+            if case_def.is_some_and(|case_def| !case_def.public) {
+                return Err(format!("Case {}.{} is private", ty, case));
+            }
+        }
+        Ok(())
+    }
+
+    fn function(
+        &self,
+        args: &[ParamDef],
+        ret_type: &Option<IdLoc>,
+        body: Option<&Expr>,
+    ) -> Result<(), String> {
+        for param_type in args.iter().filter_map(|arg| arg.param_type.as_ref()) {
+            self.type_ref(param_type)?;
+        }
+        if let Some(ret_type) = ret_type {
+            self.type_ref(ret_type)?;
+        }
+        match body {
+            Some(body) => self.expr(body),
+            None => Ok(()),
+        }
+    }
+
+    fn expr(&self, expr: &Expr) -> Result<(), String> {
+        match expr {
+            Expr::Array(elements) | Expr::Tuple(elements) => {
+                elements.iter().try_for_each(|element| self.expr(element))
+            }
+            Expr::Const(_) | Expr::Var(_) => Ok(()),
+            Expr::FuncCall(function, args) => {
+                if let IdLoc::Other(module, name) = function {
+                    let overloads = self.imported(module).and_then(|m| m.functions.get(name));
+                    if overloads.is_some_and(|overloads| overloads.values().any(|f| !f.public)) {
+                        return Err(format!("Function {} is private", function));
+                    }
+                }
+                args.iter().try_for_each(|arg| self.expr(arg))
+            }
+            Expr::GetField(obj, _)
+            | Expr::GetTypeCaseField(obj, _, _)
+            | Expr::GetTupleField(obj, _, _)
+            | Expr::Panic(_, _, obj) => self.expr(obj),
+            Expr::If(condition, then_expr, else_expr) => {
+                self.expr(condition)?;
+                self.expr(then_expr)?;
+                self.expr(else_expr)
+            }
+            Expr::IsTypeCase(obj, ty, case) => {
+                self.case(ty, case)?;
+                self.expr(obj)
+            }
+            Expr::Let(_, value, body) => {
+                self.expr(value)?;
+                self.expr(body)
+            }
+            Expr::LetPattern(pattern, value, body) => {
+                self.pattern(pattern)?;
+                self.expr(value)?;
+                self.expr(body)
+            }
+            Expr::Match(_, obj, arms) => {
+                self.expr(obj)?;
+                for (pattern, body) in arms.iter() {
+                    self.pattern(pattern)?;
+                    self.expr(body)?;
+                }
+                Ok(())
+            }
+            Expr::Seq(first, rest) => {
+                self.expr(first)?;
+                self.expr(rest)
+            }
+            Expr::TypeCase(ty, case, args) => {
+                self.case(ty, case)?;
+                args.iter().try_for_each(|arg| self.expr(arg))
+            }
+            Expr::UpdateFields(obj, updates) => {
+                self.expr(obj)?;
+                updates.iter().try_for_each(|(_, value)| self.expr(value))
+            }
+        }
+    }
+
+    fn pattern(&self, pattern: &MatchPattern) -> Result<(), String> {
+        match pattern {
+            MatchPattern::Literal(_) | MatchPattern::Var(_) | MatchPattern::Wildcard => Ok(()),
+            MatchPattern::Tuple(fields) => fields.iter().try_for_each(|field| self.pattern(field)),
+            MatchPattern::TypeCase(ty, case, fields) => {
+                self.case(ty, case)?;
+                fields.iter().try_for_each(|field| self.pattern(field))
+            }
+        }
+    }
+}
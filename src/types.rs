@@ -11,11 +11,76 @@ pub const I32_TYPE: &'static str = "i32";
 pub const I64_TYPE: &'static str = "i64";
 pub const BOOL_TYPE: &'static str = "bool";
 pub const STR_TYPE: &'static str = "str";
+// Id of the build in string type, written string in programs. Strings are
+// objects on the heap, so their id starts with $ like user defined types.
+pub const STRING_TYPE: &'static str = "$string";
 pub const VOID_TYPE: &'static str = "void";
 pub const VOID_PTR_TYPE: &'static str = "i8*"; // LLVM does not support void*
 pub const EXIT_TYPE: &'static str = "$exit$";
+// Name of the only case of tuple types
+pub const TUPLE_CASE: &'static str = "Tuple";
+// Name of the only case of array types
+pub const ARRAY_CASE: &'static str = "Array";
 
-pub fn type_to_llvm_type(
+// Id of the tuple type with the field types. Tuples holding user defined
+// types are objects on the heap and start with $ like user defined types, so
+// that the GC traces them. Other tuples are passed by value.
+pub fn tuple_type_id(fields: &[Rc<String>]) -> Rc<String> {
+    let fields = fields
+        .iter()
+        .map(|field| field.as_str())
+        .collect::<Vec<_>>();
+    let boxed = if fields.iter().any(|field| field.starts_with('$')) {
+        "$"
+    } else {
+        ""
+    };
+    Rc::new(format!("{}({})", boxed, fields.join(", ")))
+}
+
+// The field types of a tuple type, None for other types
+pub fn tuple_fields(type_id: &str) -> Option<Vec<Rc<String>>> {
+    let inner = type_id
+        .strip_prefix('$')
+        .unwrap_or(type_id)
+        .strip_prefix('(')?
+        .strip_suffix(')')?;
+    let mut fields = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (index, c) in inner.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                fields.push(Rc::new(inner[start..index].to_string()));
+                start = index + 2;
+            }
+            _ => (),
+        }
+    }
+    fields.push(Rc::new(inner[start..].to_string()));
+    Some(fields)
+}
+
+// Tuples without user defined types are LLVM structs passed by value
+pub fn is_unboxed_tuple(type_id: &str) -> bool {
+    type_id.starts_with('(')
+}
+
+// Id of the array type with the element type. Arrays are always objects on
+// the heap.
+pub fn array_type_id(element: &Rc<String>) -> Rc<String> {
+    Rc::new(format!("$[{}]", element))
+}
+
+// The element type of an array type, None for other types
+pub fn array_element(type_id: &str) -> Option<Rc<String>> {
+    let element = type_id.strip_prefix("$[")?.strip_suffix(']')?;
+    Some(Rc::new(element.to_string()))
+}
+
+pub(crate) fn type_to_llvm_type(
     context: *mut llvm::LLVMContext,
     llvm_structs: &HashMap<Rc<String>, *mut llvm::LLVMType>,
     ty: &Rc<String>,
@@ -34,6 +99,9 @@ pub fn type_to_llvm_type(
         }
         "void" => unsafe { llvm::core::LLVMVoidTypeInContext(context) },
         "$exit$" => unsafe { llvm::core::LLVMVoidTypeInContext(context) },
+        _ if is_unboxed_tuple(ty) => *llvm_structs
+            .get(ty)
+            .unwrap_or_else(|| panic!("Could not find llvm struct {}", ty)),
         _ => unsafe {
             llvm::core::LLVMPointerType(
                 *llvm_structs
@@ -45,11 +113,26 @@ pub fn type_to_llvm_type(
     }
 }
 
-pub fn create_structs<Gc: GC>(
+pub(crate) fn create_structs<Gc: GC>(
     binary: &Binary,
     context: *mut llvm::LLVMContext,
 ) -> HashMap<Rc<String>, *mut llvm::LLVMType> {
     let mut ret = HashMap::new();
+    // Strings have a length and their bytes after the header and case id
+    let string_name = CString::new(STRING_TYPE).unwrap();
+    let string_struct = unsafe { llvm::core::LLVMStructCreateNamed(context, string_name.as_ptr()) };
+    let mut string_fields = Gc::get_type_header(context);
+    unsafe {
+        string_fields.push(llvm::core::LLVMInt32TypeInContext(context));
+        string_fields.push(llvm::core::LLVMInt32TypeInContext(context));
+        string_fields.push(llvm::core::LLVMArrayType(
+            llvm::core::LLVMInt8TypeInContext(context),
+            0,
+        ));
+        let fields_len = string_fields.len().try_into().unwrap();
+        llvm::core::LLVMStructSetBody(string_struct, string_fields.as_mut_ptr(), fields_len, 0);
+    }
+    ret.insert(Rc::new(STRING_TYPE.to_string()), string_struct);
     for (name, _) in binary.types.iter() {
         let type_name = CString::new(name.as_str()).unwrap();
         let llvm_struct = unsafe { llvm::core::LLVMStructCreateNamed(context, type_name.as_ptr()) };
@@ -58,6 +141,34 @@ pub fn create_structs<Gc: GC>(
 
     for (name, t) in binary.types.iter() {
         let llvm_struct = *ret.get(name).unwrap();
+        if is_unboxed_tuple(name) {
+            // Only the fields, without header and case id
+            let mut fields = t.cases[0]
+                .fields
+                .iter()
+                .map(|f| type_to_llvm_type(context, &ret, f))
+                .collect::<Vec<_>>();
+            let fields_len = fields.len().try_into().unwrap();
+            unsafe {
+                llvm::core::LLVMStructSetBody(llvm_struct, fields.as_mut_ptr(), fields_len, 0)
+            };
+            continue;
+        }
+        if let Some(element) = array_element(name) {
+            // The case id, the length and the elements
+            let mut fields = Gc::get_type_header(context);
+            unsafe {
+                fields.push(llvm::core::LLVMInt32TypeInContext(context));
+                fields.push(llvm::core::LLVMInt64TypeInContext(context));
+                fields.push(llvm::core::LLVMArrayType(
+                    type_to_llvm_type(context, &ret, &element),
+                    0,
+                ));
+                let fields_len = fields.len().try_into().unwrap();
+                llvm::core::LLVMStructSetBody(llvm_struct, fields.as_mut_ptr(), fields_len, 0);
+            }
+            continue;
+        }
         let mut fields = Gc::get_type_header(context);
         fields.push(unsafe { llvm::core::LLVMInt32TypeInContext(context) });
         // First push user defined types
@@ -92,7 +203,7 @@ pub fn create_structs<Gc: GC>(
     ret
 }
 
-pub fn get_struct_size(
+pub(crate) fn get_struct_size(
     llvm_structs: &HashMap<Rc<String>, *mut llvm::LLVMType>,
     ty: &Rc<String>,
 ) -> *mut llvm::LLVMValue {
@@ -105,7 +216,7 @@ pub fn get_struct_size(
     }
 }
 
-pub fn get_case_id_case_indices_pointer_indices<Gc: GC>(
+pub(crate) fn get_case_id_case_indices_pointer_indices<Gc: GC>(
     context: *mut llvm::LLVMContext,
     binary: &Binary,
     llvm_structs: &HashMap<Rc<String>, *mut llvm::LLVMType>,
@@ -0,0 +1,122 @@
+// Reading the dependencies from the Arena.toml manifest of a program
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use arena::sources::{FileSystem, SourceLoader};
+
+// A project folder with the manifest, a main module and the folders lib and
+// vendor/greetings for dependencies
+fn project(name: &str, manifest: &str) -> PathBuf {
+    let folder =
+        std::env::temp_dir().join(format!("arena-manifest-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&folder);
+    fs::create_dir_all(folder.join("app")).unwrap();
+    fs::create_dir_all(folder.join("lib")).unwrap();
+    fs::create_dir_all(folder.join("vendor/greetings")).unwrap();
+    fs::write(folder.join("app/Arena.toml"), manifest).unwrap();
+    fs::write(folder.join("app/main.arena"), "fn main() = ()\n").unwrap();
+    folder
+}
+
+fn dependencies(name: &str, manifest: &str) -> Vec<(String, PathBuf)> {
+    let folder = project(name, manifest);
+    let loader = FileSystem::new(&folder.join("app/main.arena"), &[]).unwrap();
+    let dependencies = loader
+        .dependencies()
+        .into_iter()
+        .map(|(name, path)| (name, path.strip_prefix(&folder).unwrap().to_path_buf()))
+        .collect();
+    fs::remove_dir_all(&folder).unwrap();
+    dependencies
+}
+
+fn error(name: &str, manifest: &str) -> String {
+    let folder = project(name, manifest);
+    let error = FileSystem::new(&folder.join("app/main.arena"), &[])
+        .err()
+        .expect("Manifest should be invalid")
+        .to_string();
+    fs::remove_dir_all(&folder).unwrap();
+    let manifest_path = folder.join("app/Arena.toml");
+    error
+        .strip_prefix(manifest_path.to_str().unwrap())
+        .unwrap_or(&error)
+        .to_string()
+}
+
+fn expected(dependencies: &[(&str, &str)]) -> Vec<(String, PathBuf)> {
+    dependencies
+        .iter()
+        .map(|(name, path)| (name.to_string(), Path::new(path).to_path_buf()))
+        .collect()
+}
+
+#[test]
+fn valid_manifests() {
+    assert_eq!(dependencies("empty", ""), expected(&[]));
+    assert_eq!(
+        dependencies(
+            "inline",
+            "# Project\n[package]\nname = \"app\" # name\nversion = \"0.1\"\nedition = 2024\n\n\
+             [dependencies]\ngreetings = { path = \"../vendor/greetings\", version = \"0.1\" }\n\
+             \"lib\" = { version = '1', path = '../lib' }\n",
+        ),
+        expected(&[("greetings", "vendor/greetings"), ("lib", "lib")])
+    );
+    assert_eq!(
+        dependencies(
+            "tables",
+            "[dependencies.greetings]\npath = \"../vendor/greetings\"\noptional = true\n\n\
+             [dependencies.\"lib\"]\npath = \"../lib\"\n",
+        ),
+        expected(&[("greetings", "vendor/greetings"), ("lib", "lib")])
+    );
+    assert_eq!(
+        dependencies("dotted", "[dependencies]\nlib.path = \"../lib\"\n"),
+        expected(&[("lib", "lib")])
+    );
+}
+
+#[test]
+fn invalid_manifests() {
+    assert_eq!(error("section", "[deps]\n"), ":1: Unknown table deps");
+    assert_eq!(
+        error("string", "[dependencies]\nlib = \"../lib\"\n"),
+        ":2: Expected lib = { path = \"...\" }"
+    );
+    assert_eq!(
+        error("no-path", "[dependencies.lib]\nversion = \"1\"\n"),
+        ":1: Dependency lib has no path"
+    );
+    assert_eq!(
+        error("path-type", "[dependencies]\nlib = { path = 1 }\n"),
+        ":2: The path of dependency lib has to be a string"
+    );
+    assert_eq!(
+        error(
+            "twice",
+            "[dependencies]\nlib = { path = \"../lib\" }\n\"lib\" = { path = \"../lib\" }\n"
+        ),
+        ":3: lib is defined twice"
+    );
+    assert_eq!(
+        error(
+            "unterminated",
+            "[dependencies]\nlib = { path = \"../lib }\n"
+        ),
+        ":2: Unterminated string"
+    );
+    assert_eq!(
+        error("unclosed", "[dependencies]\nlib = { path = \"../lib\"\n"),
+        ":2: Expected , at the end of the line"
+    );
+    assert_eq!(
+        error("trailing", "[package]\nname = \"app\" \"web\"\n"),
+        ":2: Unexpected \"web\""
+    );
+    assert!(
+        error("missing", "[dependencies]\nlib = { path = \"../nope\" }\n")
+            .starts_with(":2: Folder ")
+    );
+}
@@ -0,0 +1,150 @@
+// The generated code has to be the same whatever other library folders are
+// configured with -I and ARENA_PATH, as module ids are part of every symbol,
+// and modules in different library folders must not share an id.
+
+use std::path::{Path, PathBuf};
+
+use arena::codegen::Backend;
+use arena::sources::{InMemory, SourceLoader};
+use arena::{CodegenOptions, Error};
+
+// In-memory program with configurable library folders
+struct Program {
+    files: InMemory,
+    library_folders: Vec<PathBuf>,
+}
+
+impl SourceLoader for Program {
+    fn canonicalize(&self, path: &Path) -> Option<PathBuf> {
+        self.files.canonicalize(path)
+    }
+
+    fn read(&self, path: &Path) -> Result<String, Error> {
+        self.files.read(path)
+    }
+
+    fn dependencies(&self) -> Vec<(String, PathBuf)> {
+        Vec::new()
+    }
+
+    fn library_folders(&self) -> Vec<PathBuf> {
+        self.library_folders.clone()
+    }
+}
+
+fn llvm_ir(library_folders: &[&str]) -> String {
+    let mut files = InMemory::new();
+    files
+        .add(
+            "/app/main.arena",
+            "import Greeting\n\nfn main() = Greeting::hello()\n",
+        )
+        .add(
+            "/lib/Greeting.arena",
+            "pub fn hello() = print(\"Hello\\n\")\n",
+        );
+    let program = Program {
+        files,
+        library_folders: library_folders.iter().map(PathBuf::from).collect(),
+    };
+    let (codes, main_path) = arena::load(&program, "/app/main.arena".as_ref()).unwrap();
+    let asts = arena::parse(&codes).unwrap();
+    let (modules, main_module_id) = arena::resolve_all_imports(&program, asts, &main_path).unwrap();
+    let binary = arena::type_check(&modules, &main_module_id).unwrap();
+    arena::codegen(binary, Backend::Arc, &CodegenOptions::default()).unwrap()
+}
+
+#[test]
+fn module_ids_do_not_depend_on_other_library_folders() {
+    let default = llvm_ir(&["/lib"]);
+    assert!(default.contains("hello"));
+    assert_eq!(default, llvm_ir(&["/include", "/lib"]));
+    assert_eq!(
+        default,
+        llvm_ir(&["/include", "/arena_path", "/home/.arena/lib", "/lib"])
+    );
+}
+
+#[test]
+fn library_folders_with_the_same_module_path_do_not_collide() {
+    let mut files = InMemory::new();
+    files
+        .add(
+            "/app/main.arena",
+            "import First\nimport Second\n\nfn main() =\n    First::hello();\n    Second::hello()\n",
+        )
+        .add("/first/First.arena", "import B\n\npub fn hello() = B::hello()\n")
+        .add("/first/B.arena", "pub fn hello() = print(\"First\\n\")\n")
+        .add("/second/Second.arena", "import B\n\npub fn hello() = B::hello()\n")
+        .add("/second/B.arena", "pub fn hello() = print(\"Second\\n\")\n");
+    let program = Program {
+        files,
+        library_folders: vec![PathBuf::from("/first"), PathBuf::from("/second")],
+    };
+    let (codes, main_path) = arena::load(&program, "/app/main.arena".as_ref()).unwrap();
+    let asts = arena::parse(&codes).unwrap();
+    let (modules, main_module_id) = arena::resolve_all_imports(&program, asts, &main_path).unwrap();
+    assert_eq!(modules.len(), 5);
+    let binary = arena::type_check(&modules, &main_module_id).unwrap();
+    let ir = arena::codegen(binary, Backend::Arc, &CodegenOptions::default()).unwrap();
+    assert!(ir.contains("First\\0A") && ir.contains("Second\\0A"));
+}
+
+#[test]
+fn import_cycles_are_reported_with_their_chain() {
+    let mut files = InMemory::new();
+    files
+        .add("/app/main.arena", "import A\n\nfn main() = A::hello()\n")
+        .add("/app/A.arena", "import B\n\npub fn hello() = B::hello()\n")
+        .add("/app/B.arena", "import A\n\npub fn hello() = print(1)\n");
+    match arena::load(&files, "/app/main.arena".as_ref()) {
+        Err(Error::ImportCycle { chain }) => assert_eq!(
+            chain,
+            [
+                "/app/main.arena",
+                "/app/A.arena",
+                "/app/B.arena",
+                "/app/A.arena"
+            ]
+            .iter()
+            .map(PathBuf::from)
+            .collect::<Vec<_>>()
+        ),
+        _ => panic!("Expected an import cycle"),
+    }
+}
+
+#[test]
+fn missing_imports_are_reported_with_the_searched_files() {
+    let mut files = InMemory::new();
+    files
+        .add("/app/main.arena", "import A\n\nfn main() = A::hello()\n")
+        .add(
+            "/app/A.arena",
+            "import Missing\n\npub fn hello() = Missing::hello()\n",
+        );
+    let program = Program {
+        files,
+        library_folders: vec![PathBuf::from("/lib")],
+    };
+    match arena::load(&program, "/app/main.arena".as_ref()) {
+        Err(Error::Import {
+            path,
+            import,
+            imported_by,
+            searched,
+        }) => {
+            assert_eq!(path, Path::new("/app/A.arena"));
+            assert_eq!(import, "Missing");
+            assert_eq!(imported_by, [PathBuf::from("/app/main.arena")]);
+            assert_eq!(
+                searched,
+                [
+                    PathBuf::from("/app/Missing.arena"),
+                    PathBuf::from("/lib/Missing.arena")
+                ]
+            );
+        }
+        _ => panic!("Expected a missing import"),
+    }
+}
@@ -0,0 +1,90 @@
+// Type errors carry the function they are found in, which the language
+// server shows them at
+
+use arena::sources::InMemory;
+use arena::{Error, FunctionLocation};
+
+fn type_error(code: &str) -> (String, FunctionLocation) {
+    let mut files = InMemory::new();
+    files.add("/app/main.arena", code);
+    let (codes, main_path) = arena::load(&files, "/app/main.arena".as_ref()).unwrap();
+    let asts = arena::parse(&codes).unwrap();
+    let (modules, main_module_id) = arena::resolve_all_imports(&files, asts, &main_path).unwrap();
+    match arena::type_check(&modules, &main_module_id) {
+        Err(Error::Type {
+            message,
+            function: Some(function),
+        }) => (message, function),
+        Err(error) => panic!("Expected a type error in a function, got {}", error),
+        Ok(_) => panic!("Expected a type error"),
+    }
+}
+
+// The byte offset of the name of the function
+fn location(code: &str, name: &str) -> usize {
+    code.find(&format!("fn {}", name)).unwrap() + 3
+}
+
+#[test]
+fn inferred_functions() {
+    let code = "fn sq(x) = x * x\n\nfn main() =\n    print(sq(2));\n    print(sq(true))\n";
+    let (message, function) = type_error(code);
+    assert_eq!(
+        message,
+        "Function sq was inferred as sq(i32) at its first use in function main, \
+        which conflicts with the call sq(bool)"
+    );
+    assert_eq!(function.name, "main");
+    assert_eq!(function.location, location(code, "main"));
+
+    let code = "fn id(x) = x\n\nfn first() -> i32 = id(1)\n\nfn main() =\n    print(first());\n    print(id(true))\n";
+    let (message, function) = type_error(code);
+    assert_eq!(
+        message,
+        "Function id was inferred as id(i32) at its first use in function first, \
+        which conflicts with the call id(bool)"
+    );
+    assert_eq!(function.name, "main");
+
+    let code = "fn main() =\n    let empty = [];\n    print(1)\n";
+    let (_, function) = type_error(code);
+    assert_eq!(function.name, "main");
+    assert_eq!(function.location, location(code, "main"));
+}
+
+#[test]
+fn annotated_functions() {
+    let code = "fn main() = print(half(1))\n\nfn half(x: i32) -> i32 = x + \"a\"\n";
+    let (message, function) = type_error(code);
+    assert_eq!(
+        message,
+        "Could not find function add with signature [\"i32\", \"str\"]"
+    );
+    assert_eq!(function.name, "half");
+    assert_eq!(function.location, location(code, "half"));
+}
+
+#[test]
+fn visibility() {
+    let mut files = InMemory::new();
+    files
+        .add(
+            "/app/main.arena",
+            "import Secret\n\nfn main() = print(Secret::hidden())\n",
+        )
+        .add("/app/Secret.arena", "fn hidden() -> i32 = 1\n");
+    let (codes, main_path) = arena::load(&files, "/app/main.arena".as_ref()).unwrap();
+    let asts = arena::parse(&codes).unwrap();
+    let (modules, main_module_id) = arena::resolve_all_imports(&files, asts, &main_path).unwrap();
+    match arena::type_check(&modules, &main_module_id) {
+        Err(Error::Type {
+            function: Some(function),
+            ..
+        }) => {
+            assert_eq!(function.module_id, main_module_id);
+            assert_eq!(function.name, "main");
+            assert_eq!(function.location, 18);
+        }
+        _ => panic!("Expected a visibility error in main"),
+    }
+}
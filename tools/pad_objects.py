@@ -0,0 +1,48 @@
+#!/usr/bin/env python3
+
+# Copies the folder of an objects benchmark with the given number of padding
+# bytes in every object and prints the path of the copied program, e.g.
+# ./tools/store_comparison_metrics.py $(./tools/pad_objects.py benchmarks/nqueens_objects/NQueenProblem.arena 16)
+# The padding fields of the unused EMPTY cases are commented out, each of them
+# adds 4 bytes once uncommented.
+
+import os
+import re
+import sys
+import tempfile
+from pathlib import Path
+
+if len(sys.argv) != 3:
+    print("Please provide the program and the number of padding bytes")
+    exit(1)
+
+program = Path(sys.argv[1])
+padding = int(sys.argv[2])
+if padding % 4 != 0:
+    print("The padding has to be a multiple of 4 bytes")
+    exit(1)
+fields = padding // 4
+
+padded_folder = Path(tempfile.gettempdir()).joinpath(
+    'arena-padded', program.parent.name + '_' + str(padding))
+os.makedirs(padded_folder, exist_ok=True)
+
+# Uncomments the last fields of a commented out block of padding fields
+def uncomment(match):
+    lines = match.group(2).splitlines(keepends=True)
+    if fields > len(lines):
+        print("A padding of " + str(padding) + " bytes needs more padding fields")
+        exit(1)
+    if fields == 0:
+        return match.group(0)
+    commented = lines[:len(lines) - fields]
+    if not commented:
+        return ''.join(lines)
+    return match.group(1) + '/*\n' + ''.join(commented) + match.group(1) + '*/\n' + ''.join(lines[len(commented):])
+
+for source in program.parent.glob('*.arena'):
+    code = source.read_text()
+    code = re.sub(r'([ \t]*)/\*\n((?:[ \t]*i32,?\n)+)[ \t]*\*/\n', uncomment, code)
+    padded_folder.joinpath(source.name).write_text(code)
+
+print(padded_folder.joinpath(program.name))
@@ -10,76 +10,76 @@ commands = [
     (1, 'rm -f benchmarks/nqueens_stack/results.csv'),
     (1, 'cp ./tools/results_template.csv benchmarks/nqueens_stack/results.csv'),
     (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_stack/NQueenProblem.arena >> benchmarks/nqueens_stack/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_stack/NQueenProblem_10.arena >> benchmarks/nqueens_stack/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_stack/NQueenProblem_100.arena >> benchmarks/nqueens_stack/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_stack/NQueenProblem_500.arena >> benchmarks/nqueens_stack/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_stack/NQueenProblem_1000.arena >> benchmarks/nqueens_stack/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_stack/NQueenProblem_5000.arena >> benchmarks/nqueens_stack/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_stack/NQueenProblem_10000.arena >> benchmarks/nqueens_stack/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_stack/NQueenProblem_50000.arena >> benchmarks/nqueens_stack/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_stack/NQueenProblem_100000.arena >> benchmarks/nqueens_stack/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_stack/NQueenProblem.arena 10 >> benchmarks/nqueens_stack/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_stack/NQueenProblem.arena 100 >> benchmarks/nqueens_stack/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_stack/NQueenProblem.arena 500 >> benchmarks/nqueens_stack/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_stack/NQueenProblem.arena 1000 >> benchmarks/nqueens_stack/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_stack/NQueenProblem.arena 5000 >> benchmarks/nqueens_stack/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_stack/NQueenProblem.arena 10000 >> benchmarks/nqueens_stack/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_stack/NQueenProblem.arena 50000 >> benchmarks/nqueens_stack/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_stack/NQueenProblem.arena 100000 >> benchmarks/nqueens_stack/results.csv'),
     (1, './tools/visualize_comparison_results.py benchmarks/nqueens_stack/results.csv'),
 
     (1, 'rm -f benchmarks/nqueens_objects/results.csv'),
     (1, 'cp ./tools/results_template.csv benchmarks/nqueens_objects/results.csv'),
     (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_objects/NQueenProblem.arena >> benchmarks/nqueens_objects/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_objects/NQueenProblem_8.arena >> benchmarks/nqueens_objects/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_objects/NQueenProblem_16.arena >> benchmarks/nqueens_objects/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_objects/NQueenProblem_24.arena >> benchmarks/nqueens_objects/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_objects/NQueenProblem_32.arena >> benchmarks/nqueens_objects/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_objects/NQueenProblem_40.arena >> benchmarks/nqueens_objects/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py $(./tools/pad_objects.py benchmarks/nqueens_objects/NQueenProblem.arena 8) >> benchmarks/nqueens_objects/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py $(./tools/pad_objects.py benchmarks/nqueens_objects/NQueenProblem.arena 16) >> benchmarks/nqueens_objects/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py $(./tools/pad_objects.py benchmarks/nqueens_objects/NQueenProblem.arena 24) >> benchmarks/nqueens_objects/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py $(./tools/pad_objects.py benchmarks/nqueens_objects/NQueenProblem.arena 32) >> benchmarks/nqueens_objects/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py $(./tools/pad_objects.py benchmarks/nqueens_objects/NQueenProblem.arena 40) >> benchmarks/nqueens_objects/results.csv'),
     (1, './tools/visualize_comparison_results.py benchmarks/nqueens_objects/results.csv'),
 
     (1, 'rm -f benchmarks/nqueens_heap/results.csv'),
     (1, 'cp ./tools/results_template.csv benchmarks/nqueens_heap/results.csv'),
     (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_heap/NQueenProblem.arena >> benchmarks/nqueens_heap/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_heap/NQueenProblem_100.arena >> benchmarks/nqueens_heap/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_heap/NQueenProblem_200.arena >> benchmarks/nqueens_heap/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_heap/NQueenProblem_300.arena >> benchmarks/nqueens_heap/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_heap/NQueenProblem_400.arena >> benchmarks/nqueens_heap/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_heap/NQueenProblem_500.arena >> benchmarks/nqueens_heap/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_heap/NQueenProblem_600.arena >> benchmarks/nqueens_heap/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_heap/NQueenProblem_700.arena >> benchmarks/nqueens_heap/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_heap/NQueenProblem_800.arena >> benchmarks/nqueens_heap/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_heap/NQueenProblem_900.arena >> benchmarks/nqueens_heap/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_heap/NQueenProblem_1000.arena >> benchmarks/nqueens_heap/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_heap/NQueenProblem_1100.arena >> benchmarks/nqueens_heap/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_heap/NQueenProblem.arena 100 >> benchmarks/nqueens_heap/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_heap/NQueenProblem.arena 200 >> benchmarks/nqueens_heap/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_heap/NQueenProblem.arena 300 >> benchmarks/nqueens_heap/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_heap/NQueenProblem.arena 400 >> benchmarks/nqueens_heap/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_heap/NQueenProblem.arena 500 >> benchmarks/nqueens_heap/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_heap/NQueenProblem.arena 600 >> benchmarks/nqueens_heap/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_heap/NQueenProblem.arena 700 >> benchmarks/nqueens_heap/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_heap/NQueenProblem.arena 800 >> benchmarks/nqueens_heap/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_heap/NQueenProblem.arena 900 >> benchmarks/nqueens_heap/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_heap/NQueenProblem.arena 1000 >> benchmarks/nqueens_heap/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/nqueens_heap/NQueenProblem.arena 1100 >> benchmarks/nqueens_heap/results.csv'),
     (1, './tools/visualize_comparison_results.py benchmarks/nqueens_heap/results.csv'),
 
     (1, 'rm -f benchmarks/sorting_stack/results.csv'),
     (1, 'cp ./tools/results_template.csv benchmarks/sorting_stack/results.csv'),
     (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_stack/Sorting.arena >> benchmarks/sorting_stack/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_stack/Sorting_10.arena >> benchmarks/sorting_stack/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_stack/Sorting_100.arena >> benchmarks/sorting_stack/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_stack/Sorting_500.arena >> benchmarks/sorting_stack/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_stack/Sorting_1000.arena >> benchmarks/sorting_stack/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_stack/Sorting_5000.arena >> benchmarks/sorting_stack/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_stack/Sorting_10000.arena >> benchmarks/sorting_stack/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_stack/Sorting_50000.arena >> benchmarks/sorting_stack/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_stack/Sorting_100000.arena >> benchmarks/sorting_stack/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_stack/Sorting.arena 10 >> benchmarks/sorting_stack/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_stack/Sorting.arena 100 >> benchmarks/sorting_stack/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_stack/Sorting.arena 500 >> benchmarks/sorting_stack/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_stack/Sorting.arena 1000 >> benchmarks/sorting_stack/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_stack/Sorting.arena 5000 >> benchmarks/sorting_stack/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_stack/Sorting.arena 10000 >> benchmarks/sorting_stack/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_stack/Sorting.arena 50000 >> benchmarks/sorting_stack/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_stack/Sorting.arena 100000 >> benchmarks/sorting_stack/results.csv'),
     (1, './tools/visualize_comparison_results.py benchmarks/sorting_stack/results.csv'),
 
     (1, 'rm -f benchmarks/sorting_objects/results.csv'),
     (1, 'cp ./tools/results_template.csv benchmarks/sorting_objects/results.csv'),
     (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_objects/Sorting.arena >> benchmarks/sorting_objects/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_objects/Sorting_8.arena >> benchmarks/sorting_objects/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_objects/Sorting_16.arena >> benchmarks/sorting_objects/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_objects/Sorting_24.arena >> benchmarks/sorting_objects/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_objects/Sorting_32.arena >> benchmarks/sorting_objects/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_objects/Sorting_40.arena >> benchmarks/sorting_objects/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py $(./tools/pad_objects.py benchmarks/sorting_objects/Sorting.arena 8) >> benchmarks/sorting_objects/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py $(./tools/pad_objects.py benchmarks/sorting_objects/Sorting.arena 16) >> benchmarks/sorting_objects/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py $(./tools/pad_objects.py benchmarks/sorting_objects/Sorting.arena 24) >> benchmarks/sorting_objects/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py $(./tools/pad_objects.py benchmarks/sorting_objects/Sorting.arena 32) >> benchmarks/sorting_objects/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py $(./tools/pad_objects.py benchmarks/sorting_objects/Sorting.arena 40) >> benchmarks/sorting_objects/results.csv'),
     (1, './tools/visualize_comparison_results.py benchmarks/sorting_objects/results.csv'),
 
     (1, 'rm -f benchmarks/sorting_heap/results.csv'),
     (1, 'cp ./tools/results_template.csv benchmarks/sorting_heap/results.csv'),
     (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_heap/Sorting.arena >> benchmarks/sorting_heap/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_heap/Sorting_100.arena >> benchmarks/sorting_heap/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_heap/Sorting_200.arena >> benchmarks/sorting_heap/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_heap/Sorting_300.arena >> benchmarks/sorting_heap/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_heap/Sorting_400.arena >> benchmarks/sorting_heap/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_heap/Sorting_500.arena >> benchmarks/sorting_heap/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_heap/Sorting_600.arena >> benchmarks/sorting_heap/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_heap/Sorting_700.arena >> benchmarks/sorting_heap/results.csv'),
-    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_heap/Sorting_800.arena >> benchmarks/sorting_heap/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_heap/Sorting.arena 100 >> benchmarks/sorting_heap/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_heap/Sorting.arena 200 >> benchmarks/sorting_heap/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_heap/Sorting.arena 300 >> benchmarks/sorting_heap/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_heap/Sorting.arena 400 >> benchmarks/sorting_heap/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_heap/Sorting.arena 500 >> benchmarks/sorting_heap/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_heap/Sorting.arena 600 >> benchmarks/sorting_heap/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_heap/Sorting.arena 700 >> benchmarks/sorting_heap/results.csv'),
+    (BENCHMARKING_REPETITION, './tools/store_comparison_metrics.py benchmarks/sorting_heap/Sorting.arena 800 >> benchmarks/sorting_heap/results.csv'),
     (1, './tools/visualize_comparison_results.py benchmarks/sorting_heap/results.csv'),
 ]
 
@@ -1,16 +1,18 @@
 #!/usr/bin/env python3
 
 import os
+import shlex
 import sys
 from locale import atof, setlocale, LC_NUMERIC
 
-if len(sys.argv) != 2:
-    print("Please provide exactly one program to benchmark")
+# The arguments after the program are passed to it
+if len(sys.argv) < 2:
+    print("Please provide the program to benchmark and its arguments")
     exit(1)
 
 setlocale(LC_NUMERIC, '')
 
-stream = os.popen('./tools/benchmark.sh ' + sys.argv[1])
+stream = os.popen('./tools/benchmark.sh ' + ' '.join(shlex.quote(arg) for arg in sys.argv[1:]))
 output = stream.read()
 lines = output.split("\n")
 